@@ -0,0 +1,36 @@
+//! Compares typed calls with calls on a reused buffer of raw slots.
+//! Run this with `cargo bench --bench call_raw`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasm3::Environment;
+
+fn call_raw(c: &mut Criterion) {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(&include_bytes!("../examples/wasm/wasm_add/wasm_add.wasm")[..])
+        .expect("Unable to load module");
+    let func = module
+        .find_function::<(i64, i64), i64>("add")
+        .expect("Unable to find function");
+
+    let mut group = c.benchmark_group("add");
+    group.bench_function("call", |b| {
+        b.iter(|| func.call(black_box(1), black_box(6)).unwrap())
+    });
+    group.bench_function("call_raw", |b| {
+        let mut slots = [0, 6];
+        b.iter(|| {
+            slots[0] = black_box(1);
+            // both slots hold i64 arguments
+            unsafe { func.call_raw(&mut slots) }.unwrap();
+            slots[0]
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, call_raw);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Compares regular calls with unchecked calls of a small function.
+//! Run this with `cargo bench --bench call_unchecked`.
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+use wasm3::Environment;
+
+/// (module
+///   (func $fib (export "fib") (param i32) (result i32)
+///     (if (i32.lt_u (local.get 0) (i32.const 2))
+///       (then (return (local.get 0))))
+///     (return
+///       (i32.add
+///         (call $fib (i32.sub (local.get 0) (i32.const 2)))
+///         (call $fib (i32.sub (local.get 0) (i32.const 1)))))))
+const FIB32: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x03, 0x02, 0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x66, 0x69, 0x62, 0x00, 0x00, 0x0a, 0x1f, 0x01,
+    0x1d, 0x00, 0x20, 0x00, 0x41, 0x02, 0x49, 0x04, 0x40, 0x20, 0x00, 0x0f, 0x0b, 0x20, 0x00, 0x41,
+    0x02, 0x6b, 0x10, 0x00, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x10, 0x00, 0x6a, 0x0f, 0x0b,
+];
+
+fn call_unchecked(c: &mut Criterion) {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module");
+    let fib = module
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+
+    let mut group = c.benchmark_group("fib");
+    group.bench_function("call", |b| b.iter(|| fib.call(black_box(7)).unwrap()));
+    group.bench_function("call_unchecked", |b| {
+        // no other call into the runtime is in progress
+        b.iter(|| unsafe { fib.call_unchecked(black_box(7)) }.unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, call_unchecked);
+criterion_main!(benches);
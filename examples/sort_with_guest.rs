@@ -0,0 +1,33 @@
+//! Sorts a vector using a comparison function exported by a wasm module.
+use wasm3::Environment;
+
+/// (module
+///   (func (export "compare") (param i32 i32) (result i32)
+///     (i32.sub
+///       (i32.lt_s (local.get 0) (local.get 1))
+///       (i32.gt_s (local.get 0) (local.get 1)))))
+const COMPARE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+    0x7f, 0x03, 0x02, 0x01, 0x00, 0x07, 0x0b, 0x01, 0x07, 0x63, 0x6f, 0x6d, 0x70, 0x61, 0x72, 0x65,
+    0x00, 0x00, 0x0a, 0x0f, 0x01, 0x0d, 0x00, 0x20, 0x00, 0x20, 0x01, 0x48, 0x20, 0x00, 0x20, 0x01,
+    0x4a, 0x6b, 0x0b,
+];
+
+fn main() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(COMPARE)
+        .expect("Unable to load module");
+    let compare = module
+        .find_function::<(i32, i32), i32>("compare")
+        .expect("Unable to find function");
+    let compare = compare.as_callable();
+
+    let mut numbers = vec![3, 1, 4, 1, 5, 9, 2, 6];
+    numbers.sort_by(|&a, &b| compare((a, b)).unwrap().cmp(&0));
+    assert_eq!(numbers, [9, 6, 5, 4, 3, 2, 1, 1]);
+    println!("Sorted by the guest: {:?}", numbers);
+}
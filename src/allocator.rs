@@ -0,0 +1,118 @@
+//! Custom heap allocator support. With the `custom-allocator` feature wasm3 is compiled to call
+//! the shims defined here instead of the C heap functions.
+use alloc::boxed::Box;
+
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::error::{Error, Result};
+
+/// Allocates `size` bytes, returning a null pointer on failure. See C's `malloc`.
+pub type AllocFn = unsafe extern "C" fn(size: cty::size_t) -> *mut cty::c_void;
+/// Resizes the allocation at `ptr` to `size` bytes, returning a null pointer on failure.
+/// See C's `realloc`.
+pub type ReallocFn =
+    unsafe extern "C" fn(ptr: *mut cty::c_void, size: cty::size_t) -> *mut cty::c_void;
+/// Frees the allocation at `ptr`, which may be null. See C's `free`.
+pub type FreeFn = unsafe extern "C" fn(ptr: *mut cty::c_void);
+
+extern "C" {
+    fn malloc(size: cty::size_t) -> *mut cty::c_void;
+    fn realloc(ptr: *mut cty::c_void, size: cty::size_t) -> *mut cty::c_void;
+    fn free(ptr: *mut cty::c_void);
+}
+
+/// The set of functions wasm3 allocates with, which can not be changed once installed.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Allocator {
+    alloc: AllocFn,
+    realloc: ReallocFn,
+    free: FreeFn,
+}
+
+static LIBC_ALLOCATOR: Allocator = Allocator {
+    alloc: malloc,
+    realloc,
+    free,
+};
+
+// null until either an allocator gets installed or wasm3 allocates for the first time
+static INSTALLED: AtomicPtr<Allocator> = AtomicPtr::new(ptr::null_mut());
+
+impl Allocator {
+    pub(crate) fn new(alloc: AllocFn, realloc: ReallocFn, free: FreeFn) -> Self {
+        Allocator {
+            alloc,
+            realloc,
+            free,
+        }
+    }
+
+    /// Installs this allocator for all of wasm3.
+    ///
+    /// This fails if wasm3 already uses a different allocator, as memory allocated by one
+    /// allocator must not be freed by another.
+    pub(crate) fn install(self) -> Result<()> {
+        let allocator = Box::into_raw(Box::new(self));
+        match INSTALLED.compare_exchange(
+            ptr::null_mut(),
+            allocator,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => Ok(()),
+            Err(installed) => {
+                drop(unsafe { Box::from_raw(allocator) });
+                if unsafe { (*installed).is_same(&self) } {
+                    Ok(())
+                } else {
+                    Err(Error::AllocatorMismatch)
+                }
+            }
+        }
+    }
+
+    fn is_same(&self, other: &Allocator) -> bool {
+        self.alloc as usize == other.alloc as usize
+            && self.realloc as usize == other.realloc as usize
+            && self.free as usize == other.free as usize
+    }
+
+    /// Returns the installed allocator, falling back to C's heap functions if none has been
+    /// installed before the first allocation.
+    fn current() -> &'static Allocator {
+        let libc = &LIBC_ALLOCATOR as *const Allocator as *mut Allocator;
+        let installed = INSTALLED
+            .compare_exchange(ptr::null_mut(), libc, Ordering::AcqRel, Ordering::Acquire)
+            .unwrap_or_else(|installed| installed);
+        unsafe { &*installed }
+    }
+}
+
+#[no_mangle]
+unsafe extern "C" fn m3rs_malloc(size: cty::size_t) -> *mut cty::c_void {
+    (Allocator::current().alloc)(size)
+}
+
+#[no_mangle]
+unsafe extern "C" fn m3rs_calloc(num: cty::size_t, size: cty::size_t) -> *mut cty::c_void {
+    let len = match num.checked_mul(size) {
+        Some(len) => len,
+        None => return ptr::null_mut(),
+    };
+    let ptr = (Allocator::current().alloc)(len);
+    if !ptr.is_null() {
+        ptr::write_bytes(ptr.cast::<u8>(), 0, len);
+    }
+    ptr
+}
+
+#[no_mangle]
+unsafe extern "C" fn m3rs_realloc(ptr: *mut cty::c_void, size: cty::size_t) -> *mut cty::c_void {
+    (Allocator::current().realloc)(ptr, size)
+}
+
+#[no_mangle]
+unsafe extern "C" fn m3rs_free(ptr: *mut cty::c_void) {
+    (Allocator::current().free)(ptr)
+}
@@ -17,15 +17,38 @@ impl Drop for DropEnvironment {
 }
 
 /// An environment is required to construct [`Runtime`]s from.
+///
+/// An environment is neither `Send` nor `Sync`. wasm3 keeps mutable state in it that is shared by
+/// everything created from it without any synchronization: parsing a module registers its
+/// function types in the environment, and runtimes take code pages from and return them to a
+/// pool owned by the environment whenever they compile functions or get dropped. Modules and
+/// runtimes of one environment therefore have to stay on the thread the environment was created
+/// on.
+///
+/// ```compile_fail
+/// fn assert_send<T: Send>() {}
+/// assert_send::<wasm3::Environment>();
+/// ```
+///
+/// ```compile_fail
+/// fn assert_sync<T: Sync>() {}
+/// assert_sync::<wasm3::Environment>();
+/// ```
 #[derive(Debug, Clone)]
 pub struct Environment(Rc<DropEnvironment>);
 
 impl Environment {
     /// Creates a new environment.
     ///
+    /// wasm3 allocates all of its memory, including the code pages functions are compiled into,
+    /// with C's `malloc`, `realloc` and `free`, unless a different allocator has been installed
+    /// through an [`EnvironmentBuilder`].
+    ///
     /// # Errors
     ///
     /// This function will error on memory allocation failure.
+    ///
+    /// [`EnvironmentBuilder`]: struct.EnvironmentBuilder.html
     #[inline]
     pub fn new() -> Result<Self> {
         unsafe { NonNull::new(ffi::m3_NewEnvironment()) }
@@ -33,6 +56,40 @@ impl Environment {
             .map(|raw| Environment(Rc::new(DropEnvironment(raw))))
     }
 
+    /// Returns a builder for configuring a new environment.
+    #[inline]
+    pub fn builder() -> EnvironmentBuilder {
+        EnvironmentBuilder::new()
+    }
+
+    /// Creates a new environment, making wasm3 allocate all of its memory with the given
+    /// functions from now on.
+    ///
+    /// The allocator is global to the process, as wasm3 allocates through the same functions
+    /// regardless of environment. It therefore has to be installed before anything else uses
+    /// wasm3, after which it can not be changed anymore. Creating further environments with the
+    /// same functions is fine.
+    ///
+    /// # Errors
+    ///
+    /// This function will error in the following situations:
+    ///
+    /// * wasm3 already allocates with different functions
+    /// * a memory allocation failed
+    ///
+    /// # Safety
+    ///
+    /// The functions have to behave like C's `malloc`, `realloc` and `free` respectively and be
+    /// callable from any thread for the rest of the program's lifetime.
+    #[cfg(feature = "custom-allocator")]
+    pub unsafe fn new_with_allocator(
+        alloc: crate::AllocFn,
+        realloc: crate::ReallocFn,
+        free: crate::FreeFn,
+    ) -> Result<Self> {
+        Self::builder().allocator(alloc, realloc, free).build()
+    }
+
     /// Creates a new runtime with the given stack size in slots.
     ///
     /// # Errors
@@ -43,18 +100,99 @@ impl Environment {
         Runtime::new(self, stack_size)
     }
 
+    /// Creates a new runtime with the given stack size in slots whose linear memory can not grow
+    /// beyond `max_memory_pages` 64KiB pages.
+    /// See [`Runtime::new_with_memory_limit`] for details.
+    ///
+    /// # Errors
+    ///
+    /// This function will error on memory allocation failure.
+    ///
+    /// [`Runtime::new_with_memory_limit`]: struct.Runtime.html#method.new_with_memory_limit
+    #[inline]
+    pub fn create_runtime_with_memory_limit(
+        &self,
+        stack_size: u32,
+        max_memory_pages: u32,
+    ) -> Result<Runtime> {
+        Runtime::new_with_memory_limit(self, stack_size, max_memory_pages)
+    }
+
     /// Parses a wasm module from raw bytes.
     #[inline]
     pub fn parse_module<TData: Into<Box<[u8]>>>(&self, bytes: TData) -> Result<ParsedModule> {
         ParsedModule::parse(self, bytes)
     }
 
+    /// Whether this is the only handle to the environment.
+    pub(crate) fn is_unique(&self) -> bool {
+        Rc::strong_count(&self.0) == 1 && Rc::weak_count(&self.0) == 0
+    }
+
     #[inline]
     pub(crate) fn as_ptr(&self) -> ffi::IM3Environment {
         (self.0).0.as_ptr()
     }
 }
 
+/// A builder for an [`Environment`].
+///
+/// The size of the code pages wasm3 compiles functions into is fixed when wasm3 is built and can
+/// therefore not be configured here.
+///
+/// [`Environment`]: struct.Environment.html
+#[derive(Debug, Default)]
+pub struct EnvironmentBuilder {
+    #[cfg(feature = "custom-allocator")]
+    allocator: Option<crate::allocator::Allocator>,
+}
+
+impl EnvironmentBuilder {
+    /// Creates a builder for an environment using the default configuration.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes wasm3 allocate all of its memory with the given functions once the environment is
+    /// built. See [`Environment::new_with_allocator`] for details.
+    ///
+    /// # Safety
+    ///
+    /// The functions have to behave like C's `malloc`, `realloc` and `free` respectively and be
+    /// callable from any thread for the rest of the program's lifetime.
+    ///
+    /// [`Environment::new_with_allocator`]: struct.Environment.html#method.new_with_allocator
+    #[cfg(feature = "custom-allocator")]
+    pub unsafe fn allocator(
+        mut self,
+        alloc: crate::AllocFn,
+        realloc: crate::ReallocFn,
+        free: crate::FreeFn,
+    ) -> Self {
+        self.allocator = Some(crate::allocator::Allocator::new(alloc, realloc, free));
+        self
+    }
+
+    /// Creates the environment.
+    ///
+    /// # Errors
+    ///
+    /// This function will error in the following situations:
+    ///
+    /// * a custom allocator was configured but wasm3 already allocates with different functions
+    /// * a memory allocation failed
+    pub fn build(self) -> Result<Environment> {
+        #[cfg(feature = "custom-allocator")]
+        {
+            if let Some(allocator) = self.allocator {
+                allocator.install()?;
+            }
+        }
+        Environment::new()
+    }
+}
+
 impl core::cmp::Eq for Environment {}
 impl core::cmp::PartialEq for Environment {
     fn eq(&self, &Environment(ref other): &Environment) -> bool {
@@ -66,3 +204,8 @@ impl core::cmp::PartialEq for Environment {
 fn create_and_drop_env() {
     assert!(Environment::new().is_ok());
 }
+
+#[test]
+fn build_env() {
+    assert!(Environment::builder().build().is_ok());
+}
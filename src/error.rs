@@ -1,8 +1,12 @@
 //! Error related functionality of wasm3.
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use core::cmp;
 use core::fmt;
 
 use crate::utils::cstr_to_str;
+use crate::{SignatureStr, ValueType};
 
 /// Result alias that uses [`Error`].
 pub type Result<T> = core::result::Result<T, Error>;
@@ -73,6 +77,11 @@ impl fmt::Display for Trap {
 pub struct Wasm3Error(*const cty::c_char);
 
 impl Wasm3Error {
+    /// Returns the message wasm3 describes this error with.
+    pub fn message(self) -> &'static str {
+        unsafe { cstr_to_str(self.0) }
+    }
+
     /// Check whether this error is the specified trap.
     pub fn is_trap(self, trap: Trap) -> bool {
         trap.as_ptr() == self.0
@@ -89,42 +98,304 @@ impl cmp::PartialEq<Trap> for Wasm3Error {
 impl std::error::Error for Wasm3Error {}
 impl fmt::Debug for Wasm3Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(unsafe { cstr_to_str(self.0) }, f)
+        fmt::Debug::fmt(self.message(), f)
     }
 }
 impl fmt::Display for Wasm3Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(unsafe { cstr_to_str(self.0) }, f)
+        fmt::Display::fmt(self.message(), f)
     }
 }
 
+/// Detailed information wasm3 recorded about the last error of a runtime, see
+/// [`Runtime::take_error_info`].
+///
+/// [`Runtime::take_error_info`]: ../struct.Runtime.html#method.take_error_info
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorInfo {
+    /// The error this information was recorded for.
+    pub error: Wasm3Error,
+    /// The message wasm3 formatted for the error, this may be empty.
+    pub message: String,
+    /// The wasm3 source file the error was raised in.
+    pub file: &'static str,
+    /// The line of `file` the error was raised at.
+    pub line: u32,
+    /// The name of the module the error occurred in, if known.
+    pub module_name: Option<String>,
+    /// The name of the function the error occurred in, if known.
+    pub function_name: Option<String>,
+}
+
+impl ErrorInfo {
+    /// Copies the error information out of `info`, `None` if it does not describe an error.
+    ///
+    /// # Safety
+    ///
+    /// The pointers in `info` have to be either null or valid.
+    pub(crate) unsafe fn from_raw(info: &ffi::M3ErrorInfo) -> Option<Self> {
+        unsafe fn opt_string(ptr: *const cty::c_char) -> Option<String> {
+            if ptr.is_null() {
+                None
+            } else {
+                Some(cstr_to_str(ptr).into())
+            }
+        }
+        if info.result.is_null() {
+            return None;
+        }
+        Some(ErrorInfo {
+            error: Wasm3Error(info.result),
+            message: opt_string(info.message).unwrap_or_default(),
+            file: if info.file.is_null() {
+                ""
+            } else {
+                cstr_to_str(info.file)
+            },
+            line: info.line,
+            module_name: info
+                .module
+                .as_ref()
+                .and_then(|module| opt_string(module.name)),
+            function_name: info
+                .function
+                .as_ref()
+                .and_then(|func| opt_string(func.name)),
+        })
+    }
+}
+
+/// The trap message returned by host functions linked via [`Module::link_closure_fallible`] when
+/// they fail. Its address identifies the trap, the actual error is stored in the runtime.
+///
+/// [`Module::link_closure_fallible`]: ../struct.Module.html#method.link_closure_fallible
+pub(crate) static HOST_FUNCTION_FAILED: [u8; 21] = *b"host function failed\0";
+
 /// Error returned by wasm3-rs.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Error {
     /// An error originating from wasm3 itself may or may not be a trap.
     Wasm3(Wasm3Error),
-    /// A function has been found but its signature didn't match.
+    /// A function had a signature that didn't match or contained a type that is not supported.
     InvalidFunctionSignature,
+    /// A function has been found but its signature didn't match the requested one.
+    SignatureMismatch {
+        /// The signature of the function.
+        expected: SignatureStr,
+        /// The signature derived from the requested argument and return types.
+        requested: SignatureStr,
+        /// The index of the first argument whose type differs, `None` if the argument types only
+        /// differ in their number or the return types differ.
+        first_mismatch: Option<usize>,
+    },
     /// The specified function could not be found.
     FunctionNotFound,
+    /// More than one loaded module has a function by the specified name, see
+    /// [`Runtime::find_function`].
+    ///
+    /// [`Runtime::find_function`]: ../struct.Runtime.html#method.find_function
+    AmbiguousFunction {
+        /// The names of the modules that have a function by the specified name.
+        modules: Vec<String>,
+    },
     /// The specified module could not be found.
     ModuleNotFound,
+    /// The specified global could not be found.
+    GlobalNotFound,
+    /// An immutable global was attempted to be set.
+    GlobalNotMutable,
+    /// A global has been set to a value whose type didn't match the global's type.
+    InvalidGlobalType,
     /// The modules environment did not match the runtime's environment.
     ModuleLoadEnvMismatch,
+    /// An access to linear memory was out of bounds.
+    OutOfBoundsMemoryAccess {
+        /// The offset of the access.
+        offset: usize,
+        /// The length of the access in bytes.
+        len: usize,
+    },
+    /// A string read from linear memory was not valid UTF-8.
+    InvalidUtf8(core::str::Utf8Error),
+    /// wasm3 already allocates its memory with a different allocator, see
+    /// [`Environment::new_with_allocator`].
+    ///
+    /// [`Environment::new_with_allocator`]: ../struct.Environment.html#method.new_with_allocator
+    AllocatorMismatch,
+    /// The wasm stack of the runtime overflowed, usually due to deep recursion. This is reported
+    /// instead of [`Trap::StackOverflow`] so that it can be handled specifically, for example by
+    /// retrying with a runtime that has a larger stack. A linked closure trapping with
+    /// [`Trap::StackOverflow`] is reported the same way.
+    ///
+    /// [`Error::is_trap`] still recognizes this error as [`Trap::StackOverflow`].
+    ///
+    /// [`Trap::StackOverflow`]: enum.Trap.html#variant.StackOverflow
+    /// [`Error::is_trap`]: enum.Error.html#method.is_trap
+    StackOverflow,
+    /// The linear memory could not be grown as it would have exceeded its maximum size, see
+    /// [`Runtime::grow_memory`].
+    ///
+    /// [`Runtime::grow_memory`]: ../struct.Runtime.html#method.grow_memory
+    MemoryGrowFailed,
+    /// The runtime ran out of fuel while executing, see [`Runtime::set_fuel`].
+    ///
+    /// [`Runtime::set_fuel`]: ../struct.Runtime.html#method.set_fuel
+    OutOfFuel,
+    /// The call exceeded the number of operations it was allowed to execute, see
+    /// [`Function::call_with_timeout`].
+    ///
+    /// [`Function::call_with_timeout`]: ../struct.Function.html#method.call_with_timeout
+    Timeout,
+    /// A host function linked via [`Module::link_closure_fallible`] failed with the given message.
+    ///
+    /// [`Module::link_closure_fallible`]: ../struct.Module.html#method.link_closure_fallible
+    HostFunction(String),
+    /// The guest failed to allocate a buffer.
+    GuestAllocationFailed {
+        /// The requested size of the buffer in bytes.
+        len: usize,
+    },
+    /// A module name contained a nul byte.
+    InvalidModuleName,
+    /// A module was larger than the 4GiB wasm3 is able to parse.
+    ModuleTooLarge,
+    /// Reading a module failed with the given kind of I/O error.
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind),
+    /// A function was dynamically called with arguments that did not match its signature.
+    ArgumentTypeMismatch {
+        /// The argument types of the function.
+        expected: Vec<ValueType>,
+        /// The types of the arguments the function was called with.
+        provided: Vec<ValueType>,
+    },
+    /// A function was called with a different number of arguments or raw slots than it requires,
+    /// see [`DynFunction::call_checked`], [`DynFunction::call_argv`] and [`Function::call_raw`].
+    ///
+    /// [`DynFunction::call_checked`]: ../struct.DynFunction.html#method.call_checked
+    /// [`DynFunction::call_argv`]: ../struct.DynFunction.html#method.call_argv
+    /// [`Function::call_raw`]: ../struct.Function.html#method.call_raw
+    ArgumentCountMismatch {
+        /// The number of arguments the function takes.
+        expected: usize,
+        /// The number of arguments the function was called with.
+        provided: usize,
+    },
+    /// A string argument could not be parsed as a value of the type the function expects, see
+    /// [`DynFunction::call_argv`].
+    ///
+    /// [`DynFunction::call_argv`]: ../struct.DynFunction.html#method.call_argv
+    InvalidArgument {
+        /// The index of the offending argument.
+        index: usize,
+        /// The type the function expects the argument to have.
+        expected: ValueType,
+    },
+    /// A module imports a function another module does not export, see
+    /// [`Module::link_module`].
+    ///
+    /// [`Module::link_module`]: ../struct.Module.html#method.link_module
+    UnresolvedImport {
+        /// The name of the module the function is imported from.
+        module_name: String,
+        /// The name of the imported function.
+        function_name: String,
+    },
+    /// A module could not be unloaded as another module links to its functions, see
+    /// [`Module::unload`].
+    ///
+    /// [`Module::unload`]: ../struct.Module.html#method.unload
+    ModuleInUse {
+        /// The name of the module that was to be unloaded.
+        module_name: String,
+        /// The name of the module linking to its functions.
+        dependent: String,
+    },
 }
 
 impl Error {
     pub(crate) fn from_ffi_res(ptr: ffi::M3Result) -> Result<()> {
+        #[cfg(feature = "metering")]
+        if ptr == crate::metering::OUT_OF_FUEL.as_ptr().cast() {
+            return Err(Error::OutOfFuel);
+        }
         if ptr.is_null() {
             Ok(())
+        } else if ptr == unsafe { ffi::m3Err_trapStackOverflow } {
+            Err(Error::StackOverflow)
         } else {
             Err(Error::Wasm3(Wasm3Error(ptr)))
         }
     }
 
+    /// Checks whether this error is the specified trap, which includes [`Error::StackOverflow`]
+    /// for [`Trap::StackOverflow`].
+    ///
+    /// [`Error::StackOverflow`]: enum.Error.html#variant.StackOverflow
+    /// [`Trap::StackOverflow`]: enum.Trap.html#variant.StackOverflow
+    pub fn is_trap(&self, trap: Trap) -> bool {
+        match self {
+            Error::Wasm3(err) => err.is_trap(trap),
+            Error::StackOverflow => trap == Trap::StackOverflow,
+            _ => false,
+        }
+    }
+
+    /// Returns a static description of this error.
+    ///
+    /// Unlike the [`Display`] implementation this does not include any details carried by the
+    /// error, but it does not require formatting machinery either.
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/core/fmt/trait.Display.html
+    pub fn message(&self) -> &'static str {
+        match self {
+            Error::Wasm3(err) => err.message(),
+            Error::InvalidFunctionSignature => "the found function had an unexpected signature",
+            Error::SignatureMismatch { .. } => "the found function had an unexpected signature",
+            Error::FunctionNotFound => "the function could not be found",
+            Error::AmbiguousFunction { .. } => "more than one module has a function by that name",
+            Error::ModuleNotFound => "the module could not be found",
+            Error::GlobalNotFound => "the global could not be found",
+            Error::GlobalNotMutable => "the global is not mutable",
+            Error::InvalidGlobalType => "the value's type did not match the global's type",
+            Error::ModuleLoadEnvMismatch => "the module and runtime environments were not the same",
+            Error::OutOfBoundsMemoryAccess { .. } => "the memory access was out of bounds",
+            Error::InvalidUtf8(_) => "the string was not valid utf-8",
+            Error::AllocatorMismatch => "wasm3 already allocates with a different allocator",
+            Error::StackOverflow => "the wasm stack overflowed",
+            Error::MemoryGrowFailed => "the memory could not be grown past its maximum size",
+            Error::OutOfFuel => "the runtime ran out of fuel",
+            Error::Timeout => "the call exceeded its operation limit",
+            Error::HostFunction(_) => "a host function failed",
+            Error::GuestAllocationFailed { .. } => "the guest failed to allocate a buffer",
+            Error::InvalidModuleName => "the module name contained a nul byte",
+            Error::ModuleTooLarge => "the module was too large to be parsed",
+            #[cfg(feature = "std")]
+            Error::Io(_) => "reading the module failed",
+            Error::ArgumentTypeMismatch { .. } => {
+                "the argument types did not match the function's signature"
+            }
+            Error::ArgumentCountMismatch { .. } => {
+                "the number of arguments did not match the function's signature"
+            }
+            Error::InvalidArgument { .. } => "an argument could not be parsed",
+            Error::UnresolvedImport { .. } => "an imported function could not be resolved",
+            Error::ModuleInUse { .. } => "the module is still in use by another module",
+        }
+    }
+
     pub(crate) fn malloc_error() -> Self {
         Error::Wasm3(Wasm3Error(unsafe { ffi::m3Err_mallocFailed }))
     }
+
+    pub(crate) fn memory_overflow_error() -> Self {
+        Error::Wasm3(Wasm3Error(unsafe { ffi::m3Err_wasmMemoryOverflow }))
+    }
+
+    #[cfg(feature = "metering")]
+    pub(crate) fn malformed_error() -> Self {
+        Error::Wasm3(Wasm3Error(unsafe { ffi::m3Err_wasmMalformed }))
+    }
 }
 
 #[cfg(feature = "std")]
@@ -132,15 +403,173 @@ impl std::error::Error for Error {}
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Error::Wasm3(err) => fmt::Display::fmt(err, f),
-            Error::InvalidFunctionSignature => {
-                write!(f, "the found function had an unexpected signature")
+            Error::OutOfBoundsMemoryAccess { offset, len } => write!(
+                f,
+                "the memory access of {} bytes at offset {} was out of bounds",
+                len, offset
+            ),
+            Error::InvalidUtf8(err) => write!(f, "the string was not valid utf-8: {}", err),
+            Error::GuestAllocationFailed { len } => {
+                write!(f, "the guest failed to allocate {} bytes", len)
             }
-            Error::FunctionNotFound => write!(f, "the function could not be found"),
-            Error::ModuleNotFound => write!(f, "the module could not be found"),
-            Error::ModuleLoadEnvMismatch => {
-                write!(f, "the module and runtime environments were not the same")
+            Error::HostFunction(msg) => write!(f, "a host function failed: {}", msg),
+            Error::SignatureMismatch {
+                expected,
+                requested,
+                first_mismatch,
+            } => {
+                write!(
+                    f,
+                    "the function's signature {} did not match the requested signature {}",
+                    expected.readable(),
+                    requested.readable()
+                )?;
+                match first_mismatch {
+                    Some(idx) => write!(f, ", the types of argument {} differ", idx),
+                    None => Ok(()),
+                }
             }
+            #[cfg(feature = "std")]
+            Error::Io(kind) => write!(f, "reading the module failed: {:?}", kind),
+            Error::ArgumentTypeMismatch { expected, provided } => write!(
+                f,
+                "the function expected arguments of types {:?} but was called with {:?}",
+                expected, provided
+            ),
+            Error::AmbiguousFunction { modules } => write!(
+                f,
+                "more than one module has a function by that name: {}",
+                modules.join(", ")
+            ),
+            Error::ArgumentCountMismatch { expected, provided } => write!(
+                f,
+                "the function expected {} arguments but was called with {}",
+                expected, provided
+            ),
+            Error::InvalidArgument { index, expected } => write!(
+                f,
+                "argument {} could not be parsed as a value of type {:?}",
+                index, expected
+            ),
+            Error::UnresolvedImport {
+                module_name,
+                function_name,
+            } => write!(
+                f,
+                "the imported function {}::{} could not be resolved",
+                module_name, function_name
+            ),
+            Error::ModuleInUse {
+                module_name,
+                dependent,
+            } => write!(
+                f,
+                "the module {} can not be unloaded as {} links to its functions",
+                module_name, dependent
+            ),
+            _ => f.write_str(self.message()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_error_message() {
+        assert_eq!(
+            Error::FunctionNotFound.message(),
+            "the function could not be found"
+        );
+        assert_eq!(
+            Error::OutOfBoundsMemoryAccess { offset: 4, len: 8 }.message(),
+            "the memory access was out of bounds"
+        );
+        assert_eq!(
+            Error::from_ffi_res(Trap::StackOverflow.as_ptr()),
+            Err(Error::StackOverflow)
+        );
+        assert_eq!(
+            Error::from_ffi_res(Trap::Unreachable.as_ptr()),
+            Err(Error::Wasm3(Wasm3Error(Trap::Unreachable.as_ptr())))
+        );
+        let trap = Error::Wasm3(Wasm3Error(Trap::Unreachable.as_ptr()));
+        assert_eq!(trap.message(), Trap::Unreachable.to_string());
+        assert_eq!(trap.to_string(), Trap::Unreachable.to_string());
+    }
+
+    #[test]
+    fn test_error_display_details() {
+        assert_eq!(
+            Error::OutOfBoundsMemoryAccess { offset: 4, len: 8 }.to_string(),
+            "the memory access of 8 bytes at offset 4 was out of bounds"
+        );
+        assert_eq!(
+            Error::GuestAllocationFailed { len: 16 }.to_string(),
+            "the guest failed to allocate 16 bytes"
+        );
+        assert_eq!(
+            Error::ArgumentTypeMismatch {
+                expected: alloc::vec![ValueType::I32, ValueType::I64],
+                provided: alloc::vec![ValueType::I64],
+            }
+            .to_string(),
+            "the function expected arguments of types [I32, I64] but was called with [I64]"
+        );
+        assert_eq!(
+            Error::AmbiguousFunction {
+                modules: alloc::vec!["first".into(), "second".into()],
+            }
+            .to_string(),
+            "more than one module has a function by that name: first, second"
+        );
+        assert_eq!(
+            Error::InvalidArgument {
+                index: 2,
+                expected: ValueType::F32,
+            }
+            .to_string(),
+            "argument 2 could not be parsed as a value of type F32"
+        );
+        let i32_ty = ffi::_bindgen_ty_1::c_m3Type_i32 as u8;
+        let i64_ty = ffi::_bindgen_ty_1::c_m3Type_i64 as u8;
+        assert_eq!(
+            Error::SignatureMismatch {
+                expected: SignatureStr::from_types(i32_ty, &[i32_ty, i64_ty]),
+                requested: SignatureStr::from_types(i32_ty, &[i32_ty, i32_ty]),
+                first_mismatch: Some(1),
+            }
+            .to_string(),
+            "the function's signature i32 (i32, i64) did not match the requested signature \
+             i32 (i32, i32), the types of argument 1 differ"
+        );
+        let none_ty = ffi::_bindgen_ty_1::c_m3Type_none as u8;
+        assert_eq!(
+            Error::SignatureMismatch {
+                expected: SignatureStr::from_types(none_ty, &[]),
+                requested: SignatureStr::from_types(i64_ty, &[]),
+                first_mismatch: None,
+            }
+            .to_string(),
+            "the function's signature () () did not match the requested signature i64 ()"
+        );
+        assert_eq!(
+            Error::UnresolvedImport {
+                module_name: "lib".into(),
+                function_name: "double".into(),
+            }
+            .to_string(),
+            "the imported function lib::double could not be resolved"
+        );
+        assert_eq!(
+            Error::ModuleInUse {
+                module_name: "lib".into(),
+                dependent: "main".into(),
+            }
+            .to_string(),
+            "the module lib can not be unloaded as main links to its functions"
+        );
+    }
+}
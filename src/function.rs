@@ -1,4 +1,8 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 use core::cmp::{Eq, PartialEq};
+use core::fmt;
 use core::hash::{Hash, Hasher};
 use core::marker::PhantomData;
 use core::ptr::{self, NonNull};
@@ -6,10 +10,11 @@ use core::slice;
 use core::str;
 
 use crate::error::{Error, Result};
+use crate::memory::MemoryView;
 use crate::runtime::Runtime;
 use crate::utils::cstr_to_str;
 use crate::wasm3_priv;
-use crate::{WasmArgs, WasmType};
+use crate::{Value, ValueType, WasmArgs, WasmType};
 
 /// Calling Context for a host function.
 pub struct CallContext<'cc> {
@@ -29,30 +34,31 @@ impl<'cc> CallContext<'cc> {
         self.runtime.as_ref().memory.mallocated
     }
 
+    pub(crate) fn as_ptr(&self) -> ffi::IM3Runtime {
+        self.runtime.as_ptr()
+    }
+
     /// Returns the raw memory of the runtime associated with this context.
+    /// If no memory has been instantiated the returned slice is empty.
     ///
     /// # Safety
     ///
     /// The returned pointer may get invalidated when wasm function objects are called due to reallocations.
     pub unsafe fn memory(&self) -> *const [u8] {
-        let mallocated = self.mallocated();
-        let len = (*mallocated).length as usize;
-        let data = if len == 0 {
-            ptr::NonNull::dangling().as_ptr()
-        } else {
-            mallocated.offset(1).cast()
-        };
-        ptr::slice_from_raw_parts(data, len)
+        self.memory_mut()
     }
 
     /// Returns the raw memory of the runtime associated with this context.
+    /// If no memory has been instantiated the returned slice is empty.
     ///
     /// # Safety
     ///
     /// The returned pointer may get invalidated when wasm function objects are called due to reallocations.
     pub unsafe fn memory_mut(&self) -> *mut [u8] {
         let mallocated = self.mallocated();
-        let len = (*mallocated).length as usize;
+        let len = mallocated
+            .as_ref()
+            .map_or(0, |header| header.length as usize);
         let data = if len == 0 {
             ptr::NonNull::dangling().as_ptr()
         } else {
@@ -60,6 +66,35 @@ impl<'cc> CallContext<'cc> {
         };
         ptr::slice_from_raw_parts_mut(data, len)
     }
+
+    /// Returns a bounds-checked view into the memory of the runtime associated with this context.
+    ///
+    /// The view borrows this context mutably, so it can not outlive the host function call.
+    pub fn memory_view(&mut self) -> MemoryView<'_> {
+        MemoryView::new(unsafe { &mut *self.memory_mut() })
+    }
+
+    /// Returns the runtime this context belongs to, which allows the linked closure to call back
+    /// into wasm functions of the runtime's modules.
+    ///
+    /// Such nested calls run on the same stack as the call that invoked the closure, above the
+    /// frames of all calls still in progress. Every level of nesting therefore adds to the stack
+    /// usage, so deeply nested calls require a correspondingly larger stack and fail with
+    /// [`Error::StackOverflow`] once it has been used up.
+    ///
+    /// The runtime borrows this context, so no memory view can be held across a nested call as
+    /// the memory may be grown by it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the closure has been invoked by a call that was not made through this crate.
+    ///
+    /// [`Error::StackOverflow`]: error/enum.Error.html#variant.StackOverflow
+    pub fn runtime(&self) -> &Runtime {
+        // set by Runtime::enter for the duration of the call that invoked the closure
+        unsafe { self.runtime.as_ref().userdata.cast::<Runtime>().as_ref() }
+            .expect("linked closure was not invoked by a call into this runtime")
+    }
 }
 
 // redefine of ffi::RawCall without the Option<T> around it
@@ -70,18 +105,222 @@ pub type RawCall = unsafe extern "C" fn(
     _mem: *mut cty::c_void,
 ) -> *const cty::c_void;
 
+/// Type of a raw host function for wasm3 that receives a user supplied context pointer.
+pub type RawCallEx = unsafe extern "C" fn(
+    runtime: ffi::IM3Runtime,
+    _sp: ffi::m3stack_t,
+    _mem: *mut cty::c_void,
+    context: *mut cty::c_void,
+) -> *const cty::c_void;
+
 pub(crate) type NNM3Function = NonNull<ffi::M3Function>;
 
+/// Returns the argument types of the given function.
+pub(crate) fn arg_types(func: &ffi::M3Function) -> &[ValueType] {
+    let func_type = unsafe { &*func.funcType };
+    // argTypes is actually dynamically sized.
+    let args =
+        unsafe { slice::from_raw_parts(func_type.argTypes.as_ptr(), func_type.numArgs as usize) };
+    ValueType::from_type_indices(args).expect("function had an unsupported argument type")
+}
+
+/// Returns the name of the module the given function belongs to.
+pub(crate) fn module_name(func: &ffi::M3Function) -> &str {
+    unsafe { func.module.as_ref() }.map_or("", |module| unsafe { cstr_to_str(module.name) })
+}
+
+/// Returns the return type of the given function, `None` if it does not return anything.
+pub(crate) fn ret_type(func: &ffi::M3Function) -> Option<ValueType> {
+    ValueType::from_type_index(unsafe { (*func.funcType).returnType })
+}
+
+/// Returns the index of the given function in its module.
+fn function_index(func: &ffi::M3Function) -> usize {
+    let functions = unsafe { (*func.module).functions };
+    (func as *const ffi::M3Function as usize - functions as usize)
+        / core::mem::size_of::<ffi::M3Function>()
+}
+
+/// Returns the name of the given function, `None` if it is anonymous.
+pub(crate) fn function_name(func: &ffi::M3Function) -> Option<&str> {
+    if func.name.is_null() {
+        None
+    } else {
+        Some(unsafe { cstr_to_str(func.name) })
+    }
+}
+
+/// Formats the given function as `Function` and `DynFunction` do for `Debug`.
+fn debug_function(ty: &str, func: &ffi::M3Function, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let signature = SignatureStr::from_func_type(unsafe { &*func.funcType });
+    f.debug_struct(ty)
+        .field("name", &function_name(func))
+        .field("module", &module_name(func))
+        .field("signature", &format_args!("{}", signature))
+        .finish()
+}
+
+/// Formats the given function as `Function` and `DynFunction` do for `Display`, which is
+/// `module::name`.
+fn display_function(func: &ffi::M3Function, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+        f,
+        "{}::{}",
+        module_name(func),
+        function_name(func).unwrap_or("<anonymous>")
+    )
+}
+
+/// The parameter and return types of a function.
+///
+/// wasm3 does not support multiple return values, so `returns` holds at most one type.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FunctionSignature {
+    /// The types of the function's parameters.
+    pub params: Vec<ValueType>,
+    /// The types of the function's return values, empty if it does not return anything.
+    pub returns: Vec<ValueType>,
+}
+
+impl FunctionSignature {
+    pub(crate) fn of(func: &ffi::M3Function) -> Self {
+        FunctionSignature {
+            params: arg_types(func).to_vec(),
+            returns: ret_type(func).into_iter().collect(),
+        }
+    }
+}
+
+/// A function signature in wasm3's string notation.
+///
+/// The return type is followed by the parenthesized argument types, where `i`, `I`, `f` and `F`
+/// denote `i32`, `i64`, `f32` and `f64` and `v` denotes the absence of a return value.
+/// A function taking an `i32` and an `i64` and returning nothing is written as `v(iI)`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SignatureStr(String);
+
+impl SignatureStr {
+    pub(crate) fn from_func_type(func_type: &ffi::M3FuncType) -> Self {
+        // argTypes is actually dynamically sized.
+        let args = unsafe {
+            slice::from_raw_parts(func_type.argTypes.as_ptr(), func_type.numArgs as usize)
+        };
+        Self::from_types(func_type.returnType, args)
+    }
+
+    pub(crate) fn from_types(ret: u8, args: &[u8]) -> Self {
+        fn type_char(ty: u8) -> char {
+            match ty {
+                ty if ty == i32::TYPE_INDEX => 'i',
+                ty if ty == i64::TYPE_INDEX => 'I',
+                ty if ty == f32::TYPE_INDEX => 'f',
+                ty if ty == f64::TYPE_INDEX => 'F',
+                ty if ty == <()>::TYPE_INDEX => 'v',
+                _ => '?',
+            }
+        }
+        let mut sig = String::with_capacity(args.len() + 3);
+        sig.push(type_char(ret));
+        sig.push('(');
+        sig.extend(args.iter().copied().map(type_char));
+        sig.push(')');
+        SignatureStr(sig)
+    }
+
+    /// Returns the signature as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns a wrapper displaying the signature with the types spelled out, such as
+    /// `i32 (i32, i64)` for `i(iI)` and `() (i32)` for `v(i)`.
+    pub(crate) fn readable(&self) -> impl fmt::Display + '_ {
+        struct Readable<'a>(&'a str);
+
+        impl fmt::Display for Readable<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fn type_name(ty: char) -> &'static str {
+                    match ty {
+                        'i' => "i32",
+                        'I' => "i64",
+                        'f' => "f32",
+                        'F' => "f64",
+                        'v' => "()",
+                        _ => "?",
+                    }
+                }
+                let mut chars = self.0.chars().filter(|&c| c != '(' && c != ')');
+                write!(f, "{} (", chars.next().map_or("?", type_name))?;
+                for (idx, ty) in chars.enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str(type_name(ty))?;
+                }
+                f.write_str(")")
+            }
+        }
+
+        Readable(&self.0)
+    }
+}
+
+impl fmt::Display for SignatureStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// A callable wasm3 function.
 /// This has a generic `call` function for up to 26 parameters emulating an overloading behaviour without having to resort to tuples.
 /// These are hidden to not pollute the documentation.
-#[derive(Debug, Copy, Clone)]
+///
+/// Functions return at most one value, as the bundled wasm3 does not support multi-value returns.
+///
+/// Calls do not allocate, the arguments are written straight into the runtime's stack and the
+/// return value is read back from it.
+///
+/// A function borrows the [`Runtime`] it was looked up in, so it can not outlive it.
+///
+/// Functions compare equal and hash alike if they refer to the same function of the same loaded
+/// module, regardless of how they were looked up. As every loaded module has its own functions,
+/// this also tells apart the same function in different runtimes. To identify a function across
+/// reloads of the same module, use its [`index`] instead.
+///
+/// ```compile_fail
+/// # fn wasm() -> &'static [u8] { &[] }
+/// let env = wasm3::Environment::new().unwrap();
+/// let rt = env.create_runtime(1024).unwrap();
+/// let func = rt
+///     .parse_and_load_module(wasm())
+///     .unwrap()
+///     .find_function::<(), ()>("main")
+///     .unwrap();
+/// drop(rt);
+/// func.call().unwrap();
+/// ```
+///
+/// [`Runtime`]: struct.Runtime.html
+/// [`index`]: #method.index
+#[derive(Copy, Clone)]
 pub struct Function<'rt, Args, Ret> {
     raw: NNM3Function,
     rt: &'rt Runtime,
     _pd: PhantomData<*const (Args, Ret)>,
 }
 
+impl<'rt, Args, Ret> fmt::Debug for Function<'rt, Args, Ret> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_function("Function", unsafe { self.raw.as_ref() }, f)
+    }
+}
+
+impl<'rt, Args, Ret> fmt::Display for Function<'rt, Args, Ret> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_function(unsafe { self.raw.as_ref() }, f)
+    }
+}
+
 impl<'rt, Args, Ret> Eq for Function<'rt, Args, Ret> {}
 impl<'rt, Args, Ret> PartialEq for Function<'rt, Args, Ret> {
     fn eq(&self, other: &Self) -> bool {
@@ -105,9 +344,40 @@ where
         unsafe { cstr_to_str(self.raw.as_ref().import.moduleUtf8) }
     }
 
-    /// The name of this function.
-    pub fn name(&self) -> &str {
-        unsafe { cstr_to_str(self.raw.as_ref().name) }
+    /// The name of this function, `None` if it is neither exported nor named by the module's
+    /// name section.
+    pub fn name(&self) -> Option<&str> {
+        function_name(unsafe { self.raw.as_ref() })
+    }
+
+    /// The name of the module this function belongs to.
+    pub fn module_name(&self) -> &str {
+        module_name(unsafe { self.raw.as_ref() })
+    }
+
+    /// The types of this function's arguments.
+    pub fn arg_types(&self) -> &[ValueType] {
+        arg_types(unsafe { self.raw.as_ref() })
+    }
+
+    /// The type of this function's return value, `None` if it does not return anything.
+    pub fn ret_type(&self) -> Option<ValueType> {
+        ret_type(unsafe { self.raw.as_ref() })
+    }
+
+    /// The parameter and return types of this function.
+    pub fn signature(&self) -> FunctionSignature {
+        FunctionSignature::of(unsafe { self.raw.as_ref() })
+    }
+
+    /// The index of this function in its module, counting imported functions.
+    ///
+    /// The index only depends on the module's bytes, so it identifies the function across
+    /// reloads of the module and can be passed to [`Module::function`] to look it up again.
+    ///
+    /// [`Module::function`]: struct.Module.html#method.function
+    pub fn index(&self) -> usize {
+        function_index(unsafe { self.raw.as_ref() })
     }
 }
 
@@ -125,10 +395,16 @@ where
         } = unsafe { &*func.as_mut().funcType };
         // argTypes is actually dynamically sized.
         let args = unsafe { slice::from_raw_parts(args.as_ptr(), num as usize) };
-        match Ret::TYPE_INDEX == ret && Args::validate_types(args) {
-            true => Ok(()),
-            false => Err(Error::InvalidFunctionSignature),
+        if Ret::TYPE_INDEX == ret && Args::validate_types(args) {
+            return Ok(());
         }
+        let mut requested = Vec::new();
+        Args::push_type_indices(&mut requested);
+        Err(Error::SignatureMismatch {
+            expected: SignatureStr::from_types(ret, args),
+            requested: SignatureStr::from_types(Ret::TYPE_INDEX, &requested),
+            first_mismatch: args.iter().zip(&requested).position(|(ty, req)| ty != req),
+        })
     }
 
     #[inline]
@@ -145,50 +421,223 @@ where
 
     #[inline]
     pub(crate) fn compile(self) -> Result<Self> {
-        unsafe {
-            if self.raw.as_ref().compiled.is_null() {
-                Error::from_ffi_res(wasm3_priv::Compile_Function(self.raw.as_ptr()))?;
+        compile_impl(self.raw).map(|()| self)
+    }
+
+    /// Calls this function with the given arguments, aborting it once it executed `max_ops`
+    /// operations. This bounds the execution of untrusted code, including infinite loops, without
+    /// having to adjust the fuel of the runtime around every call.
+    ///
+    /// Operations are counted like fuel: every function call and every loop iteration is one
+    /// operation, see [`Runtime::set_fuel`]. They are subtracted from the runtime's fuel as usual,
+    /// so the call is also limited by the fuel left in the runtime.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::Timeout`] if the call exceeded `max_ops`,
+    /// [`Error::OutOfFuel`] if the runtime ran out of fuel first, and any error a regular call
+    /// returns otherwise.
+    ///
+    /// [`Runtime::set_fuel`]: struct.Runtime.html#method.set_fuel
+    /// [`Error::Timeout`]: error/enum.Error.html#variant.Timeout
+    /// [`Error::OutOfFuel`]: error/enum.Error.html#variant.OutOfFuel
+    #[cfg(feature = "metering")]
+    pub fn call_with_timeout(&self, args: Args, max_ops: u64) -> Result<Ret> {
+        let limited = max_ops < self.rt.fuel_remaining();
+        match self.rt.with_fuel_limit(max_ops, || self.call_impl(args)) {
+            Err(Error::OutOfFuel) if limited => Err(Error::Timeout),
+            res => res,
+        }
+    }
+
+    /// Calls this function with arguments taken from a buffer of raw slots, writing the return
+    /// value back into the first slot. This allows encoding the arguments once and reusing the
+    /// buffer across calls in a tight loop.
+    ///
+    /// Each argument occupies one slot, in the order of the function's parameters. `i32` and
+    /// `f32` values are stored in the lower 32 bits of their slot, as the integer itself and as
+    /// the bits of the float respectively, and the upper 32 bits are ignored for arguments and
+    /// zeroed for the return value. `i64` and `f64` values occupy the whole slot in the same way.
+    ///
+    /// The `call_raw` benchmark compares this with [`Function::call`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the buffer has fewer slots than the function has parameters, or no slot for its return
+    ///   value, see [`Error::ArgumentCountMismatch`]
+    /// * the arguments do not fit on the runtime's stack, see [`Error::StackOverflow`]
+    /// * the function trapped
+    ///
+    /// # Safety
+    ///
+    /// The slots are handed to the function without any knowledge of the values they are meant to
+    /// hold, so the caller must ensure that each argument slot holds a value of the respective
+    /// parameter's type encoded as described above, just as the function's own code would.
+    ///
+    /// [`Function::call`]: #method.call
+    /// [`Error::ArgumentCountMismatch`]: error/enum.Error.html#variant.ArgumentCountMismatch
+    /// [`Error::StackOverflow`]: error/enum.Error.html#variant.StackOverflow
+    pub unsafe fn call_raw(&self, slots: &mut [u64]) -> Result<()> {
+        let func = self.raw.as_ref();
+        let arg_types = arg_types(func);
+        let ret = ret_type(func);
+        let required = arg_types.len().max(ret.is_some() as usize);
+        if slots.len() < required {
+            return Err(Error::ArgumentCountMismatch {
+                expected: required,
+                provided: slots.len(),
+            });
+        }
+
+        // reborrowing might be UB here due to aliasing, but there is currently no other stable way to get the metadata of a raw fat pointer
+        let stack = &mut *self.rt.call_stack();
+        let mut offset = 0;
+        for (&slot, &ty) in slots.iter().zip(arg_types) {
+            let arg = Value::from_raw_slot(ty, slot);
+            if offset + arg.size_in_slot_count() > stack.len() {
+                return Err(Error::StackOverflow);
             }
-        };
-        Ok(self)
+            arg.push_on_stack(stack[offset..].as_mut_ptr());
+            offset += arg.size_in_slot_count();
+        }
+
+        let res = self.rt.enter(func, || {
+            call_impl_(
+                func.compiled,
+                stack.as_mut_ptr(),
+                self.rt.mallocated(),
+                0,
+                0.0,
+            )
+        });
+        self.rt.call_result(res.cast())?;
+        if let Some(ret) = ret {
+            slots[0] =
+                Value::pop_from_stack(ret as u8, stack.as_mut_ptr()).map_or(0, Value::to_raw_slot);
+        }
+        Ok(())
     }
 
-    fn call_impl(&self, args: Args) -> Result<Ret> {
+    /// Returns a closure calling this function with its arguments given as a tuple, or as a
+    /// single value for functions taking one argument. This allows handing the function to
+    /// generic code, such as iterator adapters, without writing a wrapping closure.
+    ///
+    /// The function has already been looked up and compiled, so calling the closure is as cheap
+    /// as calling [`Function::call`].
+    ///
+    /// ```no_run
+    /// # fn wasm() -> &'static [u8] { &[] }
+    /// # fn main() -> wasm3::error::Result<()> {
+    /// # let env = wasm3::Environment::new()?;
+    /// # let rt = env.create_runtime(1024)?;
+    /// # let module = rt.parse_and_load_module(wasm())?;
+    /// let add = module.find_function::<(i64, i64), i64>("add")?;
+    /// let sums = [(1, 2), (3, 4)]
+    ///     .iter()
+    ///     .copied()
+    ///     .map(add.as_callable())
+    ///     .collect::<Result<Vec<_>, _>>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`Function::call`]: #method.call
+    pub fn as_callable(&self) -> impl Fn(Args) -> Result<Ret> + '_ {
+        move |args| self.call_impl(args)
+    }
+
+    /// Calls this function with the given arguments, skipping the bookkeeping a regular call
+    /// does around the call into wasm. This shaves off a little overhead in a hot loop calling a
+    /// tiny function, see the `call_unchecked` benchmark.
+    ///
+    /// Like a regular call this does not check the runtime or the signature of the function, as
+    /// both are already guaranteed by looking up the function. Unlike a regular call it however:
+    ///
+    /// * always uses the runtime's whole stack, instead of the part above calls in progress
+    /// * does not make the runtime available to linked closures via [`CallContext::runtime`],
+    ///   which panics if they try to access it
+    /// * is neither metered with the `metering` feature nor reported to the call observer with
+    ///   the `profiling` feature
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure that no other call into this runtime is in progress, that is this
+    /// function must not be called from within a linked closure invoked by a call into the same
+    /// runtime. Doing so overwrites the stack of the call in progress.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the function trapped, like a regular call.
+    ///
+    /// [`CallContext::runtime`]: struct.CallContext.html#method.runtime
+    #[inline]
+    pub unsafe fn call_unchecked(&self, args: Args) -> Result<Ret> {
         let stack = self.rt.stack_mut();
-        let ret = unsafe {
+        args.push_on_stack(stack);
+        let res = call_impl_(
+            self.raw.as_ref().compiled,
+            stack.cast(),
+            self.rt.mallocated(),
+            0,
+            0.0,
+        );
+        #[cfg(feature = "catch-unwind")]
+        crate::unwind::resume(res);
+        self.rt
+            .call_result(res.cast())
+            .map(|()| Ret::pop_from_stack(stack.cast()))
+    }
+
+    pub(crate) fn call_impl(&self, args: Args) -> Result<Ret> {
+        let stack = self.rt.call_stack();
+        let ret = self.rt.enter(unsafe { self.raw.as_ref() }, || unsafe {
             args.push_on_stack(stack);
-            Self::call_impl_(
+            call_impl_(
                 self.raw.as_ref().compiled,
                 stack.cast(),
                 self.rt.mallocated(),
                 0,
                 0.0,
             )
-        };
-        Error::from_ffi_res(ret.cast()).map(|()| unsafe { Ret::pop_from_stack(stack.cast()) })
+        });
+        self.rt
+            .call_result(ret.cast())
+            .map(|()| unsafe { Ret::pop_from_stack(stack.cast()) })
     }
+}
 
-    #[inline]
-    unsafe fn call_impl_(
-        _pc: ffi::pc_t,
-        _sp: ffi::m3stack_t,
-        _mem: *mut ffi::M3MemoryHeader,
-        _r0: ffi::m3reg_t,
-        _fp0: f64,
-    ) -> ffi::m3ret_t {
-        let possible_trap = ffi::m3_Yield();
-        if !possible_trap.is_null() {
-            possible_trap.cast()
-        } else {
-            (*_pc.cast::<ffi::IM3Operation>()).expect("IM3Operation was null")(
-                _pc.add(1),
-                _sp,
-                _mem,
-                _r0,
-                _fp0,
-            )
+#[inline]
+unsafe fn call_impl_(
+    _pc: ffi::pc_t,
+    _sp: ffi::m3stack_t,
+    _mem: *mut ffi::M3MemoryHeader,
+    _r0: ffi::m3reg_t,
+    _fp0: f64,
+) -> ffi::m3ret_t {
+    let possible_trap = ffi::m3_Yield();
+    if !possible_trap.is_null() {
+        possible_trap.cast()
+    } else {
+        (*_pc.cast::<ffi::IM3Operation>()).expect("IM3Operation was null")(
+            _pc.add(1),
+            _sp,
+            _mem,
+            _r0,
+            _fp0,
+        )
+    }
+}
+
+#[inline]
+pub(crate) fn compile_impl(func: NNM3Function) -> Result<()> {
+    unsafe {
+        if func.as_ref().compiled.is_null() {
+            Error::from_ffi_res(wasm3_priv::Compile_Function(func.as_ptr()))?;
         }
     }
+    Ok(())
 }
 
 macro_rules! func_call_impl {
@@ -240,3 +689,243 @@ where
         self.call_impl(())
     }
 }
+
+/// A callable wasm3 function whose signature is only known at runtime.
+///
+/// Unlike [`Function`] the argument and return types are checked on every call instead of once
+/// on lookup, which allows dispatching to functions whose signature is not known at compile time.
+///
+/// Equality and hashing work like they do for [`Function`], by the loaded function referred to.
+#[derive(Copy, Clone)]
+pub struct DynFunction<'rt> {
+    raw: NNM3Function,
+    rt: &'rt Runtime,
+}
+
+impl<'rt> fmt::Debug for DynFunction<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        debug_function("DynFunction", unsafe { self.raw.as_ref() }, f)
+    }
+}
+
+impl<'rt> fmt::Display for DynFunction<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display_function(unsafe { self.raw.as_ref() }, f)
+    }
+}
+
+impl<'rt, Args, Ret> From<Function<'rt, Args, Ret>> for DynFunction<'rt> {
+    /// Erases the signature of a function, allowing it to be called with [`Value`]s.
+    ///
+    /// [`Value`]: enum.Value.html
+    fn from(func: Function<'rt, Args, Ret>) -> Self {
+        // functions are compiled on lookup already
+        DynFunction {
+            raw: func.raw,
+            rt: func.rt,
+        }
+    }
+}
+
+impl<'rt> Eq for DynFunction<'rt> {}
+impl<'rt> PartialEq for DynFunction<'rt> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<'rt> Hash for DynFunction<'rt> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.raw.hash(state);
+    }
+}
+
+impl<'rt> DynFunction<'rt> {
+    #[inline]
+    pub(crate) fn from_raw(rt: &'rt Runtime, raw: NNM3Function) -> Result<Self> {
+        // make sure the function is compiled
+        compile_impl(raw).map(|()| DynFunction { raw, rt })
+    }
+
+    /// The name of the import module of this function.
+    pub fn import_module_name(&self) -> &str {
+        unsafe { cstr_to_str(self.raw.as_ref().import.moduleUtf8) }
+    }
+
+    /// The name of this function, `None` if it is neither exported nor named by the module's
+    /// name section.
+    pub fn name(&self) -> Option<&str> {
+        function_name(unsafe { self.raw.as_ref() })
+    }
+
+    /// The name of the module this function belongs to.
+    pub fn module_name(&self) -> &str {
+        module_name(unsafe { self.raw.as_ref() })
+    }
+
+    /// The types of this function's arguments.
+    pub fn arg_types(&self) -> &[ValueType] {
+        arg_types(unsafe { self.raw.as_ref() })
+    }
+
+    /// The type of this function's return value, `None` if it does not return anything.
+    pub fn ret_type(&self) -> Option<ValueType> {
+        ret_type(unsafe { self.raw.as_ref() })
+    }
+
+    /// The parameter and return types of this function.
+    pub fn signature(&self) -> FunctionSignature {
+        FunctionSignature::of(unsafe { self.raw.as_ref() })
+    }
+
+    /// The index of this function in its module, counting imported functions.
+    ///
+    /// The index only depends on the module's bytes, so it identifies the function across
+    /// reloads of the module and can be passed to [`Module::function`] to look it up again.
+    ///
+    /// [`Module::function`]: struct.Module.html#method.function
+    pub fn index(&self) -> usize {
+        function_index(unsafe { self.raw.as_ref() })
+    }
+
+    /// Converts this into a [`Function`] with a statically known signature.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the signature of the function does not match `Args` and
+    /// `Ret`.
+    pub fn try_typed<Args, Ret>(self) -> Result<Function<'rt, Args, Ret>>
+    where
+        Args: WasmArgs,
+        Ret: WasmType,
+    {
+        Function::from_raw(self.rt, self.raw)
+    }
+
+    /// Calls this function with the given arguments, returning its results.
+    /// A function without a return value returns an empty `Vec`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the number or types of the arguments do not match the function's signature, in which
+    ///   case [`Error::ArgumentTypeMismatch`] lists the expected and provided types
+    /// * the arguments do not fit on the runtime's stack, see [`Error::StackOverflow`]
+    /// * the function trapped
+    ///
+    /// [`Error::ArgumentTypeMismatch`]: error/enum.Error.html#variant.ArgumentTypeMismatch
+    /// [`Error::StackOverflow`]: error/enum.Error.html#variant.StackOverflow
+    pub fn call_dynamic(&self, args: &[Value]) -> Result<Vec<Value>> {
+        let arg_types = self.arg_types();
+        if args.len() != arg_types.len()
+            || args
+                .iter()
+                .zip(arg_types)
+                .any(|(arg, &ty)| arg.value_type() != ty)
+        {
+            return Err(Error::ArgumentTypeMismatch {
+                expected: arg_types.to_vec(),
+                provided: args.iter().map(Value::value_type).collect(),
+            });
+        }
+        self.call_values(args)
+    }
+
+    /// Calls this function with the given arguments like [`DynFunction::call_dynamic`], but
+    /// reports a wrong number of arguments separately from arguments of the wrong type.
+    /// This allows scripting hosts to tell their users precisely what was wrong with a call.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the number of arguments does not match the function's signature, see
+    ///   [`Error::ArgumentCountMismatch`]
+    /// * the types of the arguments do not match the function's signature, in which case
+    ///   [`Error::ArgumentTypeMismatch`] lists the expected and provided types
+    /// * the function trapped
+    ///
+    /// [`DynFunction::call_dynamic`]: struct.DynFunction.html#method.call_dynamic
+    /// [`Error::ArgumentCountMismatch`]: error/enum.Error.html#variant.ArgumentCountMismatch
+    /// [`Error::ArgumentTypeMismatch`]: error/enum.Error.html#variant.ArgumentTypeMismatch
+    pub fn call_checked(&self, args: &[Value]) -> Result<Vec<Value>> {
+        let arg_types = self.arg_types();
+        if args.len() != arg_types.len() {
+            return Err(Error::ArgumentCountMismatch {
+                expected: arg_types.len(),
+                provided: args.len(),
+            });
+        }
+        self.call_dynamic(args)
+    }
+
+    fn call_values(&self, args: &[Value]) -> Result<Vec<Value>> {
+        let ret = unsafe { (*self.raw.as_ref().funcType).returnType };
+
+        // reborrowing might be UB here due to aliasing, but there is currently no other stable way to get the metadata of a raw fat pointer
+        let stack = unsafe { &mut *self.rt.call_stack() };
+        let req_size = args.iter().map(Value::size_in_slot_count).sum::<usize>();
+        if req_size > stack.len() {
+            return Err(Error::StackOverflow);
+        }
+        let mut offset = 0;
+        for arg in args {
+            unsafe { arg.push_on_stack(stack[offset..].as_mut_ptr()) };
+            offset += arg.size_in_slot_count();
+        }
+
+        let res = self.rt.enter(unsafe { self.raw.as_ref() }, || unsafe {
+            call_impl_(
+                self.raw.as_ref().compiled,
+                stack.as_mut_ptr(),
+                self.rt.mallocated(),
+                0,
+                0.0,
+            )
+        });
+        self.rt.call_result(res.cast())?;
+        Ok(unsafe { Value::pop_from_stack(ret, stack.as_mut_ptr()) }
+            .into_iter()
+            .collect())
+    }
+
+    /// Calls this function with arguments given as strings, each of which is parsed according to
+    /// the type of the respective parameter as described by [`Value::parse`].
+    /// This is useful for command line style harnesses that read their arguments as text.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the number of arguments does not match the function's signature, see
+    ///   [`Error::ArgumentCountMismatch`]
+    /// * an argument could not be parsed, in which case [`Error::InvalidArgument`] names the
+    ///   index of the first offending argument
+    /// * the function trapped
+    ///
+    /// [`Value::parse`]: enum.Value.html#method.parse
+    /// [`Error::ArgumentCountMismatch`]: error/enum.Error.html#variant.ArgumentCountMismatch
+    /// [`Error::InvalidArgument`]: error/enum.Error.html#variant.InvalidArgument
+    pub fn call_argv(&self, args: &[&str]) -> Result<Vec<Value>> {
+        let arg_types = self.arg_types();
+        if args.len() != arg_types.len() {
+            return Err(Error::ArgumentCountMismatch {
+                expected: arg_types.len(),
+                provided: args.len(),
+            });
+        }
+        let args = args
+            .iter()
+            .zip(arg_types)
+            .enumerate()
+            .map(|(index, (arg, &ty))| {
+                Value::parse(ty, arg).ok_or(Error::InvalidArgument {
+                    index,
+                    expected: ty,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.call_dynamic(&args)
+    }
+}
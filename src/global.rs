@@ -0,0 +1,93 @@
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ptr::NonNull;
+
+use crate::error::{Error, Result};
+use crate::runtime::Runtime;
+use crate::utils::cstr_to_str;
+use crate::{Value, ValueType};
+
+/// A global variable of a loaded [`Module`].
+///
+/// [`Module`]: struct.Module.html
+#[derive(Copy, Clone)]
+pub struct Global<'rt> {
+    raw: NonNull<ffi::M3Global>,
+    _pd: PhantomData<&'rt Runtime>,
+}
+
+impl<'rt> Global<'rt> {
+    pub(crate) fn from_raw(raw: NonNull<ffi::M3Global>) -> Self {
+        Global {
+            raw,
+            _pd: PhantomData,
+        }
+    }
+
+    /// The name this global is exported as, `None` if it is not exported.
+    pub fn name(&self) -> Option<&'rt str> {
+        let name = unsafe { self.raw.as_ref().name };
+        if name.is_null() {
+            None
+        } else {
+            Some(unsafe { cstr_to_str(name) })
+        }
+    }
+
+    /// The declared type of this global.
+    pub fn value_type(&self) -> ValueType {
+        ValueType::from_type_index(unsafe { self.raw.as_ref().type_ })
+            .expect("validated globals always have a value type")
+    }
+
+    /// Returns the current value of this global, typed after the global's declared type.
+    pub fn get(&self) -> Value {
+        let mut tagged = unsafe { mem::zeroed::<ffi::M3TaggedValue>() };
+        let res = unsafe { ffi::m3_GetGlobal(self.raw.as_ptr(), &mut tagged) };
+        Error::from_ffi_res(res)
+            .ok()
+            .and_then(|()| unsafe { Value::from_tagged(&tagged) })
+            .expect("validated globals always have a value type")
+    }
+
+    /// Whether this global can be set.
+    pub fn is_mutable(&self) -> bool {
+        unsafe { self.raw.as_ref().isMutable }
+    }
+
+    /// Sets the value of this global.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the global is immutable
+    /// * the type of the value does not match the global's declared type
+    pub fn set(&self, value: Value) -> Result<()> {
+        if !self.is_mutable() {
+            return Err(Error::GlobalNotMutable);
+        }
+        if value.type_index() != unsafe { self.raw.as_ref().type_ } {
+            return Err(Error::InvalidGlobalType);
+        }
+        let mut tagged = value.to_tagged();
+        Error::from_ffi_res(unsafe { ffi::m3_SetGlobal(self.raw.as_ptr(), &mut tagged) })
+    }
+}
+
+impl<'rt> Eq for Global<'rt> {}
+impl<'rt> PartialEq for Global<'rt> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw
+    }
+}
+
+impl<'rt> fmt::Debug for Global<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Global")
+            .field("name", &self.name())
+            .field("value", &self.get())
+            .finish()
+    }
+}
@@ -0,0 +1,148 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::mem;
+
+use crate::error::{Error, Result};
+use crate::function::Function;
+use crate::memory::checked_range;
+use crate::runtime::Runtime;
+
+/// Allocates buffers in guest memory through functions exported by the guest.
+///
+/// The guest has to export an allocation function of the signature `(i32) -> i32`, taking a
+/// size in bytes and returning a pointer into its linear memory or `0` on failure, as well as a
+/// matching deallocation function of the signature `(i32) -> ()`.
+pub struct GuestAllocator<'rt> {
+    rt: &'rt Runtime,
+    alloc: Function<'rt, u32, u32>,
+    free: Function<'rt, u32, ()>,
+}
+
+impl<'rt> GuestAllocator<'rt> {
+    /// Creates an allocator using the `malloc` and `free` functions exported by the runtime's
+    /// loaded modules.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either function could not be found or has an
+    /// unexpected signature.
+    pub fn new(rt: &'rt Runtime) -> Result<Self> {
+        Self::with_functions(rt, "malloc", "free")
+    }
+
+    /// Creates an allocator using the given exported allocation and deallocation functions.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if either function could not be found or has an
+    /// unexpected signature.
+    pub fn with_functions(rt: &'rt Runtime, alloc_name: &str, free_name: &str) -> Result<Self> {
+        Ok(GuestAllocator {
+            rt,
+            alloc: rt.find_function(alloc_name)?,
+            free: rt.find_function(free_name)?,
+        })
+    }
+
+    /// Allocates a guest buffer and copies `bytes` into it.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * the guest's allocation function trapped or returned `0`
+    /// * the returned buffer does not lie within the guest's memory
+    pub fn alloc_bytes(&self, bytes: &[u8]) -> Result<GuestBuffer<'rt>> {
+        let len = u32::try_from(bytes.len())
+            .map_err(|_| Error::GuestAllocationFailed { len: bytes.len() })?;
+        let ptr = self.alloc.call(len)?;
+        if ptr == 0 {
+            return Err(Error::GuestAllocationFailed { len: bytes.len() });
+        }
+        // construct the buffer first so that it gets freed should the write fail
+        let buffer = GuestBuffer {
+            free: self.free,
+            ptr,
+            len,
+        };
+        // the allocation may have grown the memory, so it has to be fetched after the call
+        let memory = unsafe { &mut *self.rt.memory_slice_ptr() };
+        let range = checked_range(memory.len(), ptr as usize, bytes.len())?;
+        memory[range].copy_from_slice(bytes);
+        Ok(buffer)
+    }
+
+    /// Allocates a guest buffer and copies the UTF-8 bytes of `s` into it.
+    /// No nul-terminator is appended.
+    ///
+    /// # Errors
+    ///
+    /// See [`GuestAllocator::alloc_bytes`].
+    ///
+    /// [`GuestAllocator::alloc_bytes`]: #method.alloc_bytes
+    #[inline]
+    pub fn alloc_str(&self, s: &str) -> Result<GuestBuffer<'rt>> {
+        self.alloc_bytes(s.as_bytes())
+    }
+}
+
+impl<'rt> fmt::Debug for GuestAllocator<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GuestAllocator")
+            .field("alloc", &self.alloc.name())
+            .field("free", &self.free.name())
+            .finish()
+    }
+}
+
+/// A buffer in guest memory allocated by a [`GuestAllocator`].
+/// The buffer is freed when dropped.
+#[derive(Debug)]
+pub struct GuestBuffer<'rt> {
+    free: Function<'rt, u32, ()>,
+    ptr: u32,
+    len: u32,
+}
+
+impl<'rt> GuestBuffer<'rt> {
+    /// The guest pointer to the start of this buffer.
+    #[inline]
+    pub fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
+    /// The length of this buffer in bytes.
+    #[inline]
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Whether this buffer is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Frees this buffer, returning an error if the guest's deallocation function trapped.
+    pub fn free(self) -> Result<()> {
+        let res = self.free.call(self.ptr);
+        mem::forget(self);
+        res
+    }
+
+    /// Gives up ownership of this buffer without freeing it, returning its guest pointer.
+    ///
+    /// This has to be used when the guest takes ownership of the buffer and frees it itself.
+    pub fn into_raw(self) -> u32 {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
+}
+
+impl<'rt> Drop for GuestBuffer<'rt> {
+    fn drop(&mut self) {
+        // there is no way to report a trap from here, use GuestBuffer::free to observe it
+        let _ = self.free.call(self.ptr);
+    }
+}
@@ -1,24 +1,72 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 //! A rust wrapper for [WASM3](https://github.com/wasm3/wasm3).
+//!
+//! Without the `std` feature this crate is `no_std`, it does however always require a global
+//! allocator, as wasm3 itself allocates on the heap.
+//!
+//! The whole API of environments, runtimes, modules, functions and linked closures is available
+//! without `std`. The `std` feature, which is enabled by default, only adds:
+//!
+//! * implementations of `std::error::Error` for the error types
+//! * parsing modules from a reader with [`ParsedModule::parse_from_reader`]
+//! * the `metering` and `catch-unwind` features, which rely on thread locals
+//! * [`profiling::StdClock`] with the `profiling` feature
+//!
+//! The default `wasi` feature requires a hosted target as well, so building for a bare metal
+//! target like `thumbv7em-none-eabi` requires disabling the default features.
+//!
+//! [`ParsedModule::parse_from_reader`]: struct.ParsedModule.html#method.parse_from_reader
+//! [`profiling::StdClock`]: profiling/struct.StdClock.html
 
 extern crate alloc;
 
 pub mod error;
 
+#[cfg(feature = "custom-allocator")]
+mod allocator;
+#[cfg(feature = "custom-allocator")]
+pub use self::allocator::{AllocFn, FreeFn, ReallocFn};
 mod environment;
-pub use self::environment::Environment;
+pub use self::environment::{Environment, EnvironmentBuilder};
 mod function;
-pub use self::function::{CallContext, Function, RawCall};
+pub use self::function::{
+    CallContext, DynFunction, Function, FunctionSignature, RawCall, RawCallEx, SignatureStr,
+};
+mod global;
+pub use self::global::Global;
+mod guest_allocator;
+pub use self::guest_allocator::{GuestAllocator, GuestBuffer};
 mod macros;
 pub use self::macros::*;
+mod memory;
+pub use self::memory::{MemorySnapshot, MemoryView};
+#[cfg(feature = "metering")]
+mod metering;
 mod module;
-pub use self::module::{Module, ParsedModule};
+pub use self::module::{
+    ExportedFunction, FunctionInfo, ImportDescriptor, ImportedFunction, Module, ModuleInfo,
+    ParsedModule, ValidationReport,
+};
+#[cfg(feature = "profiling")]
+pub mod profiling;
 mod runtime;
-pub use self::runtime::Runtime;
+pub use self::runtime::{Runtime, RuntimeBuilder, SendRuntime};
+#[cfg(feature = "std")]
+pub use self::runtime::{RuntimeGuard, RuntimeHandle};
 mod ty;
 pub use self::ty::{WasmArg, WasmArgs, WasmType};
+#[cfg(feature = "catch-unwind")]
+mod unwind;
 mod utils;
+mod value;
+pub use self::value::{Value, ValueType};
+#[cfg(feature = "wasi")]
+mod wasi;
+#[cfg(feature = "wasi")]
+pub use self::wasi::{OutputBuffer, WasiConfig};
+mod wasm_ptr;
+pub use self::wasm_ptr::{WasmPtr, WasmSlice};
 pub use ffi as wasm3_sys;
 
 pub(crate) mod wasm3_priv;
@@ -0,0 +1,432 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use core::convert::TryInto;
+#[cfg(feature = "bytemuck")]
+use core::mem;
+use core::ops::Range;
+use core::str;
+
+use crate::error::{Error, Result};
+
+/// A bounds-checked view into the linear memory of a [`Runtime`].
+///
+/// All accesses are little-endian and may be unaligned, as wasm memory gives no alignment guarantees.
+///
+/// [`Runtime`]: struct.Runtime.html
+#[derive(Debug)]
+pub struct MemoryView<'m> {
+    memory: &'m mut [u8],
+}
+
+macro_rules! memory_view_rw_impl {
+    ($($ty:ident: $read:ident, $write:ident;)*) => {
+        $(
+            #[doc = concat!("Reads a `", stringify!($ty), "` at the given offset.")]
+            ///
+            /// # Errors
+            ///
+            /// This function will error if the access is out of bounds.
+            #[inline]
+            pub fn $read(&self, offset: usize) -> Result<$ty> {
+                self.read_array(offset).map($ty::from_le_bytes)
+            }
+
+            #[doc = concat!("Writes a `", stringify!($ty), "` at the given offset.")]
+            ///
+            /// # Errors
+            ///
+            /// This function will error if the access is out of bounds.
+            #[inline]
+            pub fn $write(&mut self, offset: usize, val: $ty) -> Result<()> {
+                self.write_array(offset, val.to_le_bytes())
+            }
+        )*
+    };
+}
+
+impl<'m> MemoryView<'m> {
+    pub(crate) fn new(memory: &'m mut [u8]) -> Self {
+        MemoryView { memory }
+    }
+
+    /// The size of the viewed memory in bytes.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Whether the viewed memory is empty, meaning no memory has been instantiated.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.memory.is_empty()
+    }
+
+    fn get(&self, offset: usize, len: usize) -> Result<&[u8]> {
+        checked_range(self.memory.len(), offset, len).map(|range| &self.memory[range])
+    }
+
+    pub(crate) fn get_mut(&mut self, offset: usize, len: usize) -> Result<&mut [u8]> {
+        checked_range(self.memory.len(), offset, len).map(move |range| &mut self.memory[range])
+    }
+
+    /// Reads `len` bytes at the given offset.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[inline]
+    pub fn read_bytes(&self, offset: usize, len: usize) -> Result<&[u8]> {
+        self.get(offset, len)
+    }
+
+    /// Copies `dst.len()` bytes at the given offset into `dst`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[inline]
+    pub fn read_into(&self, offset: usize, dst: &mut [u8]) -> Result<()> {
+        self.get(offset, dst.len())
+            .map(|src| dst.copy_from_slice(src))
+    }
+
+    /// Writes the given bytes at the given offset.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[inline]
+    pub fn write_bytes(&mut self, offset: usize, bytes: &[u8]) -> Result<()> {
+        self.get_mut(offset, bytes.len())
+            .map(|dst| dst.copy_from_slice(bytes))
+    }
+
+    /// Writes the UTF-8 bytes of `s` at the given offset.
+    /// No nul-terminator is appended.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds, in which case nothing is written.
+    #[inline]
+    pub fn write_str(&mut self, offset: usize, s: &str) -> Result<()> {
+        self.write_bytes(offset, s.as_bytes())
+    }
+
+    /// Reads a UTF-8 string of `len` bytes at the given offset.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds or the bytes are not valid UTF-8.
+    #[inline]
+    pub fn read_str(&self, offset: usize, len: usize) -> Result<&str> {
+        read_str(self.memory, offset, len)
+    }
+
+    /// Reads a nul-terminated UTF-8 string at the given offset, excluding the terminator.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if no terminator is found before the end of memory or the bytes
+    /// are not valid UTF-8.
+    #[inline]
+    pub fn read_cstr(&self, offset: usize) -> Result<&str> {
+        read_cstr(self.memory, offset)
+    }
+
+    /// Reads `count` values of type `T` at the given offset.
+    ///
+    /// The values are borrowed from memory if the offset is suitably aligned for `T`, otherwise
+    /// they are copied out.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[cfg(feature = "bytemuck")]
+    pub fn read_slice_of<T: bytemuck::Pod>(
+        &self,
+        offset: usize,
+        count: usize,
+    ) -> Result<Cow<'_, [T]>> {
+        let size = mem::size_of::<T>();
+        let len = count
+            .checked_mul(size)
+            .ok_or(Error::OutOfBoundsMemoryAccess {
+                offset,
+                len: usize::MAX,
+            })?;
+        self.get(offset, len)
+            .map(|bytes| match bytemuck::try_cast_slice(bytes) {
+                Ok(values) => Cow::Borrowed(values),
+                Err(_) => Cow::Owned(
+                    (0..count)
+                        .map(|i| bytemuck::pod_read_unaligned(&bytes[i * size..][..size]))
+                        .collect(),
+                ),
+            })
+    }
+
+    /// Writes the given values at the given offset.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[cfg(feature = "bytemuck")]
+    #[inline]
+    pub fn write_slice_of<T: bytemuck::Pod>(&mut self, offset: usize, values: &[T]) -> Result<()> {
+        self.write_bytes(offset, bytemuck::cast_slice(values))
+    }
+
+    fn read_array<const N: usize>(&self, offset: usize) -> Result<[u8; N]> {
+        self.get(offset, N)
+            .map(|bytes| bytes.try_into().expect("slice length was checked"))
+    }
+
+    fn write_array<const N: usize>(&mut self, offset: usize, bytes: [u8; N]) -> Result<()> {
+        self.get_mut(offset, N)
+            .map(|dst| dst.copy_from_slice(&bytes))
+    }
+
+    memory_view_rw_impl! {
+        u8: read_u8, write_u8;
+        u16: read_u16, write_u16;
+        u32: read_u32, write_u32;
+        u64: read_u64, write_u64;
+        i32: read_i32, write_i32;
+        i64: read_i64, write_i64;
+        f32: read_f32, write_f32;
+        f64: read_f64, write_f64;
+    }
+}
+
+/// A copy of the linear memory of a [`Runtime`], taken with [`Runtime::snapshot_memory`].
+///
+/// [`Runtime`]: struct.Runtime.html
+/// [`Runtime::snapshot_memory`]: struct.Runtime.html#method.snapshot_memory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemorySnapshot {
+    data: Vec<u8>,
+    pages: u32,
+}
+
+impl MemorySnapshot {
+    pub(crate) fn new(data: Vec<u8>, pages: u32) -> Self {
+        MemorySnapshot { data, pages }
+    }
+
+    /// The contents of the memory at the time the snapshot was taken.
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// The number of 64KiB pages the memory had at the time the snapshot was taken.
+    #[inline]
+    pub fn pages(&self) -> u32 {
+        self.pages
+    }
+}
+
+/// Returns the range of an access of `len` bytes at `offset` if it lies within a memory of `memory_len` bytes.
+pub(crate) fn checked_range(memory_len: usize, offset: usize, len: usize) -> Result<Range<usize>> {
+    match offset.checked_add(len) {
+        Some(end) if end <= memory_len => Ok(offset..end),
+        _ => Err(Error::OutOfBoundsMemoryAccess { offset, len }),
+    }
+}
+
+/// Reads a UTF-8 string of `len` bytes at `offset`.
+pub(crate) fn read_str(memory: &[u8], offset: usize, len: usize) -> Result<&str> {
+    checked_range(memory.len(), offset, len)
+        .and_then(|range| str::from_utf8(&memory[range]).map_err(Error::InvalidUtf8))
+}
+
+/// Reads a string of `len` bytes at `offset`, replacing invalid UTF-8 sequences.
+pub(crate) fn read_str_lossy(memory: &[u8], offset: usize, len: usize) -> Result<Cow<'_, str>> {
+    checked_range(memory.len(), offset, len).map(|range| String::from_utf8_lossy(&memory[range]))
+}
+
+/// Returns the bytes of the nul-terminated string at `offset`, excluding the terminator.
+/// The search for the terminator stops at the end of memory.
+fn cstr_bytes(memory: &[u8], offset: usize) -> Result<&[u8]> {
+    let tail = memory.get(offset..).unwrap_or_default();
+    tail.iter()
+        .position(|&byte| byte == 0)
+        .map(|len| &tail[..len])
+        .ok_or(Error::OutOfBoundsMemoryAccess {
+            offset,
+            len: tail.len() + 1,
+        })
+}
+
+/// Reads a nul-terminated UTF-8 string at `offset`, excluding the terminator.
+pub(crate) fn read_cstr(memory: &[u8], offset: usize) -> Result<&str> {
+    cstr_bytes(memory, offset).and_then(|bytes| str::from_utf8(bytes).map_err(Error::InvalidUtf8))
+}
+
+/// Reads a nul-terminated string at `offset`, replacing invalid UTF-8 sequences.
+pub(crate) fn read_cstr_lossy(memory: &[u8], offset: usize) -> Result<Cow<'_, str>> {
+    cstr_bytes(memory, offset).map(String::from_utf8_lossy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_view_roundtrip() {
+        let mut memory = [0; 32];
+        let mut view = MemoryView::new(&mut memory);
+        view.write_u8(0, 0xAB).unwrap();
+        view.write_u16(1, 0xBEEF).unwrap();
+        view.write_u32(3, 0xDEAD_BEEF).unwrap();
+        view.write_u64(7, 0x0123_4567_89AB_CDEF).unwrap();
+        view.write_i32(15, -2).unwrap();
+        view.write_f32(19, 1.5).unwrap();
+        view.write_f64(23, -0.25).unwrap();
+        assert_eq!(view.read_u8(0), Ok(0xAB));
+        assert_eq!(view.read_u16(1), Ok(0xBEEF));
+        assert_eq!(view.read_u32(3), Ok(0xDEAD_BEEF));
+        assert_eq!(view.read_u64(7), Ok(0x0123_4567_89AB_CDEF));
+        assert_eq!(view.read_i32(15), Ok(-2));
+        assert_eq!(view.read_f32(19), Ok(1.5));
+        assert_eq!(view.read_f64(23), Ok(-0.25));
+    }
+
+    #[test]
+    fn test_memory_view_read_into() {
+        let mut memory = *b"hello, wasm";
+        let view = MemoryView::new(&mut memory);
+        let mut dst = [0; 4];
+        view.read_into(7, &mut dst).unwrap();
+        assert_eq!(&dst, b"wasm");
+        assert!(view.read_into(8, &mut dst).is_err());
+        assert_eq!(&dst, b"wasm");
+    }
+
+    #[test]
+    fn test_memory_view_str() {
+        let mut memory = [0; 8];
+        let mut view = MemoryView::new(&mut memory);
+        view.write_str(2, "wasm").unwrap();
+        assert_eq!(view.read_str(2, 4), Ok("wasm"));
+        assert!(view.write_str(6, "wasm").is_err());
+        assert_eq!(view.read_bytes(6, 2), Ok(&[0, 0][..]));
+    }
+
+    #[test]
+    fn test_memory_view_little_endian() {
+        let mut memory = [0; 4];
+        let mut view = MemoryView::new(&mut memory);
+        view.write_u32(0, 0x0403_0201).unwrap();
+        assert_eq!(memory, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_memory_view_out_of_bounds() {
+        let mut memory = [0; 8];
+        let mut view = MemoryView::new(&mut memory);
+        assert_eq!(
+            view.read_u64(1),
+            Err(Error::OutOfBoundsMemoryAccess { offset: 1, len: 8 })
+        );
+        assert_eq!(
+            view.write_u32(usize::MAX, 0),
+            Err(Error::OutOfBoundsMemoryAccess {
+                offset: usize::MAX,
+                len: 4
+            })
+        );
+        assert_eq!(view.read_u64(0), Ok(0));
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    #[repr(C)]
+    struct Particle {
+        id: u32,
+        mass: f32,
+    }
+
+    #[cfg(feature = "bytemuck")]
+    unsafe impl bytemuck::Zeroable for Particle {}
+    #[cfg(feature = "bytemuck")]
+    unsafe impl bytemuck::Pod for Particle {}
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_memory_view_slice_of() {
+        #[repr(align(8))]
+        struct Memory([u8; 32]);
+
+        let particles = [
+            Particle { id: 1, mass: 0.5 },
+            Particle { id: 2, mass: -3.0 },
+        ];
+        let mut memory = Memory([0; 32]);
+        let mut view = MemoryView::new(&mut memory.0);
+        // aligned reads borrow, unaligned ones copy
+        for &(offset, borrowed) in &[(8, true), (3, false)] {
+            view.write_slice_of(offset, &particles).unwrap();
+            let read = view.read_slice_of::<Particle>(offset, 2).unwrap();
+            assert_eq!(&*read, &particles[..]);
+            assert_eq!(matches!(read, Cow::Borrowed(_)), borrowed);
+        }
+        assert_eq!(view.read_u32(3), Ok(1));
+        assert_eq!(view.read_f32(15), Ok(-3.0));
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_memory_view_slice_of_out_of_bounds() {
+        let mut memory = [0; 16];
+        let mut view = MemoryView::new(&mut memory);
+        assert_eq!(
+            view.read_slice_of::<Particle>(8, 2),
+            Err(Error::OutOfBoundsMemoryAccess { offset: 8, len: 16 })
+        );
+        assert_eq!(
+            view.read_slice_of::<Particle>(0, usize::MAX),
+            Err(Error::OutOfBoundsMemoryAccess {
+                offset: 0,
+                len: usize::MAX
+            })
+        );
+        assert_eq!(
+            view.write_slice_of(12, &[Particle { id: 0, mass: 0.0 }]),
+            Err(Error::OutOfBoundsMemoryAccess { offset: 12, len: 8 })
+        );
+        assert_eq!(
+            view.read_slice_of::<Particle>(16, 0).as_deref(),
+            Ok(&[][..])
+        );
+    }
+
+    #[test]
+    fn test_read_cstr_unterminated() {
+        let memory = *b"abc\0def";
+        assert_eq!(read_cstr(&memory, 0), Ok("abc"));
+        assert_eq!(read_cstr(&memory, 3), Ok(""));
+        assert_eq!(
+            read_cstr(&memory, 4),
+            Err(Error::OutOfBoundsMemoryAccess { offset: 4, len: 5 })
+        );
+        assert_eq!(
+            read_cstr(&memory, 9),
+            Err(Error::OutOfBoundsMemoryAccess { offset: 9, len: 1 })
+        );
+    }
+
+    #[test]
+    fn test_read_str_invalid_utf8() {
+        let memory = *b"a\xFFb\0";
+        assert!(matches!(
+            read_str(&memory, 0, 3),
+            Err(Error::InvalidUtf8(_))
+        ));
+        assert!(matches!(read_cstr(&memory, 0), Err(Error::InvalidUtf8(_))));
+        assert_eq!(read_str_lossy(&memory, 0, 3).as_deref(), Ok("a\u{FFFD}b"));
+        assert_eq!(read_cstr_lossy(&memory, 0).as_deref(), Ok("a\u{FFFD}b"));
+    }
+}
@@ -0,0 +1,321 @@
+//! Fuel metering, hooked into wasm3 by overriding its weak `m3_Yield` function which it calls on
+//! every function call.
+//!
+//! Loops do not necessarily call any functions, so modules are instrumented before they are
+//! parsed: a call to an empty function is inserted at the start of every loop, which charges one
+//! unit of fuel per iteration. The function is appended to the module's functions so that the
+//! indices of all others stay the same, and a custom section placed in front of all other sections
+//! marks the module as instrumented so that the function can be hidden again.
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ptr;
+use core::slice;
+
+use crate::error::{Error, Result};
+use crate::module::{import_kinds, read_leb_u32, split_section};
+
+/// The trap message returned by `m3_Yield` once the fuel has been used up.
+/// Its address identifies the trap when it gets returned from a call.
+pub(crate) static OUT_OF_FUEL: [u8; 12] = *b"out of fuel\0";
+
+/// The name of the custom section marking a module as instrumented.
+pub(crate) const MARKER_NAME: &str = "wasm3-rs:metering";
+
+/// The custom section marking a module as instrumented, including its id and size.
+const MARKER: [u8; 20] = *b"\x00\x12\x11wasm3-rs:metering";
+
+/// The body of the function called at the start of every loop, including its size.
+const TICK_BODY: [u8; 3] = [0x02, 0x00, 0x0b];
+
+std::thread_local! {
+    // the fuel of the runtime currently executing on this thread, charged in place so that the
+    // runtime always sees how much of it is left, even from within a linked closure
+    static FUEL: Cell<*const Cell<u64>> = Cell::new(ptr::null());
+}
+
+/// Runs `f` with `fuel` installed as the fuel of the current thread. The previously installed fuel
+/// is restored afterwards, even if `f` panics, so that nested calls into other runtimes are
+/// metered separately.
+pub(crate) fn with_fuel<R>(fuel: &Cell<u64>, f: impl FnOnce() -> R) -> R {
+    struct Restore(*const Cell<u64>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FUEL.with(|current| current.set(self.0));
+        }
+    }
+
+    let _restore = Restore(FUEL.with(|current| current.replace(fuel)));
+    f()
+}
+
+#[no_mangle]
+#[allow(non_snake_case)]
+extern "C" fn m3_Yield() -> ffi::M3Result {
+    let fuel = FUEL.with(Cell::get);
+    // calls are only made with a runtime's fuel installed, and the runtime outlives them
+    match unsafe { fuel.as_ref() }.map(Cell::get) {
+        Some(0) => OUT_OF_FUEL.as_ptr().cast(),
+        Some(left) => {
+            unsafe { (*fuel).set(left - 1) };
+            ptr::null()
+        }
+        None => ptr::null(),
+    }
+}
+
+/// Returns whether the given module has been instrumented by [`instrument`], in which case its
+/// last function is the one called at the start of every loop.
+pub(crate) fn is_instrumented(module: &ffi::M3Module) -> bool {
+    if module.wasmStart.is_null() {
+        return false;
+    }
+    let bytes = unsafe {
+        slice::from_raw_parts(
+            module.wasmStart,
+            module.wasmEnd.offset_from(module.wasmStart) as usize,
+        )
+    };
+    bytes.get(8..8 + MARKER.len()) == Some(&MARKER[..])
+}
+
+/// Instruments the given wasm binary so that every loop iteration consumes fuel, returning `None`
+/// if it does not contain any loops.
+///
+/// # Errors
+///
+/// This function will error if the binary is malformed or its code uses an unknown instruction.
+pub(crate) fn instrument(module: &[u8]) -> Result<Option<Vec<u8>>> {
+    instrument_impl(module).ok_or_else(Error::malformed_error)
+}
+
+fn instrument_impl(module: &[u8]) -> Option<Option<Vec<u8>>> {
+    let (header, mut bytes) = (module.get(..8)?, &module[8..]);
+    let mut sections = Vec::new();
+    let mut marked = false;
+    while !bytes.is_empty() {
+        let section = split_section(&mut bytes)?;
+        // drop existing markers so that only modules instrumented here are considered as such
+        if section == (0, &MARKER[2..]) {
+            marked = true;
+        } else {
+            sections.push(section);
+        }
+    }
+    let payload = |id| {
+        sections
+            .iter()
+            .find(|&&(sid, _)| sid == id)
+            .map(|&(_, p)| p)
+    };
+    let (types, funcs, code) = match (payload(1), payload(3), payload(10)) {
+        (Some(types), Some(funcs), Some(code)) => (types, funcs, code),
+        _ => return Some(marked.then(|| without_markers(header, &sections))),
+    };
+    let tick = imported_functions(payload(2).unwrap_or(&[]))?
+        .checked_add(read_leb_u32(&mut &funcs[..])?)?;
+
+    let mut bodies = code;
+    let num_bodies = read_leb_u32(&mut bodies)?;
+    let mut new_code = Vec::with_capacity(code.len() + TICK_BODY.len());
+    write_leb_u32(&mut new_code, num_bodies.checked_add(1)?);
+    let mut loops = false;
+    let mut body = Vec::new();
+    for _ in 0..num_bodies {
+        let len = read_leb_u32(&mut bodies)? as usize;
+        body.clear();
+        loops |= instrument_body(bodies.get(..len)?, tick, &mut body)?;
+        bodies = &bodies[len..];
+        write_leb_u32(&mut new_code, body.len() as u32);
+        new_code.extend_from_slice(&body);
+    }
+    if !bodies.is_empty() {
+        return None;
+    }
+    if !loops {
+        return Some(marked.then(|| without_markers(header, &sections)));
+    }
+    new_code.extend_from_slice(&TICK_BODY);
+
+    let (mut types, mut funcs) = (types, funcs);
+    let num_types = read_leb_u32(&mut types)?;
+    let mut new_types = Vec::with_capacity(types.len() + 8);
+    write_leb_u32(&mut new_types, num_types.checked_add(1)?);
+    new_types.extend_from_slice(types);
+    // the type of the tick function, () -> ()
+    new_types.extend_from_slice(&[0x60, 0x00, 0x00]);
+    let num_funcs = read_leb_u32(&mut funcs)?;
+    let mut new_funcs = Vec::with_capacity(funcs.len() + 8);
+    write_leb_u32(&mut new_funcs, num_funcs.checked_add(1)?);
+    new_funcs.extend_from_slice(funcs);
+    write_leb_u32(&mut new_funcs, num_types);
+
+    let mut out = Vec::with_capacity(module.len() + MARKER.len() + 32);
+    out.extend_from_slice(header);
+    out.extend_from_slice(&MARKER);
+    for &(id, payload) in &sections {
+        let payload = match id {
+            1 => &new_types[..],
+            3 => &new_funcs[..],
+            10 => &new_code[..],
+            _ => payload,
+        };
+        write_section(&mut out, id, payload);
+    }
+    Some(Some(out))
+}
+
+/// Reassembles a module from its header and sections, which no longer contain a marker.
+fn without_markers(header: &[u8], sections: &[(u8, &[u8])]) -> Vec<u8> {
+    let mut out = header.to_vec();
+    for &(id, payload) in sections {
+        write_section(&mut out, id, payload);
+    }
+    out
+}
+
+fn write_section(out: &mut Vec<u8>, id: u8, payload: &[u8]) {
+    out.push(id);
+    write_leb_u32(out, payload.len() as u32);
+    out.extend_from_slice(payload);
+}
+
+/// Returns the number of functions the given import section imports.
+fn imported_functions(imports: &[u8]) -> Option<u32> {
+    if imports.is_empty() {
+        return Some(0);
+    }
+    let kinds = import_kinds(imports)?;
+    Some(kinds.iter().filter(|&&kind| kind == 0x00).count() as u32)
+}
+
+/// Copies the given function body to `out`, inserting a call to `tick` at the start of every
+/// loop. Returns whether the body contains any loops.
+fn instrument_body(body: &[u8], tick: u32, out: &mut Vec<u8>) -> Option<bool> {
+    let mut bytes = body;
+    for _ in 0..read_leb_u32(&mut bytes)? {
+        read_leb_u32(&mut bytes)?;
+        bytes = bytes.get(1..)?;
+    }
+    out.extend_from_slice(&body[..body.len() - bytes.len()]);
+    let mut loops = false;
+    while let Some((&op, rest)) = bytes.split_first() {
+        let start = bytes;
+        bytes = rest;
+        skip_immediates(op, &mut bytes)?;
+        out.extend_from_slice(&start[..start.len() - bytes.len()]);
+        if op == 0x03 {
+            out.push(0x10);
+            write_leb_u32(out, tick);
+            loops = true;
+        }
+    }
+    Some(loops)
+}
+
+/// Skips the immediates of the instruction with the given opcode.
+fn skip_immediates(op: u8, bytes: &mut &[u8]) -> Option<()> {
+    match op {
+        // unreachable, nop, else, end, return, drop, select, ref.is_null and numeric instructions
+        0x00 | 0x01 | 0x05 | 0x0b | 0x0f | 0x1a | 0x1b | 0xd1 | 0x45..=0xc4 => {}
+        // block, loop and if
+        0x02..=0x04 => match bytes.first()? {
+            0x40 | 0x6f | 0x70 | 0x7b..=0x7f => *bytes = &bytes[1..],
+            // a type index as a signed 33-bit integer
+            _ => skip_leb(bytes)?,
+        },
+        // br, br_if, call, return_call, local and global accesses, table.get, table.set,
+        // memory.size, memory.grow and ref.func
+        0x0c | 0x0d | 0x10 | 0x12 | 0x20..=0x26 | 0x3f | 0x40 | 0xd2 => skip_leb(bytes)?,
+        0x0e => {
+            for _ in 0..read_leb_u32(bytes)? {
+                skip_leb(bytes)?;
+            }
+            skip_leb(bytes)?;
+        }
+        // call_indirect and return_call_indirect
+        0x11 | 0x13 => {
+            skip_leb(bytes)?;
+            skip_leb(bytes)?;
+        }
+        // select with explicit value types
+        0x1c => {
+            let len = read_leb_u32(bytes)? as usize;
+            *bytes = bytes.get(len..)?;
+        }
+        // loads and stores
+        0x28..=0x3e => {
+            skip_leb(bytes)?;
+            skip_leb(bytes)?;
+        }
+        0x41 | 0x42 => skip_leb(bytes)?,
+        0x43 => *bytes = bytes.get(4..)?,
+        0x44 => *bytes = bytes.get(8..)?,
+        // ref.null
+        0xd0 => *bytes = bytes.get(1..)?,
+        0xfc => match read_leb_u32(bytes)? {
+            // saturating truncations
+            0..=7 => {}
+            // data.drop, memory.fill, elem.drop, table.grow, table.size and table.fill
+            9 | 11 | 13 | 15..=17 => skip_leb(bytes)?,
+            // memory.init, memory.copy, table.init and table.copy
+            8 | 10 | 12 | 14 => {
+                skip_leb(bytes)?;
+                skip_leb(bytes)?;
+            }
+            _ => return None,
+        },
+        _ => return None,
+    }
+    Some(())
+}
+
+/// Skips a LEB128 encoded integer of up to 64 bits.
+fn skip_leb(bytes: &mut &[u8]) -> Option<()> {
+    let len = bytes.iter().take(10).position(|&byte| byte & 0x80 == 0)? + 1;
+    *bytes = &bytes[len..];
+    Some(())
+}
+
+fn write_leb_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instrument() {
+        // (module (func (loop (br 0))))
+        let module = [
+            0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+            0x03, 0x02, 0x01, 0x00, 0x0a, 0x09, 0x01, 0x07, 0x00, 0x03, 0x40, 0x0c, 0x00, 0x0b,
+            0x0b,
+        ];
+        let mut expected = module[..8].to_vec();
+        expected.extend_from_slice(&MARKER);
+        expected.extend_from_slice(&[
+            0x01, 0x07, 0x02, 0x60, 0x00, 0x00, 0x60, 0x00, 0x00, 0x03, 0x03, 0x02, 0x00, 0x01,
+            0x0a, 0x0e, 0x02, 0x09, 0x00, 0x03, 0x40, 0x10, 0x01, 0x0c, 0x00, 0x0b, 0x0b, 0x02,
+            0x00, 0x0b,
+        ]);
+        assert_eq!(instrument(&module), Ok(Some(expected.clone())));
+        // instrumenting twice only inserts another call, keeping a single marker
+        let twice = instrument(&expected).unwrap().unwrap();
+        assert_eq!(
+            twice.windows(MARKER.len()).filter(|&w| w == MARKER).count(),
+            1
+        );
+        // modules without loops are left alone
+        assert_eq!(instrument(&module[..18]), Ok(None));
+        assert!(instrument(&module[..27]).is_err());
+    }
+}
@@ -1,53 +1,407 @@
 use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
 use core::mem;
 use core::ptr::{self, NonNull};
 use core::slice;
+use core::str;
 
 use crate::environment::Environment;
-use crate::error::{Error, Result, Trap};
-use crate::function::{CallContext, Function, NNM3Function, RawCall};
+use crate::error::{Error, Result, Trap, HOST_FUNCTION_FAILED};
+use crate::function::{
+    self, CallContext, DynFunction, Function, FunctionSignature, NNM3Function, RawCall, RawCallEx,
+    SignatureStr,
+};
+use crate::global::Global;
 use crate::runtime::Runtime;
-use crate::utils::{cstr_to_str, eq_cstr_str};
+use crate::utils::{cstr_to_str, eq_cstr_bytes, eq_cstr_str};
+use crate::value::ValueType;
+#[cfg(feature = "wasi")]
+use crate::wasi::WasiConfig;
 use crate::wasm3_priv;
 
+/// The name wasm3 gives to modules that have not been named.
+const UNNAMED_MODULE: &str = ".unnamed";
+
+/// Checks that a module of `len` bytes can be handed to wasm3, which takes its length as a `u32`.
+fn module_len(len: usize) -> Result<u32> {
+    u32::try_from(len).map_err(|_| Error::ModuleTooLarge)
+}
+
 /// A parsed module which can be loaded into a [`Runtime`].
 pub struct ParsedModule {
     data: Box<[u8]>,
+    // the nul terminated name set via set_name, wasm3 only keeps a pointer to it
+    name: Option<Box<[u8]>>,
     raw: ffi::IM3Module,
     env: Environment,
 }
 
 impl ParsedModule {
     /// Parses a wasm module from raw bytes.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the module is larger than 4GiB or wasm3 failed to parse it.
     pub fn parse<TData: Into<Box<[u8]>>>(env: &Environment, data: TData) -> Result<Self> {
         let data = data.into();
-        assert!(data.len() <= !0u32 as usize);
-        let mut module = ptr::null_mut();
-        let res = unsafe {
-            ffi::m3_ParseModule(env.as_ptr(), &mut module, data.as_ptr(), data.len() as u32)
+        #[cfg(feature = "metering")]
+        let data = match crate::metering::instrument(&data)? {
+            Some(instrumented) => instrumented.into_boxed_slice(),
+            None => data,
         };
+        let len = module_len(data.len())?;
+        let mut module = ptr::null_mut();
+        let res = unsafe { ffi::m3_ParseModule(env.as_ptr(), &mut module, data.as_ptr(), len) };
         Error::from_ffi_res(res).map(|_| ParsedModule {
             data,
+            name: None,
             raw: module,
             env: env.clone(),
         })
     }
 
+    /// Reads a wasm module from `reader` until it is exhausted and parses it.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if reading failed, the module is larger than 4GiB or wasm3
+    /// failed to parse it.
+    #[cfg(feature = "std")]
+    pub fn parse_from_reader<R: std::io::Read>(env: &Environment, mut reader: R) -> Result<Self> {
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|err| Error::Io(err.kind()))?;
+        Self::parse(env, data)
+    }
+
     pub(crate) fn as_ptr(&self) -> ffi::IM3Module {
         self.raw
     }
 
-    pub(crate) fn take_data(self) -> Box<[u8]> {
-        let res = unsafe { ptr::read(&self.data) };
+    pub(crate) fn take_data(self) -> (Box<[u8]>, Option<Box<[u8]>>) {
+        let res = unsafe { (ptr::read(&self.data), ptr::read(&self.name)) };
+        // the module itself is owned by the runtime now, but the environment still has to be released
+        drop(unsafe { ptr::read(&self.env) });
         mem::forget(self);
         res
     }
 
+    /// The name of this module.
+    pub fn name(&self) -> &str {
+        unsafe { cstr_to_str((*self.raw).name) }
+    }
+
+    /// Renames this module, which changes the name it can be found by with
+    /// [`Runtime::find_module`] once it has been loaded.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the name contains a nul byte.
+    ///
+    /// [`Runtime::find_module`]: struct.Runtime.html#method.find_module
+    pub fn set_name(&mut self, name: &str) -> Result<()> {
+        if name.as_bytes().contains(&0) {
+            return Err(Error::InvalidModuleName);
+        }
+        let mut buf = Vec::with_capacity(name.len() + 1);
+        buf.extend_from_slice(name.as_bytes());
+        buf.push(0);
+        let buf = buf.into_boxed_slice();
+        unsafe { (*self.raw).name = buf.as_ptr().cast() };
+        // the previous name is only dropped after wasm3 no longer points to it
+        self.name = Some(buf);
+        Ok(())
+    }
+
     /// The environment this module was parsed in.
     pub fn environment(&self) -> &Environment {
         &self.env
     }
+
+    /// Returns an iterator over the functions this module imports.
+    /// This allows checking whether all imports can be satisfied before loading the module.
+    pub fn imports(&self) -> impl Iterator<Item = ImportDescriptor<'_>> + '_ {
+        unsafe { functions_raw(self.raw) }
+            .map(|func| unsafe { &*func.as_ptr() })
+            .filter(|func| !func.import.moduleUtf8.is_null())
+            .map(|func| unsafe {
+                ImportDescriptor {
+                    module_name: cstr_to_str(func.import.moduleUtf8),
+                    field_name: cstr_to_str(func.import.fieldUtf8),
+                    signature: SignatureStr::from_func_type(&*func.funcType),
+                }
+            })
+    }
+
+    /// Checks this module without loading it into a runtime, returning a summary of its contents.
+    ///
+    /// Parsing already validates the module's structure, but wasm3 compiles function bodies
+    /// lazily, so these are only validated once the module has been loaded and the respective
+    /// function is looked up.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a function of the module has a signature with a
+    /// type this crate does not support.
+    pub fn validate(&self) -> Result<ValidationReport> {
+        let globals = unsafe { globals_raw(self.raw) };
+        let module = unsafe { &*self.raw };
+
+        let mut report = ValidationReport {
+            functions: unsafe { functions_raw(self.raw) }.count(),
+            imports: globals.iter().filter(|global| global.imported).count()
+                + module.memoryImported as usize,
+            exports: globals
+                .iter()
+                .filter(|global| !global.name.is_null())
+                .count(),
+            globals: globals.len(),
+            memories: memory_count(&self.data),
+        };
+        for func in unsafe { functions_raw(self.raw) } {
+            let func = unsafe { func.as_ref() };
+            let func_type = unsafe { &*func.funcType };
+            ValueType::from_type_indices(unsafe {
+                slice::from_raw_parts(func_type.argTypes.as_ptr(), func_type.numArgs as usize)
+            })
+            .ok_or(Error::InvalidFunctionSignature)?;
+            if func_type.returnType != ffi::_bindgen_ty_1::c_m3Type_none as u8
+                && ValueType::from_type_index(func_type.returnType).is_none()
+            {
+                return Err(Error::InvalidFunctionSignature);
+            }
+            if !func.import.moduleUtf8.is_null() {
+                report.imports += 1;
+            } else if is_exported(func) {
+                report.exports += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Returns an iterator over the names and payloads of this module's custom sections, in the
+    /// order they appear in the module.
+    ///
+    /// wasm3 skips custom sections while parsing, so these are read from the bytes the module was
+    /// parsed from. Custom sections whose name is not valid UTF-8 are skipped.
+    pub fn custom_sections(&self) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+        custom_sections(&self.data)
+    }
+}
+
+/// Returns the number of memories the given wasm binary declares or imports, stopping at the first
+/// malformed section.
+///
+/// This is read from the binary as wasm3 records a memory by its maximum size, which may be zero.
+fn memory_count(module: &[u8]) -> usize {
+    // skip the magic number and version
+    let mut bytes = module.get(8..).unwrap_or_default();
+    core::iter::from_fn(move || split_section(&mut bytes))
+        .map(|(id, mut payload)| match id {
+            2 => import_kinds(payload)
+                .map_or(0, |kinds| kinds.iter().filter(|&&kind| kind == 2).count()),
+            5 => read_leb_u32(&mut payload).unwrap_or(0) as usize,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Returns the kinds of the imports of the given import section, `None` if it is malformed.
+pub(crate) fn import_kinds(mut imports: &[u8]) -> Option<Vec<u8>> {
+    fn skip_limits(bytes: &mut &[u8]) -> Option<()> {
+        let (&flags, rest) = bytes.split_first()?;
+        *bytes = rest;
+        read_leb_u32(bytes)?;
+        if flags & 0x01 != 0 {
+            read_leb_u32(bytes)?;
+        }
+        Some(())
+    }
+
+    let mut kinds = Vec::new();
+    for _ in 0..read_leb_u32(&mut imports)? {
+        // the module and field names
+        for _ in 0..2 {
+            let len = read_leb_u32(&mut imports)? as usize;
+            imports = imports.get(len..)?;
+        }
+        let (&kind, rest) = imports.split_first()?;
+        imports = rest;
+        match kind {
+            // a function's type index
+            0x00 => {
+                read_leb_u32(&mut imports)?;
+            }
+            // a table's element type followed by its limits
+            0x01 => {
+                imports = imports.get(1..)?;
+                skip_limits(&mut imports)?;
+            }
+            0x02 => skip_limits(&mut imports)?,
+            // a global's value type and mutability
+            0x03 => imports = imports.get(2..)?,
+            _ => return None,
+        }
+        kinds.push(kind);
+    }
+    Some(kinds)
+}
+
+/// Returns an iterator over the custom sections of the given wasm binary, stopping at the first
+/// malformed section.
+fn custom_sections(module: &[u8]) -> impl Iterator<Item = (&str, &[u8])> + '_ {
+    // skip the magic number and version
+    let mut bytes = module.get(8..).unwrap_or_default();
+    let sections = core::iter::from_fn(move || split_section(&mut bytes))
+        .filter(|&(id, _)| id == 0)
+        .filter_map(|(_, mut payload)| {
+            let len = read_leb_u32(&mut payload)? as usize;
+            let name = str::from_utf8(payload.get(..len)?).ok()?;
+            Some((name, &payload[len..]))
+        });
+    // the marker of a module instrumented for metering is an implementation detail
+    #[cfg(feature = "metering")]
+    let sections = sections.filter(|&(name, _)| name != crate::metering::MARKER_NAME);
+    sections
+}
+
+/// Returns the module name recorded in the name section of the given wasm binary, stopping at the
+/// first malformed section or subsection.
+fn name_section_module_name(module: &[u8]) -> Option<&str> {
+    let (_, mut payload) = custom_sections(module).find(|&(name, _)| name == "name")?;
+    while let Some((id, mut subsection)) = split_section(&mut payload) {
+        // the module name subsection
+        if id == 0 {
+            let len = read_leb_u32(&mut subsection)? as usize;
+            return str::from_utf8(subsection.get(..len)?).ok();
+        }
+    }
+    None
+}
+
+/// Returns the name the given wasm binary exports its memory under, stopping at the first
+/// malformed section or export.
+fn memory_export_name(module: &[u8]) -> Option<&str> {
+    let mut bytes = module.get(8..).unwrap_or_default();
+    let (_, mut payload) =
+        core::iter::from_fn(|| split_section(&mut bytes)).find(|&(id, _)| id == 7)?;
+    let count = read_leb_u32(&mut payload)?;
+    for _ in 0..count {
+        let len = read_leb_u32(&mut payload)? as usize;
+        let name = payload.get(..len)?;
+        let (&kind, mut rest) = payload[len..].split_first()?;
+        read_leb_u32(&mut rest)?;
+        payload = rest;
+        // the external kind of memories
+        if kind == 2 {
+            return str::from_utf8(name).ok();
+        }
+    }
+    None
+}
+
+/// Splits the next section off the front of `bytes`, returning its id and payload.
+pub(crate) fn split_section<'a>(bytes: &mut &'a [u8]) -> Option<(u8, &'a [u8])> {
+    let (&id, mut rest) = bytes.split_first()?;
+    let len = read_leb_u32(&mut rest)? as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (payload, rest) = rest.split_at(len);
+    *bytes = rest;
+    Some((id, payload))
+}
+
+/// Reads an unsigned LEB128 encoded `u32` off the front of `bytes`.
+pub(crate) fn read_leb_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let mut res = 0;
+    for shift in (0..32).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        res |= u32::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            return Some(res);
+        }
+    }
+    None
+}
+
+/// A summary of the contents of a [`ParsedModule`], as returned by [`ParsedModule::validate`].
+///
+/// [`ParsedModule::validate`]: struct.ParsedModule.html#method.validate
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ValidationReport {
+    /// The number of functions, including imported ones.
+    pub functions: usize,
+    /// The number of imported functions, globals and memories.
+    pub imports: usize,
+    /// The number of exported functions and globals.
+    pub exports: usize,
+    /// The number of globals, including imported ones.
+    pub globals: usize,
+    /// The number of memories, either zero or one.
+    pub memories: usize,
+}
+
+/// Metadata of a [`ParsedModule`], which is available without loading the module into a
+/// [`Runtime`].
+///
+/// Unlike [`ParsedModule::validate`] this does not check the module's function signatures.
+///
+/// [`ParsedModule::validate`]: struct.ParsedModule.html#method.validate
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ModuleInfo {
+    /// The name given to the module via [`ParsedModule::set_name`], or else the module name
+    /// recorded in its name section. `None` if it has not been named either way.
+    ///
+    /// [`ParsedModule::set_name`]: struct.ParsedModule.html#method.set_name
+    pub name: Option<String>,
+    /// The number of functions, including imported ones.
+    pub num_functions: usize,
+    /// The number of imported functions, globals and memories.
+    pub num_imports: usize,
+    /// The number of globals, including imported ones.
+    pub num_globals: usize,
+    /// The number of memories, either zero or one.
+    pub num_memories: usize,
+}
+
+impl From<&ParsedModule> for ModuleInfo {
+    fn from(module: &ParsedModule) -> Self {
+        let globals = unsafe { globals_raw(module.raw) };
+        let raw = unsafe { &*module.raw };
+        ModuleInfo {
+            name: Some(module.name())
+                .filter(|&name| name != UNNAMED_MODULE)
+                .or_else(|| name_section_module_name(&module.data))
+                .map(Into::into),
+            num_functions: unsafe { functions_raw(module.raw) }.count(),
+            num_imports: unsafe { functions_raw(module.raw) }
+                .filter(|func| unsafe { !func.as_ref().import.moduleUtf8.is_null() })
+                .count()
+                + globals.iter().filter(|global| global.imported).count()
+                + raw.memoryImported as usize,
+            num_globals: globals.len(),
+            num_memories: memory_count(&module.data),
+        }
+    }
+}
+
+/// A function import declared by a [`ParsedModule`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImportDescriptor<'m> {
+    /// The name of the module the function is imported from.
+    pub module_name: &'m str,
+    /// The name of the imported function.
+    pub field_name: &'m str,
+    /// The signature the imported function is expected to have.
+    pub signature: SignatureStr,
 }
 
 impl Drop for ParsedModule {
@@ -56,7 +410,140 @@ impl Drop for ParsedModule {
     }
 }
 
+/// A function exported by a loaded [`Module`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ExportedFunction<'rt> {
+    name: &'rt str,
+    index: usize,
+}
+
+impl<'rt> ExportedFunction<'rt> {
+    /// The name this function is exported as.
+    pub fn name(&self) -> &'rt str {
+        self.name
+    }
+
+    /// The index of this function in its module.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+/// Information about a function exported by a loaded [`Module`].
+#[derive(Copy, Clone)]
+pub struct FunctionInfo<'rt> {
+    // the function may be linked to while this is alive, so it is only borrowed per access
+    raw: NNM3Function,
+    index: usize,
+    _pd: PhantomData<&'rt ffi::M3Function>,
+}
+
+impl<'rt> FunctionInfo<'rt> {
+    /// The name this function is exported as.
+    pub fn name(&self) -> Option<&'rt str> {
+        function::function_name(self.raw())
+    }
+
+    /// The index of this function in its module.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The types of this function's arguments.
+    pub fn arg_types(&self) -> &'rt [ValueType] {
+        function::arg_types(self.raw())
+    }
+
+    /// The type of this function's return value, `None` if it does not return anything.
+    pub fn ret_type(&self) -> Option<ValueType> {
+        function::ret_type(self.raw())
+    }
+
+    /// The parameter and return types of this function.
+    pub fn signature(&self) -> FunctionSignature {
+        FunctionSignature::of(self.raw())
+    }
+
+    fn raw(&self) -> &'rt ffi::M3Function {
+        // the fields read through this are not touched by linking
+        unsafe { &*self.raw.as_ptr() }
+    }
+}
+
+impl<'rt> fmt::Debug for FunctionInfo<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FunctionInfo")
+            .field("name", &self.name())
+            .field("index", &self.index)
+            .field("arg_types", &self.arg_types())
+            .field("ret_type", &self.ret_type())
+            .finish()
+    }
+}
+
+/// A function import of a loaded [`Module`].
+#[derive(Copy, Clone)]
+pub struct ImportedFunction<'rt> {
+    // the import may be linked while this is alive, so it is only read through the pointer
+    raw: NNM3Function,
+    _pd: PhantomData<&'rt ffi::M3Function>,
+}
+
+impl<'rt> ImportedFunction<'rt> {
+    fn from_raw(raw: NNM3Function) -> Self {
+        ImportedFunction {
+            raw,
+            _pd: PhantomData,
+        }
+    }
+
+    /// The name of the module the function is imported from.
+    pub fn module_name(&self) -> &'rt str {
+        unsafe { cstr_to_str((*self.raw.as_ptr()).import.moduleUtf8) }
+    }
+
+    /// The name of the imported function.
+    pub fn field_name(&self) -> &'rt str {
+        unsafe { cstr_to_str((*self.raw.as_ptr()).import.fieldUtf8) }
+    }
+
+    /// The signature the imported function is expected to have.
+    pub fn signature(&self) -> SignatureStr {
+        SignatureStr::from_func_type(unsafe { &*(*self.raw.as_ptr()).funcType })
+    }
+
+    /// Whether a function has been linked to this import.
+    /// Calling into a function that calls an unlinked import fails.
+    pub fn is_linked(&self) -> bool {
+        unsafe { !(*self.raw.as_ptr()).compiled.is_null() }
+    }
+}
+
+impl<'rt> fmt::Debug for ImportedFunction<'rt> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportedFunction")
+            .field("module_name", &self.module_name())
+            .field("field_name", &self.field_name())
+            .field("signature", &self.signature())
+            .field("is_linked", &self.is_linked())
+            .finish()
+    }
+}
+
 /// A loaded module belonging to a specific runtime. Allows for linking and looking up functions.
+///
+/// A module borrows the [`Runtime`] it was loaded into, so it can not outlive it.
+///
+/// ```compile_fail
+/// # fn wasm() -> &'static [u8] { &[] }
+/// let env = wasm3::Environment::new().unwrap();
+/// let rt = env.create_runtime(1024).unwrap();
+/// let module = rt.parse_and_load_module(wasm()).unwrap();
+/// drop(rt);
+/// module.name();
+/// ```
+///
+/// [`Runtime`]: struct.Runtime.html
 // needs no drop as loaded modules will be cleaned up by the runtime
 pub struct Module<'rt> {
     raw: ffi::IM3Module,
@@ -104,9 +591,147 @@ impl<'rt> Module<'rt> {
             .and_then(|_| unsafe { self.link_func_impl(func, f) })
     }
 
+    /// Links the given function to the corresponding module and function name, passing `context`
+    /// to it on every invocation.
+    /// This allows C-style callbacks to carry state without having to box a closure.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * a memory allocation failed
+    /// * no function by the given name in the given module could be found
+    /// * the function has been found but the signature did not match
+    ///
+    /// # Safety
+    ///
+    /// `context` is handed to `f` as is, so it has to stay valid for whatever `f` does with it
+    /// for as long as the function may be called by this module.
+    pub unsafe fn link_function_with_context<Args, Ret>(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        f: RawCallEx,
+        context: *mut cty::c_void,
+    ) -> Result<()>
+    where
+        Args: crate::WasmArgs,
+        Ret: crate::WasmType,
+    {
+        let func = self.find_import_function(module_name, function_name)?;
+        Function::<'_, Args, Ret>::validate_sig(func)
+            .and_then(|_| self.link_func_ex_impl(func, f, context))
+    }
+
+    /// Links the given function to every import of this module that has not been linked yet,
+    /// returning the number of imports that were linked.
+    ///
+    /// This allows degrading gracefully when a module imports functions the host does not
+    /// provide, for example by linking a function that reports the call and traps. As the imports
+    /// may have any signature, they are not checked against `f`, which has to handle all of them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a memory allocation failed, in which case the
+    /// imports linked up to that point stay linked.
+    pub fn link_all_missing_with(&mut self, f: RawCall) -> Result<usize> {
+        let mut linked = 0;
+        for func in unsafe { functions_raw(self.raw) }.filter(|func| unsafe {
+            !func.as_ref().import.moduleUtf8.is_null() && func.as_ref().compiled.is_null()
+        }) {
+            unsafe { self.link_func_impl(func, f) }?;
+            linked += 1;
+        }
+        Ok(linked)
+    }
+
+    /// Links the imports of this module from the module named like `provider` to the functions
+    /// `provider` exports under the same names. This allows splitting an application into
+    /// multiple modules that call each other directly, without going through the host.
+    ///
+    /// All modules of a runtime share its linear memory, so the modules see the same memory.
+    /// Nothing is linked if any of the imports can not be resolved. Imports that have already
+    /// been linked, for example to a closure, are left as they are. Once linked, `provider` can
+    /// not be unloaded with [`Module::unload`] for as long as this module is loaded.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * `provider` does not export a function this module imports from it, in which case
+    ///   [`Error::UnresolvedImport`] names the missing function
+    /// * an exported function's signature does not match the import's signature
+    /// * compiling an exported function failed
+    ///
+    /// # Panics
+    ///
+    /// Panics if `provider` has been loaded into a different runtime.
+    ///
+    /// [`Error::UnresolvedImport`]: error/enum.Error.html#variant.UnresolvedImport
+    /// [`Module::unload`]: #method.unload
+    pub fn link_module(&mut self, provider: &Module<'rt>) -> Result<()> {
+        assert!(
+            ptr::eq(self.rt, provider.rt),
+            "modules can only be linked within the same runtime"
+        );
+        let provider_name = provider.name();
+        // the provider may be this very module, so its functions are only accessed through
+        // pointers and no two borrows of them overlap
+        let exports = unsafe { functions_raw(provider.raw) }
+            .filter(|func| is_exported(unsafe { func.as_ref() }))
+            .collect::<Vec<_>>();
+        let imports = unsafe { functions_raw(self.raw) }
+            .filter(|func| {
+                let func = unsafe { func.as_ref() };
+                unsafe { eq_cstr_str(func.import.moduleUtf8, provider_name) }
+                &&func.compiled.is_null()
+            })
+            .collect::<Vec<_>>();
+        let mut resolved = Vec::with_capacity(imports.len());
+        for import in imports {
+            let field = unsafe { cstr_to_str(import.as_ref().import.fieldUtf8) };
+            let export = exports
+                .iter()
+                .copied()
+                .find(|func| unsafe { eq_cstr_str(func.as_ref().name, field) })
+                .ok_or_else(|| Error::UnresolvedImport {
+                    module_name: provider_name.to_string(),
+                    function_name: field.to_string(),
+                })?;
+            let (export_ref, import_ref) = unsafe { (export.as_ref(), import.as_ref()) };
+            let expected = SignatureStr::from_func_type(unsafe { &*export_ref.funcType });
+            let requested = SignatureStr::from_func_type(unsafe { &*import_ref.funcType });
+            if expected != requested {
+                return Err(Error::SignatureMismatch {
+                    first_mismatch: function::arg_types(export_ref)
+                        .iter()
+                        .zip(function::arg_types(import_ref))
+                        .position(|(export, import)| export != import),
+                    expected,
+                    requested,
+                });
+            }
+            function::compile_impl(export)?;
+            resolved.push((import, export));
+        }
+        for (import, export) in resolved {
+            // calls to the import jump straight into the exported function's code
+            unsafe { (*import.as_ptr()).compiled = (*export.as_ptr()).compiled };
+        }
+        Ok(())
+    }
+
     /// Links the given closure to the corresponding module and function name.
     /// This boxes the closure and therefor requires a heap allocation.
     ///
+    /// The closure receives a [`CallContext`] through which it can access the memory of the
+    /// runtime, for example to read buffers the guest passes by pointer. Returning an error from
+    /// the closure traps the guest, making the call into wasm fail with the returned [`Trap`].
+    ///
+    /// The closure must not panic unless the crate is built with `panic = "abort"` or the
+    /// `catch-unwind` feature, as unwinding into wasm3 is undefined behavior. With the feature a
+    /// panic traps the guest instead, and is resumed once the call into wasm has returned.
+    ///
     /// # Errors
     ///
     /// This function will return an error in the following situations:
@@ -114,23 +739,74 @@ impl<'rt> Module<'rt> {
     /// * a memory allocation failed
     /// * no function by the given name in the given module could be found
     /// * the function has been found but the signature did not match
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let output = Rc::new(RefCell::new(Vec::new()));
+    /// let sink = output.clone();
+    /// module.link_closure("env", "write", move |mut cc, (ptr, len): (u32, u32)| {
+    ///     let memory = cc.memory_view();
+    ///     let bytes = memory
+    ///         .read_bytes(ptr as usize, len as usize)
+    ///         .map_err(|_| Trap::OutOfBoundsMemoryAccess)?;
+    ///     sink.borrow_mut().extend_from_slice(bytes);
+    ///     Ok(len)
+    /// })?;
+    /// ```
+    ///
+    /// [`CallContext`]: struct.CallContext.html
+    /// [`Trap`]: error/enum.Trap.html
     pub fn link_closure<Args, Ret, F>(
         &mut self,
         module_name: &str,
         function_name: &str,
-        closure: F,
+        mut closure: F,
     ) -> Result<()>
     where
         Args: crate::WasmArgs,
         Ret: crate::WasmType,
         F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, Trap> + 'static,
     {
-        let func = self.find_import_function(module_name, function_name)?;
-        Function::<'_, Args, Ret>::validate_sig(func)?;
-        let mut closure = Box::pin(closure);
-        unsafe { self.link_closure_impl(func, closure.as_mut().get_unchecked_mut()) }?;
-        self.rt.push_closure(closure);
-        Ok(())
+        self.link_closure_impl_boxed(module_name, function_name, move |cc, args| {
+            closure(cc, args).map_err(Trap::as_ptr)
+        })
+    }
+
+    /// Links the given fallible closure to the corresponding module and function name.
+    /// This boxes the closure and therefor requires a heap allocation.
+    ///
+    /// Should the closure return an error the guest traps and the call into wasm that led to
+    /// the closure being invoked returns [`Error::HostFunction`] carrying the error's message.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * a memory allocation failed
+    /// * no function by the given name in the given module could be found
+    /// * the function has been found but the signature did not match
+    ///
+    /// [`Error::HostFunction`]: error/enum.Error.html#variant.HostFunction
+    pub fn link_closure_fallible<Args, Ret, E, F>(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        mut closure: F,
+    ) -> Result<()>
+    where
+        Args: crate::WasmArgs,
+        Ret: crate::WasmType,
+        E: fmt::Display,
+        F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, E> + 'static,
+    {
+        let host_error = self.rt.host_error();
+        self.link_closure_impl_boxed(module_name, function_name, move |cc, args| {
+            closure(cc, args).map_err(|err| {
+                *host_error.borrow_mut() = Some(err.to_string());
+                HOST_FUNCTION_FAILED.as_ptr().cast()
+            })
+        })
     }
 
     /// Looks up a function by the given name in this module.
@@ -143,27 +819,66 @@ impl<'rt> Module<'rt> {
     /// * no function by the given name in the given module could be found
     /// * the function has been found but the signature did not match
     pub fn find_function<Args, Ret>(&self, function_name: &str) -> Result<Function<'rt, Args, Ret>>
+    where
+        Args: crate::WasmArgs,
+        Ret: crate::WasmType,
+    {
+        self.find_function_by_bytes(function_name.as_bytes())
+    }
+
+    /// Looks up a function by the given name in this module, where the name does not have to be
+    /// valid UTF-8.
+    /// This is useful for names read from a wasm binary, as these are not guaranteed to be valid
+    /// UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * a memory allocation failed
+    /// * no function by the given name in the given module could be found
+    /// * the function has been found but the signature did not match
+    pub fn find_function_by_bytes<Args, Ret>(
+        &self,
+        function_name: &[u8],
+    ) -> Result<Function<'rt, Args, Ret>>
     where
         Args: crate::WasmArgs,
         Ret: crate::WasmType,
     {
         let func = unsafe {
-            slice::from_raw_parts_mut(
-                if (*self.raw).functions.is_null() {
-                    NonNull::dangling().as_ptr()
-                } else {
-                    (*self.raw).functions
-                },
-                (*self.raw).numFunctions as usize,
-            )
-            .iter_mut()
-            .find(|func| eq_cstr_str(func.name, function_name))
-            .map(NonNull::from)
-            .ok_or(Error::FunctionNotFound)?
+            functions_raw(self.raw)
+                .find(|func| eq_cstr_bytes(func.as_ref().name, function_name))
+                .ok_or(Error::FunctionNotFound)?
         };
         Function::from_raw(self.rt, func).and_then(Function::compile)
     }
 
+    /// Returns whether this module has a function by the given name, without compiling it.
+    pub(crate) fn has_function(&self, function_name: &str) -> bool {
+        unsafe {
+            functions_raw(self.raw).any(|func| eq_cstr_str(func.as_ref().name, function_name))
+        }
+    }
+
+    /// Looks up a function by the given name in this module without checking its signature.
+    /// The returned [`DynFunction`] validates its arguments on every call instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * a memory allocation failed
+    /// * no function by the given name in the given module could be found
+    pub fn find_function_dyn(&self, function_name: &str) -> Result<DynFunction<'rt>> {
+        let func = unsafe {
+            functions_raw(self.raw)
+                .find(|func| eq_cstr_str(func.as_ref().name, function_name))
+                .ok_or(Error::FunctionNotFound)?
+        };
+        DynFunction::from_raw(self.rt, func)
+    }
+
     /// Looks up a function by its index in this module.
     ///
     /// # Errors
@@ -179,31 +894,171 @@ impl<'rt> Module<'rt> {
         Ret: crate::WasmType,
     {
         let func = unsafe {
-            slice::from_raw_parts_mut(
-                if (*self.raw).functions.is_null() {
-                    NonNull::dangling().as_ptr()
-                } else {
-                    (*self.raw).functions
-                },
-                (*self.raw).numFunctions as usize,
-            )
-            .get(function_index)
-            .map(NonNull::from)
-            .ok_or(Error::FunctionNotFound)?
+            functions_raw(self.raw)
+                .nth(function_index)
+                .ok_or(Error::FunctionNotFound)?
         };
         Function::from_raw(self.rt, func).and_then(Function::compile)
     }
 
+    /// Runs the start function of this module, if it has one that has not been run yet.
+    ///
+    /// The start function is not run when the module is loaded, as it may call imported
+    /// functions, which can only be linked once the module has been loaded. Call this after
+    /// linking all imports and before calling any other function of the module. The start
+    /// function is run at most once, later calls do nothing.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if compiling the start function failed or the start
+    /// function trapped, in which case it will not be run again either.
+    pub fn run_start(&self) -> Result<()> {
+        let raw = unsafe { &mut *self.raw };
+        let index = raw.startFunction;
+        if index < 0 {
+            return Ok(());
+        }
+        // like wasm3 itself, mark the start function as run before it is called
+        raw.startFunction = -1;
+        self.function::<(), ()>(index as usize)?.call()
+    }
+
+    /// Looks up an exported global by the given name in this module.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if no global is exported under the given name.
+    pub fn find_global(&self, global_name: &str) -> Result<Global<'rt>> {
+        unsafe {
+            self.globals_raw()
+                .iter_mut()
+                .find(|global| eq_cstr_str(global.name, global_name))
+                .map(|global| Global::from_raw(NonNull::from(global)))
+                .ok_or(Error::GlobalNotFound)
+        }
+    }
+
+    /// Returns an iterator over all globals of this module, including imported and unexported
+    /// ones.
+    pub fn globals(&self) -> impl Iterator<Item = Global<'rt>> + 'rt {
+        unsafe { self.globals_raw() }
+            .iter_mut()
+            .map(|global| Global::from_raw(NonNull::from(global)))
+    }
+
+    /// Returns an iterator over the functions exported by this module.
+    ///
+    /// The yielded index can be used to look up the function with [`Module::function`].
+    ///
+    /// [`Module::function`]: #method.function
+    pub fn exports(&self) -> impl Iterator<Item = ExportedFunction<'rt>> + 'rt {
+        unsafe { functions_raw(self.raw) }
+            .map(|func| unsafe { &*func.as_ptr() })
+            .enumerate()
+            .filter(|(_, func)| is_exported(func))
+            .map(|(index, func)| ExportedFunction {
+                name: unsafe { cstr_to_str(func.name) },
+                index,
+            })
+    }
+
+    /// Returns an iterator over information about the functions exported by this module,
+    /// including their signatures.
+    pub fn functions(&self) -> impl Iterator<Item = FunctionInfo<'rt>> + 'rt {
+        unsafe { functions_raw(self.raw) }
+            .enumerate()
+            .filter(|(_, func)| is_exported(unsafe { func.as_ref() }))
+            .map(|(index, raw)| FunctionInfo {
+                raw,
+                index,
+                _pd: PhantomData,
+            })
+    }
+
+    /// Returns an iterator over the functions this module imports, which allows listing the
+    /// imports that have not been linked yet.
+    pub fn imports(&self) -> impl Iterator<Item = ImportedFunction<'rt>> + 'rt {
+        unsafe { functions_raw(self.raw) }
+            .filter(|func| unsafe { !func.as_ref().import.moduleUtf8.is_null() })
+            .map(ImportedFunction::from_raw)
+    }
+
+    /// Looks up the import of the given function from the given module, `None` if this module
+    /// does not import it.
+    pub fn find_import(
+        &self,
+        module_name: &str,
+        function_name: &str,
+    ) -> Option<ImportedFunction<'rt>> {
+        self.find_import_function(module_name, function_name)
+            .ok()
+            .map(ImportedFunction::from_raw)
+    }
+
     /// The name of this module.
     pub fn name(&self) -> &str {
         unsafe { cstr_to_str((*self.raw).name) }
     }
 
+    /// Whether this module declares or imports a memory.
+    pub fn has_memory(&self) -> bool {
+        memory_count(self.bytes()) != 0
+    }
+
+    /// The name this module exports its memory under, `None` if it does not export a memory.
+    ///
+    /// wasm3 does not keep the names of memory exports, so this is read from the bytes the module
+    /// was parsed from.
+    pub fn memory_export_name(&self) -> Option<&'rt str> {
+        memory_export_name(self.bytes())
+    }
+
+    /// The bytes this module was parsed from.
+    fn bytes(&self) -> &'rt [u8] {
+        // the runtime keeps the module's bytes alive for as long as it is loaded
+        unsafe {
+            let raw = &*self.raw;
+            slice::from_raw_parts(
+                raw.wasmStart,
+                raw.wasmEnd.offset_from(raw.wasmStart) as usize,
+            )
+        }
+    }
+
+    /// Sets a callback that is invoked with the new size of the linear memory in bytes whenever
+    /// it has grown, replacing any previously set callback.
+    ///
+    /// The linear memory belongs to the runtime and is shared by all of its modules, so this is
+    /// the same as calling [`Runtime::set_memory_grow_callback`], which describes when the
+    /// callback is invoked. The callback must not call back into the runtime.
+    ///
+    /// [`Runtime::set_memory_grow_callback`]: struct.Runtime.html#method.set_memory_grow_callback
+    pub fn set_memory_grow_callback<F>(&self, callback: F)
+    where
+        F: Fn(usize) + 'static,
+    {
+        self.rt.set_memory_grow_callback(callback)
+    }
+
     /// Links wasi to this module.
+    ///
+    /// The program is not handed any arguments, use [`Module::link_wasi_with_config`] to pass it
+    /// arguments and environment variables or to capture its output.
+    ///
+    /// [`Module::link_wasi_with_config`]: #method.link_wasi_with_config
     #[cfg(feature = "wasi")]
     pub fn link_wasi(&mut self) -> Result<()> {
         unsafe { Error::from_ffi_res(ffi::m3_LinkWASI(self.raw)) }
     }
+
+    /// Links wasi to this module, handing the arguments and environment variables of `config` to
+    /// the program instead of the ones wasm3 provides. Output written to stdout and stderr is
+    /// captured in the buffers of `config`, if set.
+    #[cfg(feature = "wasi")]
+    pub fn link_wasi_with_config(&mut self, config: &WasiConfig) -> Result<()> {
+        self.link_wasi()?;
+        crate::wasi::link_config(self, config)
+    }
 }
 
 impl<'rt> Module<'rt> {
@@ -211,6 +1066,60 @@ impl<'rt> Module<'rt> {
         Module { raw, rt }
     }
 
+    pub(crate) fn as_ptr(&self) -> ffi::IM3Module {
+        self.raw
+    }
+
+    pub(crate) fn runtime(&self) -> &'rt Runtime {
+        self.rt
+    }
+
+    /// Unloads this module from its runtime, freeing it along with the bytes it was parsed from.
+    ///
+    /// The compiled code of the module and the closures linked to its imports are only released
+    /// once the runtime is reset or dropped, as wasm3 shares its code pages between modules. The
+    /// linear memory is kept as well, as it belongs to the runtime.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::ModuleInUse`] if another module has been linked to
+    /// this module's functions with [`Module::link_module`], in which case nothing is unloaded,
+    /// and [`Error::ModuleNotFound`] if the module is no longer loaded into its runtime.
+    ///
+    /// # Safety
+    ///
+    /// Other handles to this module as well as all [`Function`]s, [`DynFunction`]s, [`Global`]s
+    /// and other values obtained from it refer to the freed module once it has been unloaded.
+    /// Using any of them afterwards is undefined behavior, except for unloading the module again
+    /// through another handle, as is unloading the module while a call into its runtime is in
+    /// progress, for example from a linked closure.
+    ///
+    /// [`Error::ModuleInUse`]: error/enum.Error.html#variant.ModuleInUse
+    /// [`Error::ModuleNotFound`]: error/enum.Error.html#variant.ModuleNotFound
+    /// [`Module::link_module`]: #method.link_module
+    /// [`Function`]: struct.Function.html
+    /// [`DynFunction`]: struct.DynFunction.html
+    /// [`Global`]: struct.Global.html
+    pub unsafe fn unload(self) -> Result<()> {
+        self.rt.unload_module(self)
+    }
+
+    /// Returns whether any of this module's imports has been linked to a function of `provider`
+    /// with [`Module::link_module`].
+    ///
+    /// [`Module::link_module`]: #method.link_module
+    pub(crate) fn is_linked_to(&self, provider: &Module<'_>) -> bool {
+        unsafe { functions_raw(self.raw) }
+            .map(|func| unsafe { func.as_ref() })
+            .filter(|func| !func.import.moduleUtf8.is_null() && !func.compiled.is_null())
+            .any(|import| {
+                unsafe { functions_raw(provider.raw) }
+                    .map(|func| unsafe { func.as_ref() })
+                    .filter(|func| func.import.moduleUtf8.is_null())
+                    .any(|func| func.compiled == import.compiled)
+            })
+    }
+
     unsafe fn link_func_impl(&self, mut m3_func: NNM3Function, func: RawCall) -> Result<()> {
         let page = wasm3_priv::AcquireCodePageWithCapacity(self.rt.as_ptr(), 2);
         if page.is_null() {
@@ -226,15 +1135,36 @@ impl<'rt> Module<'rt> {
         }
     }
 
+    pub(crate) fn link_closure_impl_boxed<Args, Ret, F>(
+        &mut self,
+        module_name: &str,
+        function_name: &str,
+        closure: F,
+    ) -> Result<()>
+    where
+        Args: crate::WasmArgs,
+        Ret: crate::WasmType,
+        F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, ffi::M3Result>
+            + 'static,
+    {
+        let func = self.find_import_function(module_name, function_name)?;
+        Function::<'_, Args, Ret>::validate_sig(func)?;
+        let mut closure = Box::pin(closure);
+        unsafe { self.link_closure_impl(func, closure.as_mut().get_unchecked_mut()) }?;
+        self.rt.push_closure(closure);
+        Ok(())
+    }
+
     unsafe fn link_closure_impl<Args, Ret, F>(
         &self,
-        mut m3_func: NNM3Function,
+        m3_func: NNM3Function,
         closure: *mut F,
     ) -> Result<()>
     where
         Args: crate::WasmArgs,
         Ret: crate::WasmType,
-        F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, Trap> + 'static,
+        F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, ffi::M3Result>
+            + 'static,
     {
         unsafe extern "C" fn _impl<Args, Ret, F>(
             runtime: ffi::IM3Runtime,
@@ -245,7 +1175,8 @@ impl<'rt> Module<'rt> {
         where
             Args: crate::WasmArgs,
             Ret: crate::WasmType,
-            F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, Trap> + 'static,
+            F: for<'cc> FnMut(CallContext<'cc>, Args) -> core::result::Result<Ret, ffi::M3Result>
+                + 'static,
         {
             // use https://doc.rust-lang.org/std/primitive.pointer.html#method.offset_from once stable
             let stack_base = (*runtime).stack as ffi::m3stack_t;
@@ -258,16 +1189,30 @@ impl<'rt> Module<'rt> {
 
             let args = Args::pop_from_stack(stack);
             let context = CallContext::from_rt(NonNull::new_unchecked(runtime));
-            let res = (&mut *closure.cast::<F>())(context, args);
+            let closure = &mut *closure.cast::<F>();
+            // calls the closure makes back into wasm have to use the stack above its frame
+            let res = match (*runtime).userdata.cast::<Runtime>().as_ref() {
+                Some(rt) => rt.in_host_function(sp, || catch_panic(|| closure(context, args))),
+                None => catch_panic(|| closure(context, args)),
+            };
             match res {
                 Ok(ret) => {
                     ret.push_on_stack(stack.cast());
                     ffi::m3Err_none as _
                 }
-                Err(err) => err.as_ptr() as _,
+                Err(err) => err as _,
             }
         }
 
+        self.link_func_ex_impl(m3_func, _impl::<Args, Ret, F>, closure.cast())
+    }
+
+    unsafe fn link_func_ex_impl(
+        &self,
+        mut m3_func: NNM3Function,
+        func: RawCallEx,
+        userdata: *mut cty::c_void,
+    ) -> Result<()> {
         let page = wasm3_priv::AcquireCodePageWithCapacity(self.rt.as_ptr(), 3);
         if page.is_null() {
             Error::from_ffi_res(ffi::m3Err_mallocFailedCodePage)
@@ -275,33 +1220,106 @@ impl<'rt> Module<'rt> {
             m3_func.as_mut().compiled = wasm3_priv::GetPagePC(page);
             m3_func.as_mut().module = self.raw;
             wasm3_priv::EmitWord_impl(page, crate::wasm3_priv::op_CallRawFunctionEx as _);
-            wasm3_priv::EmitWord_impl(page, _impl::<Args, Ret, F> as _);
-            wasm3_priv::EmitWord_impl(page, closure.cast());
+            wasm3_priv::EmitWord_impl(page, func as _);
+            wasm3_priv::EmitWord_impl(page, userdata);
 
             wasm3_priv::ReleaseCodePage(self.rt.as_ptr(), page);
             Ok(())
         }
     }
 
+    unsafe fn globals_raw(&self) -> &'rt mut [ffi::M3Global] {
+        globals_raw(self.raw)
+    }
+
+    /// Returns the raw host function linked to the given import, `None` if the import does not
+    /// exist or is not linked to a raw host function, like the ones wasm3 links itself.
+    pub(crate) fn linked_raw_call(
+        &self,
+        module_name: &str,
+        function_name: &str,
+    ) -> Option<RawCall> {
+        let func = self.find_import_function(module_name, function_name).ok()?;
+        let pc = unsafe { func.as_ref().compiled }.cast::<*const cty::c_void>();
+        if pc.is_null() {
+            return None;
+        }
+        unsafe {
+            // raw host functions are compiled to op_CallRawFunction followed by the function
+            if *pc != wasm3_priv::op_CallRawFunction as *const cty::c_void {
+                return None;
+            }
+            Some(mem::transmute::<*const cty::c_void, RawCall>(*pc.add(1)))
+        }
+    }
+
     fn find_import_function(&self, module_name: &str, function_name: &str) -> Result<NNM3Function> {
         unsafe {
-            slice::from_raw_parts_mut(
-                if (*self.raw).functions.is_null() {
-                    NonNull::dangling().as_ptr()
-                } else {
-                    (*self.raw).functions
-                },
-                (*self.raw).numFunctions as usize,
-            )
-            .iter_mut()
-            .filter(|func| eq_cstr_str(func.import.moduleUtf8, module_name))
-            .find(|func| eq_cstr_str(func.import.fieldUtf8, function_name))
-            .map(NonNull::from)
-            .ok_or(Error::FunctionNotFound)
+            functions_raw(self.raw)
+                .filter(|func| eq_cstr_str(func.as_ref().import.moduleUtf8, module_name))
+                .find(|func| eq_cstr_str(func.as_ref().import.fieldUtf8, function_name))
+                .ok_or(Error::FunctionNotFound)
         }
     }
 }
 
+impl fmt::Display for Module<'_> {
+    /// Formats the module's name along with its number of exported and imported functions, for
+    /// example `Module(math: 3 exports, 1 imports)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Module({}: {} exports, {} imports)",
+            self.name(),
+            self.exports().count(),
+            self.imports().count()
+        )
+    }
+}
+
+/// Runs `f`, a linked closure, turning a panic into a trap with the `catch-unwind` feature.
+#[inline]
+fn catch_panic<R>(
+    f: impl FnOnce() -> core::result::Result<R, ffi::M3Result>,
+) -> core::result::Result<R, ffi::M3Result> {
+    #[cfg(feature = "catch-unwind")]
+    {
+        crate::unwind::catch(f)
+    }
+    #[cfg(not(feature = "catch-unwind"))]
+    {
+        f()
+    }
+}
+
+fn is_exported(func: &ffi::M3Function) -> bool {
+    !func.name.is_null() && func.import.moduleUtf8.is_null() && func.import.fieldUtf8.is_null()
+}
+
+unsafe fn globals_raw<'a>(raw: ffi::IM3Module) -> &'a mut [ffi::M3Global] {
+    slice::from_raw_parts_mut(
+        if (*raw).globals.is_null() {
+            NonNull::dangling().as_ptr()
+        } else {
+            (*raw).globals
+        },
+        (*raw).numGlobals as usize,
+    )
+}
+
+/// Returns pointers to the functions of the given module, including imported ones.
+///
+/// Linking writes to the functions while handles to them may be alive, so they are handed out as
+/// pointers that are only dereferenced for the duration of a single access.
+unsafe fn functions_raw(raw: ffi::IM3Module) -> impl Iterator<Item = NNM3Function> {
+    let functions = (*raw).functions;
+    let num_functions = (*raw).numFunctions as usize;
+    // the function instrumented modules call at the start of every loop is hidden
+    #[cfg(feature = "metering")]
+    let num_functions = num_functions - crate::metering::is_instrumented(&*raw) as usize;
+    (0..num_functions).map(move |index| NonNull::new_unchecked(functions.add(index)))
+}
+
 #[test]
 fn module_parse() {
     let env = Environment::new().expect("env alloc failure");
@@ -314,3 +1332,66 @@ fn module_parse() {
     ];
     let _ = Module::parse(&env, &fib32[..]).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_len() {
+        assert_eq!(module_len(0), Ok(0));
+        assert_eq!(module_len(u32::MAX as usize), Ok(u32::MAX));
+    }
+
+    #[test]
+    fn test_read_leb_u32() {
+        let mut bytes = &[0xE5, 0x8E, 0x26, 0x01][..];
+        assert_eq!(read_leb_u32(&mut bytes), Some(624_485));
+        assert_eq!(bytes, [0x01]);
+        assert_eq!(
+            read_leb_u32(&mut &[0xFF, 0xFF, 0xFF, 0xFF, 0x0F][..]),
+            Some(u32::MAX)
+        );
+        assert_eq!(read_leb_u32(&mut &[0x80, 0x80][..]), None);
+        assert_eq!(read_leb_u32(&mut &[0x80; 6][..]), None);
+    }
+
+    #[test]
+    fn test_custom_sections_malformed() {
+        let header = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // a custom section named "a" followed by one claiming more bytes than there are
+        let mut module = header.to_vec();
+        module.extend_from_slice(&[0x00, 0x03, 0x01, b'a', 0x2A, 0x00, 0x05, 0x01]);
+        assert!(custom_sections(&module).eq([("a", &[0x2A][..])].iter().copied()));
+        // a custom section whose name is not valid utf-8
+        let mut module = header.to_vec();
+        module.extend_from_slice(&[0x00, 0x02, 0x01, 0xFF]);
+        assert_eq!(custom_sections(&module).count(), 0);
+        assert_eq!(custom_sections(&header[..4]).count(), 0);
+    }
+
+    #[test]
+    fn test_memory_export_name() {
+        let header = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // exports a function "f" followed by the memory as "heap"
+        let mut module = header.to_vec();
+        module.extend_from_slice(&[0x07, 0x0C, 0x02, 0x01, b'f', 0x00, 0x00]);
+        module.extend_from_slice(&[0x04, b'h', b'e', b'a', b'p', 0x02, 0x00]);
+        assert_eq!(memory_export_name(&module), Some("heap"));
+        // the same export section cut off before the memory export
+        module[9] = 0x05;
+        module.truncate(15);
+        assert_eq!(memory_export_name(&module), None);
+        assert_eq!(memory_export_name(&header), None);
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_module_len_too_large() {
+        assert_eq!(
+            module_len(u32::MAX as usize + 1),
+            Err(Error::ModuleTooLarge)
+        );
+        assert_eq!(module_len(usize::MAX), Err(Error::ModuleTooLarge));
+    }
+}
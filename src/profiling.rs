@@ -0,0 +1,123 @@
+//! Host side timing of calls into wasm functions, reported to an observer set with
+//! [`Runtime::set_call_observer`].
+//!
+//! [`Runtime::set_call_observer`]: ../struct.Runtime.html#method.set_call_observer
+use alloc::boxed::Box;
+use core::cell::RefCell;
+use core::fmt;
+use core::time::Duration;
+
+use crate::function;
+
+/// A monotonic clock used to time calls, which allows profiling without `std`.
+pub trait Clock {
+    /// Returns the time that has passed since some fixed point in the past.
+    fn now(&self) -> Duration;
+}
+
+/// A [`Clock`] backed by [`std::time::Instant`], measuring the time since its creation.
+///
+/// [`Clock`]: trait.Clock.html
+/// [`std::time::Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone)]
+pub struct StdClock(std::time::Instant);
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Creates a clock starting at the current instant.
+    pub fn new() -> Self {
+        StdClock(std::time::Instant::now())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// A call into a wasm function, as reported to a call observer.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallEvent<'a> {
+    /// The function is about to be called.
+    Enter {
+        /// The name of the function, `None` if it is anonymous.
+        function: Option<&'a str>,
+    },
+    /// The function returned or trapped.
+    Exit {
+        /// The name of the function, `None` if it is anonymous.
+        function: Option<&'a str>,
+        /// The wall-clock time the call took, including any host functions it called.
+        duration: Duration,
+        /// Whether the call trapped instead of returning.
+        trapped: bool,
+    },
+}
+
+pub(crate) struct CallObserver {
+    clock: Box<dyn Clock>,
+    observer: Box<dyn FnMut(&CallEvent<'_>)>,
+}
+
+impl CallObserver {
+    pub(crate) fn new<C, F>(clock: C, observer: F) -> Self
+    where
+        C: Clock + 'static,
+        F: FnMut(&CallEvent<'_>) + 'static,
+    {
+        CallObserver {
+            clock: Box::new(clock),
+            observer: Box::new(observer),
+        }
+    }
+}
+
+impl fmt::Debug for CallObserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CallObserver")
+    }
+}
+
+/// Runs `f`, which calls `func`, reporting the call to the observer if one is installed.
+pub(crate) fn observe(
+    observer: &RefCell<Option<CallObserver>>,
+    func: &ffi::M3Function,
+    f: impl FnOnce() -> ffi::m3ret_t,
+) -> ffi::m3ret_t {
+    let function = function::function_name(func);
+    let start = with_observer(observer, |observer| {
+        (observer.observer)(&CallEvent::Enter { function });
+        observer.clock.now()
+    });
+    let res = f();
+    if let Some(start) = start {
+        with_observer(observer, |observer| {
+            let duration = observer.clock.now().checked_sub(start).unwrap_or_default();
+            (observer.observer)(&CallEvent::Exit {
+                function,
+                duration,
+                trapped: !res.is_null(),
+            });
+        });
+    }
+    res
+}
+
+/// Runs `f` with the installed observer. Calls made by the observer itself are not reported, as
+/// it is already borrowed then.
+fn with_observer<R>(
+    observer: &RefCell<Option<CallObserver>>,
+    f: impl FnOnce(&mut CallObserver) -> R,
+) -> Option<R> {
+    observer.try_borrow_mut().ok()?.as_mut().map(f)
+}
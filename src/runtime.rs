@@ -1,27 +1,76 @@
+use alloc::borrow::Cow;
 use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
 use alloc::vec::Vec;
-use core::cell::UnsafeCell;
+use core::cell::{Cell, RefCell, UnsafeCell};
+use core::fmt;
+use core::hash::Hasher;
 use core::mem;
+#[cfg(feature = "std")]
+use core::ops::{Deref, DerefMut};
 use core::pin::Pin;
 use core::ptr::{self, NonNull};
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::environment::Environment;
-use crate::error::{Error, Result};
+use crate::error::{Error, ErrorInfo, Result, HOST_FUNCTION_FAILED};
 use crate::function::Function;
+use crate::memory::{self, checked_range, MemorySnapshot, MemoryView};
 use crate::module::{Module, ParsedModule};
+#[cfg(feature = "profiling")]
+use crate::profiling::{CallEvent, CallObserver, Clock};
 use crate::utils::eq_cstr_str;
 
 type PinnedAnyClosure = Pin<Box<dyn core::any::Any + 'static>>;
 
+// the stack gets painted with this pattern on creation to be able to tell which slots have been used
+const STACK_PAINT: ffi::m3slot_t = ffi::m3slot_t::MAX / 0xFF * 0xA5;
+
+const PAGE_SIZE: u32 = 64 * 1024;
+
+const DEFAULT_STACK_SLOTS: u32 = 64 * 1024 / mem::size_of::<ffi::m3slot_t>() as u32;
+
+#[derive(Clone)]
+struct MemoryGrowCallback(Rc<dyn Fn(usize)>);
+
+impl fmt::Debug for MemoryGrowCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("MemoryGrowCallback")
+    }
+}
+
 /// A runtime context for wasm3 modules.
+///
+/// A runtime can not be cloned, as its linear memory is only handed out while it is borrowed
+/// mutably, which requires it to have a single owner. With the `std` feature a
+/// [`RuntimeHandle`] can be used to share a runtime instead.
+///
+/// [`RuntimeHandle`]: struct.RuntimeHandle.html
 #[derive(Debug)]
 pub struct Runtime {
     raw: NonNull<ffi::M3Runtime>,
     environment: Environment,
     // holds all linked closures so that they properly get disposed of when runtime drops
     closure_store: UnsafeCell<Vec<PinnedAnyClosure>>,
-    // holds all backing data of loaded modules as they have to be kept alive for the module's lifetime
+    // holds all backing data and names of loaded modules as they have to be kept alive for the module's lifetime
     module_data: UnsafeCell<Vec<Box<[u8]>>>,
+    // holds the message of the last failed fallible host function until the call returns
+    host_error: Rc<RefCell<Option<String>>>,
+    // the maximum number of pages linear memory may grow to, regardless of what modules declare
+    max_memory_pages: Option<u32>,
+    // invoked with the new size of linear memory whenever it is observed to have grown
+    memory_grow_callback: RefCell<Option<MemoryGrowCallback>>,
+    // the size of linear memory in bytes as last observed by check_memory_growth
+    observed_memory_size: Cell<usize>,
+    // the stack pointer of the linked closure currently running, calls it makes back into wasm
+    // use the stack above it so they don't clobber the frames of the calls in progress
+    host_stack: Cell<*mut ffi::m3slot_t>,
+    #[cfg(feature = "metering")]
+    fuel: Cell<u64>,
+    #[cfg(feature = "profiling")]
+    call_observer: RefCell<Option<CallObserver>>,
 }
 
 impl Runtime {
@@ -31,6 +80,36 @@ impl Runtime {
     ///
     /// This function will error on memory allocation failure.
     pub fn new(environment: &Environment, stack_size: u32) -> Result<Self> {
+        Self::new_impl(environment, stack_size, None)
+    }
+
+    /// Creates a new runtime with the given stack size in slots whose linear memory can not grow
+    /// beyond `max_memory_pages` 64KiB pages.
+    ///
+    /// Attempts of modules to grow their memory past this limit fail as if they exceeded their
+    /// declared maximum, modules whose initial memory already exceeds it fail to load.
+    ///
+    /// # Errors
+    ///
+    /// This function will error on memory allocation failure.
+    pub fn new_with_memory_limit(
+        environment: &Environment,
+        stack_size: u32,
+        max_memory_pages: u32,
+    ) -> Result<Self> {
+        Self::new_impl(environment, stack_size, Some(max_memory_pages))
+    }
+
+    /// Returns a builder for configuring a new runtime.
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::new()
+    }
+
+    fn new_impl(
+        environment: &Environment,
+        stack_size: u32,
+        max_memory_pages: Option<u32>,
+    ) -> Result<Self> {
         unsafe {
             NonNull::new(ffi::m3_NewRuntime(
                 environment.as_ptr(),
@@ -39,11 +118,29 @@ impl Runtime {
             ))
         }
         .ok_or_else(Error::malloc_error)
-        .map(|raw| Runtime {
-            raw,
-            environment: environment.clone(),
-            closure_store: UnsafeCell::new(Vec::new()),
-            module_data: UnsafeCell::new(Vec::new()),
+        .map(|mut raw| {
+            if let Some(max_pages) = max_memory_pages {
+                unsafe {
+                    raw.as_mut().memoryLimit = max_pages.saturating_mul(PAGE_SIZE);
+                }
+            }
+            let rt = Runtime {
+                raw,
+                environment: environment.clone(),
+                closure_store: UnsafeCell::new(Vec::new()),
+                module_data: UnsafeCell::new(Vec::new()),
+                host_error: Rc::new(RefCell::new(None)),
+                max_memory_pages,
+                memory_grow_callback: RefCell::new(None),
+                observed_memory_size: Cell::new(0),
+                host_stack: Cell::new(ptr::null_mut()),
+                #[cfg(feature = "metering")]
+                fuel: Cell::new(u64::MAX),
+                #[cfg(feature = "profiling")]
+                call_observer: RefCell::new(None),
+            };
+            unsafe { (*rt.stack_mut()).fill(STACK_PAINT) };
+            rt
         })
     }
 
@@ -57,38 +154,157 @@ impl Runtime {
 
     /// Loads a parsed module returning the module if unsuccessful.
     ///
+    /// This does not run the module's start function, see [`Module::run_start`].
+    ///
     /// # Errors
     ///
-    /// This function will error if the module's environment differs from the one this runtime uses.
+    /// This function will error in the following situations:
+    ///
+    /// * the module's environment differs from the one this runtime uses
+    /// * the module's initial memory exceeds the runtime's memory limit
+    /// * wasm3 failed to load the module
+    ///
+    /// [`Module::run_start`]: struct.Module.html#method.run_start
     pub fn load_module<'rt>(&'rt self, module: ParsedModule) -> Result<Module<'rt>> {
         if &self.environment != module.environment() {
             Err(Error::ModuleLoadEnvMismatch)
         } else {
             let raw_mod = module.as_ptr();
+            if let Some(max_pages) = self.max_memory_pages {
+                if unsafe { (*raw_mod).memoryInfo.initPages } > max_pages {
+                    return Err(Error::memory_overflow_error());
+                }
+            }
             Error::from_ffi_res(unsafe { ffi::m3_LoadModule(self.raw.as_ptr(), raw_mod) })?;
-            // SAFETY: Runtime isn't Send, therefor this access is single-threaded and kept alive only for the Vec::push call
+            if let Some(max_pages) = self.max_memory_pages {
+                // loading a module sets the maximum to the one it declares, so clamp it afterwards
+                let memory = unsafe { &mut (*self.raw.as_ptr()).memory };
+                memory.maxPages = memory.maxPages.min(max_pages);
+            }
+            self.check_memory_growth();
+            // SAFETY: Runtime isn't Send, therefor this access is single-threaded and kept alive only for the Vec::extend call
             // as such this can not alias.
-            unsafe { (*self.module_data.get()).push(module.take_data()) };
+            let (data, name) = module.take_data();
+            unsafe { (*self.module_data.get()).extend(Some(data).into_iter().chain(name)) };
 
             Ok(Module::from_raw(self, raw_mod))
         }
     }
 
+    /// Loads a parsed module and runs its start function, if it has one.
+    ///
+    /// As the start function runs right away, it may not call any imported functions since those
+    /// can only be linked after loading. Use [`Runtime::load_module`] followed by
+    /// [`Module::run_start`] for such modules instead.
+    ///
+    /// # Errors
+    ///
+    /// This function will error in the same situations as [`Runtime::load_module`], or if the
+    /// start function failed to compile or trapped.
+    ///
+    /// [`Runtime::load_module`]: struct.Runtime.html#method.load_module
+    /// [`Module::run_start`]: struct.Module.html#method.run_start
+    pub fn load_and_start<'rt>(&'rt self, module: ParsedModule) -> Result<Module<'rt>> {
+        let module = self.load_module(module)?;
+        module.run_start()?;
+        Ok(module)
+    }
+
+    /// Loads a parsed module under the given name instead of the one it was parsed with.
+    /// This allows loading multiple instances of the same module while still being able to tell
+    /// them apart, for example with [`Runtime::find_function_in`].
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the name contains a nul byte, and in the situations
+    /// described for [`Runtime::load_module`] otherwise.
+    ///
+    /// [`Runtime::find_function_in`]: struct.Runtime.html#method.find_function_in
+    /// [`Runtime::load_module`]: struct.Runtime.html#method.load_module
+    pub fn load_module_named<'rt>(
+        &'rt self,
+        mut module: ParsedModule,
+        name: &str,
+    ) -> Result<Module<'rt>> {
+        module.set_name(name)?;
+        self.load_module(module)
+    }
+
     /// Looks up a function by the given name in the loaded modules of this runtime.
     /// See [`Module::find_function`] for possible error cases.
     ///
+    /// If more than one loaded module has a function by the given name, this returns
+    /// [`Error::AmbiguousFunction`] naming the modules, use [`Runtime::find_function_in`] to look
+    /// the function up in a specific module instead.
+    ///
     /// [`Module::find_function`]: ../module/struct.Module.html#method.find_function
+    /// [`Error::AmbiguousFunction`]: error/enum.Error.html#variant.AmbiguousFunction
+    /// [`Runtime::find_function_in`]: struct.Runtime.html#method.find_function_in
     pub fn find_function<'rt, ARGS, RET>(&'rt self, name: &str) -> Result<Function<'rt, ARGS, RET>>
     where
         ARGS: crate::WasmArgs,
         RET: crate::WasmType,
     {
-        self.modules()
-            .find_map(|module| match module.find_function::<ARGS, RET>(name) {
-                res @ (Ok(_) | Err(Error::InvalidFunctionSignature)) => Some(res),
-                _ => None,
-            })
-            .unwrap_or(Err(Error::FunctionNotFound))
+        // only the function that is going to be returned gets compiled
+        let mut found: Vec<_> = self
+            .modules()
+            .filter(|module| module.has_function(name))
+            .collect();
+        match found.len() {
+            0 => Err(Error::FunctionNotFound),
+            1 => found.remove(0).find_function(name),
+            _ => Err(Error::AmbiguousFunction {
+                modules: found
+                    .iter()
+                    .map(|module| String::from(module.name()))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// Looks up a function by the given name in the loaded module of the given name.
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::ModuleNotFound`] if no module by the given name has
+    /// been loaded, see [`Module::find_function`] for the other possible error cases.
+    ///
+    /// [`Error::ModuleNotFound`]: error/enum.Error.html#variant.ModuleNotFound
+    /// [`Module::find_function`]: ../module/struct.Module.html#method.find_function
+    pub fn find_function_in<'rt, ARGS, RET>(
+        &'rt self,
+        module_name: &str,
+        function_name: &str,
+    ) -> Result<Function<'rt, ARGS, RET>>
+    where
+        ARGS: crate::WasmArgs,
+        RET: crate::WasmType,
+    {
+        self.find_module(module_name)?.find_function(function_name)
+    }
+
+    /// Looks up a function by the given name in the loaded modules of this runtime and calls it
+    /// with the given arguments. This combines [`Runtime::find_function`] and calling the found
+    /// function, with arguments passed as a tuple or a single value.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error in the following situations:
+    ///
+    /// * no loaded module has a function by the given name
+    /// * more than one loaded module has a function by the given name, see
+    ///   [`Error::AmbiguousFunction`]
+    /// * the function has been found but the signature did not match
+    /// * the function trapped
+    ///
+    /// [`Runtime::find_function`]: struct.Runtime.html#method.find_function
+    /// [`Error::AmbiguousFunction`]: error/enum.Error.html#variant.AmbiguousFunction
+    pub fn call<ARGS, RET>(&self, name: &str, args: ARGS) -> Result<RET>
+    where
+        ARGS: crate::WasmArgs,
+        RET: crate::WasmType,
+    {
+        self.find_function::<ARGS, RET>(name)?.call_impl(args)
     }
 
     /// Searches for a module with the given name in the runtime's loaded modules.
@@ -111,9 +327,40 @@ impl Runtime {
         }
     }
 
-    /// Returns an iterator over the runtime's loaded modules.
+    /// Takes the detailed information wasm3 recorded about the last error of this runtime,
+    /// returning `None` if there is none.
+    ///
+    /// wasm3 only records this information for some errors, mainly for those encountered while
+    /// compiling a function, which happens lazily on its first call. The information is cleared
+    /// by this call.
+    ///
+    /// Traps are not among them: the bundled version of wasm3 does not record backtraces, so
+    /// neither the function a trap occurred in nor its offset in the module can be recovered
+    /// after a call has trapped.
+    pub fn take_error_info(&self) -> Option<ErrorInfo> {
+        unsafe {
+            let mut info = mem::zeroed::<ffi::M3ErrorInfo>();
+            ffi::m3_GetErrorInfo(self.raw.as_ptr(), &mut info);
+            let res = ErrorInfo::from_raw(&info);
+            ffi::m3_ResetErrorInfo(self.raw.as_ptr());
+            res
+        }
+    }
+
+    /// Returns the number of modules loaded into this runtime.
+    pub fn module_count(&self) -> usize {
+        self.modules().count()
+    }
+
+    /// Returns an iterator over the runtime's loaded modules, in no particular order.
+    ///
+    /// This yields every module loaded into this runtime, which allows finding modules that were
+    /// loaded elsewhere, for example by only remembering their names. To look up a single module
+    /// by its name use [`Runtime::find_module`] instead.
+    ///
+    /// [`Runtime::find_module`]: #method.find_module
     pub fn modules<'rt>(&'rt self) -> impl Iterator<Item = Module<'rt>> + 'rt {
-        // pointer could get invalidated if modules can become unloaded
+        // unloading modules requires the runtime to be borrowed mutably, so this can't be invalidated
         // pushing new modules into the runtime while this iterator exists is fine as its backed by a linked list meaning it wont get invalidated.
         let mut module = unsafe { ptr::NonNull::new(self.raw.as_ref().modules) };
         core::iter::from_fn(move || {
@@ -122,43 +369,484 @@ impl Runtime {
         })
     }
 
+    /// Unloads the given module from this runtime, see [`Module::unload`].
+    ///
+    /// [`Module::unload`]: struct.Module.html#method.unload
+    pub(crate) unsafe fn unload_module(&self, module: Module<'_>) -> Result<()> {
+        if !ptr::eq(module.runtime(), self) {
+            return Err(Error::ModuleNotFound);
+        }
+        let raw = self.raw.as_ptr();
+        let raw_mod = module.as_ptr();
+        // kept as a raw pointer as the list is read again below before unlinking the module
+        let mut link: *mut ffi::IM3Module = &mut (*raw).modules;
+        while *link != raw_mod {
+            if (*link).is_null() {
+                return Err(Error::ModuleNotFound);
+            }
+            link = &mut (**link).next;
+        }
+
+        let dependent = self
+            .modules()
+            .find(|other| other.as_ptr() != module.as_ptr() && other.is_linked_to(&module));
+        if let Some(dependent) = dependent {
+            return Err(Error::ModuleInUse {
+                module_name: String::from(module.name()),
+                dependent: String::from(dependent.name()),
+            });
+        }
+        *link = (*raw_mod).next;
+
+        let data = (*raw_mod).wasmStart;
+        let name = (*raw_mod).name.cast::<u8>();
+        ffi::m3_FreeModule(raw_mod);
+        // the recorded error may point into the freed module
+        ffi::m3_ResetErrorInfo(raw);
+        // the module's bytes and name are no longer referenced by anything
+        (*self.module_data.get()).retain(|buf| buf.as_ptr() != data && buf.as_ptr() != name);
+        Ok(())
+    }
+
+    /// Resets this runtime to the state of a newly created one, reusing its stack instead of
+    /// reallocating it.
+    ///
+    /// All loaded modules are unloaded, their compiled code is released and all linked closures
+    /// as well as the memory grow callback and call observer are dropped. The linear memory is
+    /// shrunk to zero pages, the memory limit this runtime was created with is kept.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if shrinking the memory failed, in which case the runtime is
+    /// left untouched.
+    pub fn reset(&mut self) -> Result<()> {
+        unsafe {
+            let raw = self.raw.as_mut();
+            if !raw.memory.mallocated.is_null() {
+                Error::from_ffi_res(ffi::ResizeMemory(raw, 0))?;
+            }
+            raw.memory.maxPages = 0;
+
+            let mut module = raw.modules;
+            while !module.is_null() {
+                let next = (*module).next;
+                ffi::m3_FreeModule(module);
+                module = next;
+            }
+            raw.modules = ptr::null_mut();
+
+            // the compiled code of the freed modules is no longer referenced by anything
+            ffi::Environment_ReleaseCodePages(self.environment.as_ptr(), raw.pagesOpen);
+            ffi::Environment_ReleaseCodePages(self.environment.as_ptr(), raw.pagesFull);
+            raw.pagesOpen = ptr::null_mut();
+            raw.pagesFull = ptr::null_mut();
+            raw.numCodePages = 0;
+            raw.numActiveCodePages = 0;
+            // the recorded error may point into the freed modules
+            ffi::m3_ResetErrorInfo(raw);
+        }
+        // these were only kept alive for the modules and their linked functions
+        self.module_data.get_mut().clear();
+        self.closure_store.get_mut().clear();
+        self.memory_grow_callback.get_mut().take();
+        #[cfg(feature = "profiling")]
+        self.call_observer.get_mut().take();
+        self.host_error.take();
+        self.observed_memory_size.set(0);
+        #[cfg(feature = "metering")]
+        self.fuel.set(u64::MAX);
+        unsafe { (*self.stack_mut()).fill(STACK_PAINT) };
+        Ok(())
+    }
+
     /// Resizes the number of allocatable pages to num_pages.
     ///
     /// # Errors
     ///
     /// This function will error out if it failed to resize memory allocation.
     pub fn resize_memory(&self, num_pages: u32) -> Result<()> {
-        Error::from_ffi_res(unsafe { ffi::ResizeMemory(self.raw.as_ptr(), num_pages) })
+        Error::from_ffi_res(unsafe { ffi::ResizeMemory(self.raw.as_ptr(), num_pages) })?;
+        self.check_memory_growth();
+        Ok(())
     }
 
-    /// Returns the raw memory of this runtime.
+    /// Sets a callback that is invoked with the new size of the linear memory in bytes whenever
+    /// it has grown, replacing any previously set callback.
     ///
-    /// # Safety
+    /// wasm3 grows the memory without notifying the host, so growth caused by the guest is only
+    /// noticed once the guest calls a linked closure or the call into wasm that grew it returns,
+    /// whichever comes first. The callback is therefore always invoked before a closure gets to
+    /// see the grown memory, but not before functions linked with [`Module::link_function`] do.
+    /// Growth caused by loading a module or by resizing the memory from the host is reported right
+    /// away. Shrinking the memory does not invoke the callback.
     ///
-    /// The returned pointer may get invalidated when wasm function objects are called due to reallocations.
-    pub unsafe fn memory(&self) -> *const [u8] {
-        let len = (*self.mallocated()).length as usize;
-        let data = if len == 0 {
-            ptr::NonNull::dangling().as_ptr()
-        } else {
-            self.mallocated().offset(1).cast()
-        };
-        ptr::slice_from_raw_parts(data, len)
+    /// The callback must not call back into this runtime, as it may be invoked while the runtime
+    /// is in the middle of a call.
+    ///
+    /// [`Module::link_function`]: struct.Module.html#method.link_function
+    pub fn set_memory_grow_callback<F>(&self, callback: F)
+    where
+        F: Fn(usize) + 'static,
+    {
+        self.observed_memory_size.set(self.memory_size());
+        *self.memory_grow_callback.borrow_mut() = Some(MemoryGrowCallback(Rc::new(callback)));
+    }
+
+    /// Removes the callback set with [`Runtime::set_memory_grow_callback`].
+    ///
+    /// [`Runtime::set_memory_grow_callback`]: #method.set_memory_grow_callback
+    pub fn clear_memory_grow_callback(&self) {
+        self.memory_grow_callback.borrow_mut().take();
+    }
+
+    /// Grows the linear memory by the given number of 64KiB pages, returning the previous number
+    /// of pages like the `memory.grow` instruction does.
+    ///
+    /// Growing the memory may move it, so any previously obtained memory slices or
+    /// [`MemoryView`]s are invalidated and have to be fetched again. As both borrow the runtime
+    /// mutably, the compiler rejects holding on to them across a call to this function:
+    ///
+    /// ```compile_fail
+    /// # fn wasm() -> &'static [u8] { &[] }
+    /// let env = wasm3::Environment::new().unwrap();
+    /// let mut rt = env.create_runtime(1024).unwrap();
+    /// rt.parse_and_load_module(wasm()).unwrap();
+    /// let memory = rt.memory();
+    /// rt.grow_memory(1).unwrap();
+    /// assert_eq!(memory[0], 0);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// This function will return [`Error::MemoryGrowFailed`] if the memory would exceed the
+    /// maximum size declared by the module or the memory limit of the runtime, or an error if the
+    /// memory allocation failed. In either case the memory is left untouched.
+    ///
+    /// [`MemoryView`]: struct.MemoryView.html
+    /// [`Error::MemoryGrowFailed`]: error/enum.Error.html#variant.MemoryGrowFailed
+    pub fn grow_memory(&mut self, additional_pages: u32) -> Result<u32> {
+        let prev_pages = self.memory_pages();
+        if additional_pages == 0 {
+            return Ok(prev_pages);
+        }
+        let num_pages = prev_pages
+            .checked_add(additional_pages)
+            .ok_or(Error::MemoryGrowFailed)?;
+        match self.resize_memory(num_pages) {
+            Ok(()) => Ok(prev_pages),
+            Err(err) if err == Error::memory_overflow_error() => Err(Error::MemoryGrowFailed),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Returns the size of this runtime's linear memory in bytes.
+    /// If no memory has been instantiated yet this returns 0.
+    pub fn memory_size(&self) -> usize {
+        // m3_GetMemory reports the size as a u32, which wraps to 0 for a memory of 65536 pages
+        unsafe { self.mallocated().as_ref() }.map_or(0, |header| header.length)
+    }
+
+    /// Returns the number of 64KiB pages of this runtime's linear memory.
+    /// If no memory has been instantiated yet this returns 0.
+    pub fn memory_pages(&self) -> u32 {
+        unsafe { self.raw.as_ref().memory.numPages }
+    }
+
+    /// Returns the linear memory of this runtime.
+    /// If no memory has been instantiated yet the returned slice is empty.
+    ///
+    /// This borrows the runtime mutably as calling into wasm may grow the memory, invalidating
+    /// the slice. Any [`Module`]s or [`Function`]s of this runtime therefore have to be
+    /// dropped before the memory can be inspected, after which they can be looked up again via
+    /// [`Runtime::find_module`] and [`Runtime::find_function`].
+    ///
+    /// [`Runtime::find_module`]: #method.find_module
+    /// [`Runtime::find_function`]: #method.find_function
+    pub fn memory(&mut self) -> &[u8] {
+        unsafe { &*self.memory_slice_ptr() }
+    }
+
+    /// Returns the linear memory of this runtime mutably.
+    /// If no memory has been instantiated yet the returned slice is empty.
+    ///
+    /// See [`Runtime::memory`] for why this requires the runtime to be borrowed mutably.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // ask the guest where it expects its input
+    /// let ptr = rt
+    ///     .find_function::<(), u32>("get_input_ptr")?
+    ///     .call()? as usize;
+    /// let input = b"hello";
+    /// rt.memory_mut()
+    ///     .get_mut(ptr..ptr + input.len())
+    ///     .ok_or(Error::OutOfBoundsMemoryAccess { offset: ptr, len: input.len() })?
+    ///     .copy_from_slice(input);
+    /// rt.find_function::<(u32, u32), ()>("process")?
+    ///     .call(ptr as u32, input.len() as u32)?;
+    /// ```
+    ///
+    /// [`Runtime::memory`]: #method.memory
+    pub fn memory_mut(&mut self) -> &mut [u8] {
+        unsafe { &mut *self.memory_slice_ptr() }
+    }
+
+    /// Takes a snapshot of the linear memory of this runtime, which can later be reinstated with
+    /// [`Runtime::restore_memory`].
+    ///
+    /// Only the linear memory is captured, globals and tables are not part of the snapshot.
+    ///
+    /// [`Runtime::restore_memory`]: #method.restore_memory
+    pub fn snapshot_memory(&self) -> MemorySnapshot {
+        let memory = unsafe { &*self.memory_slice_ptr() };
+        MemorySnapshot::new(memory.to_vec(), self.memory_pages())
     }
 
-    /// Returns the raw memory of this runtime.
+    /// Restores the linear memory of this runtime to the state captured in `snapshot`.
+    ///
+    /// If the memory is smaller than the snapshot it is grown to match. As linear memory can not
+    /// shrink, a memory that has grown past the snapshot keeps its size, with the bytes beyond
+    /// the snapshot being zeroed.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the memory had to be grown and that failed or the memory is
+    /// still smaller than the snapshot, in which case the memory is left untouched.
+    pub fn restore_memory(&self, snapshot: &MemorySnapshot) -> Result<()> {
+        if self.memory_pages() < snapshot.pages() {
+            self.resize_memory(snapshot.pages())?;
+        }
+        let memory = unsafe { &mut *self.memory_slice_ptr() };
+        let range = checked_range(memory.len(), 0, snapshot.data().len())?;
+        let (restored, rest) = memory.split_at_mut(range.end);
+        restored.copy_from_slice(snapshot.data());
+        rest.fill(0);
+        Ok(())
+    }
+
+    /// Streams the linear memory of this runtime into `update` to compute a digest of it, for
+    /// example with a cryptographic hash function.
+    ///
+    /// `update` is first passed the number of pages as a little-endian `u32`, so that the digest
+    /// covers changes to the memory size, followed by the contents of the memory in chunks of
+    /// one page each. The digest therefore only depends on the state of the memory, making it
+    /// suitable to commit to the result of a deterministic execution.
+    ///
+    /// See [`Runtime::memory`] for why this requires the runtime to be borrowed mutably.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut hasher = Sha256::new();
+    /// rt.memory_digest(|chunk| hasher.update(chunk));
+    /// let digest = hasher.finalize();
+    /// ```
+    ///
+    /// [`Runtime::memory`]: #method.memory
+    pub fn memory_digest(&mut self, mut update: impl FnMut(&[u8])) {
+        update(&self.memory_pages().to_le_bytes());
+        for chunk in self.memory().chunks(PAGE_SIZE as usize) {
+            update(chunk);
+        }
+    }
+
+    /// Feeds the linear memory of this runtime into `hasher`, see [`Runtime::memory_digest`].
+    ///
+    /// [`Runtime::memory_digest`]: #method.memory_digest
+    pub fn memory_hash<H: Hasher>(&mut self, hasher: &mut H) {
+        self.memory_digest(|chunk| hasher.write(chunk))
+    }
+
+    /// Returns a pointer to the start of this runtime's linear memory together with its size in
+    /// bytes, for handing the memory to foreign code.
+    /// If no memory has been instantiated yet the size is 0 and the pointer is dangling.
     ///
     /// # Safety
     ///
-    /// The returned pointer may get invalidated when wasm function objects are called due to reallocations.
-    pub unsafe fn memory_mut(&self) -> *mut [u8] {
-        let len = (*self.mallocated()).length as usize;
-        let data = if len == 0 {
-            ptr::NonNull::dangling().as_ptr()
-        } else {
-            self.mallocated().offset(1).cast()
-        };
-        ptr::slice_from_raw_parts_mut(data, len)
+    /// The caller has to uphold the following invariants:
+    ///
+    /// * the pointer is invalidated whenever the memory is reallocated, which may happen on any
+    ///   call into wasm, on [`Runtime::grow_memory`], [`Runtime::resize_memory`] and
+    ///   [`Runtime::reset`], so it has to be fetched again afterwards
+    /// * the pointer must not be used after the runtime has been dropped
+    /// * the memory must not be accessed through the pointer while it is borrowed, for example
+    ///   through [`Runtime::memory`] or a [`MemoryView`]
+    ///
+    /// [`Runtime::grow_memory`]: #method.grow_memory
+    /// [`Runtime::resize_memory`]: #method.resize_memory
+    /// [`Runtime::reset`]: #method.reset
+    /// [`Runtime::memory`]: #method.memory
+    /// [`MemoryView`]: struct.MemoryView.html
+    pub unsafe fn memory_raw(&self) -> (*mut u8, usize) {
+        let memory = self.memory_slice_ptr();
+        (memory.cast(), (*memory).len())
+    }
+
+    /// Returns `len` bytes of linear memory starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory.
+    pub fn read_memory(&mut self, offset: usize, len: usize) -> Result<&[u8]> {
+        let memory = self.memory();
+        checked_range(memory.len(), offset, len).map(|range| &memory[range])
+    }
+
+    /// Copies `data` into linear memory starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory,
+    /// in which case nothing is written.
+    pub fn write_memory(&mut self, offset: usize, data: &[u8]) -> Result<()> {
+        let memory = self.memory_mut();
+        checked_range(memory.len(), offset, data.len())
+            .map(|range| memory[range].copy_from_slice(data))
+    }
+
+    /// Copies `dst.len()` bytes of linear memory starting at `offset` into `dst`.
+    ///
+    /// Unlike [`Runtime::read_memory`] this does not borrow the runtime mutably, and it is the
+    /// preferred way of moving large buffers out of memory as it does a single bounds check
+    /// followed by a `memcpy`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory,
+    /// in which case `dst` is left untouched.
+    ///
+    /// [`Runtime::read_memory`]: #method.read_memory
+    pub fn read_into(&self, offset: u32, dst: &mut [u8]) -> Result<()> {
+        let memory = unsafe { &*self.memory_slice_ptr() };
+        checked_range(memory.len(), offset as usize, dst.len())
+            .map(|range| dst.copy_from_slice(&memory[range]))
+    }
+
+    /// Copies `src` into linear memory starting at `offset`.
+    ///
+    /// Unlike [`Runtime::write_memory`] this does not borrow the runtime mutably, and it is the
+    /// preferred way of moving large buffers into memory as it does a single bounds check
+    /// followed by a `memcpy`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory,
+    /// in which case nothing is written.
+    ///
+    /// [`Runtime::write_memory`]: #method.write_memory
+    pub fn write_at(&self, offset: u32, src: &[u8]) -> Result<()> {
+        let memory = unsafe { &mut *self.memory_slice_ptr() };
+        checked_range(memory.len(), offset as usize, src.len())
+            .map(|range| memory[range].copy_from_slice(src))
+    }
+
+    /// Reads a UTF-8 string of `len` bytes from linear memory starting at `offset`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory or the
+    /// bytes are not valid UTF-8.
+    pub fn read_str(&mut self, offset: usize, len: usize) -> Result<&str> {
+        memory::read_str(self.memory(), offset, len)
+    }
+
+    /// Reads a string of `len` bytes from linear memory starting at `offset`, replacing invalid
+    /// UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the range is out of bounds of the current memory.
+    pub fn read_str_lossy(&mut self, offset: usize, len: usize) -> Result<Cow<'_, str>> {
+        memory::read_str_lossy(self.memory(), offset, len)
+    }
+
+    /// Reads a nul-terminated UTF-8 string from linear memory starting at `offset`.
+    /// The terminator is not part of the returned string.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if no terminator is found before the end of memory or the bytes
+    /// are not valid UTF-8.
+    pub fn read_cstr(&mut self, offset: usize) -> Result<&str> {
+        memory::read_cstr(self.memory(), offset)
+    }
+
+    /// Reads a nul-terminated string from linear memory starting at `offset`, replacing invalid
+    /// UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`.
+    /// The terminator is not part of the returned string.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if no terminator is found before the end of memory.
+    pub fn read_cstr_lossy(&mut self, offset: usize) -> Result<Cow<'_, str>> {
+        memory::read_cstr_lossy(self.memory(), offset)
+    }
+
+    /// Returns a bounds-checked view into the linear memory of this runtime.
+    ///
+    /// See [`Runtime::memory`] for why this requires the runtime to be borrowed mutably.
+    ///
+    /// [`Runtime::memory`]: #method.memory
+    pub fn memory_view(&mut self) -> MemoryView<'_> {
+        MemoryView::new(self.memory_mut())
+    }
+
+    /// Sets the fuel available to calls into this runtime.
+    ///
+    /// Every wasm function call and every loop iteration consumes one unit of fuel, once it has
+    /// been used up the call traps with [`Error::OutOfFuel`]. A runtime starts out with
+    /// `u64::MAX` units of fuel.
+    ///
+    /// [`Error::OutOfFuel`]: error/enum.Error.html#variant.OutOfFuel
+    #[cfg(feature = "metering")]
+    pub fn set_fuel(&self, units: u64) {
+        self.fuel.set(units);
+    }
+
+    /// Returns the fuel left for calls into this runtime.
+    #[cfg(feature = "metering")]
+    pub fn fuel_remaining(&self) -> u64 {
+        self.fuel.get()
+    }
+
+    /// Sets an observer that is notified whenever a wasm function is called from the host and
+    /// again once the call returns, replacing any previously set observer. The duration of each
+    /// call is measured with the given `clock`.
+    ///
+    /// Only calls made through [`Function`] and [`DynFunction`] are reported, calls between wasm
+    /// functions are not. Calls the observer itself makes into this runtime are not reported
+    /// either.
+    ///
+    /// [`Function`]: struct.Function.html
+    /// [`DynFunction`]: struct.DynFunction.html
+    #[cfg(feature = "profiling")]
+    pub fn set_call_observer<C, F>(&self, clock: C, observer: F)
+    where
+        C: Clock + 'static,
+        F: FnMut(&CallEvent<'_>) + 'static,
+    {
+        *self.call_observer.borrow_mut() = Some(CallObserver::new(clock, observer));
+    }
+
+    /// Removes the observer set with [`Runtime::set_call_observer`].
+    ///
+    /// [`Runtime::set_call_observer`]: #method.set_call_observer
+    #[cfg(feature = "profiling")]
+    pub fn clear_call_observer(&self) {
+        self.call_observer.borrow_mut().take();
+    }
+
+    /// Returns the highest number of stack slots that have been in use at once since this
+    /// runtime was created.
+    ///
+    /// This is determined by looking for the last slot that has been written to, so a slot that
+    /// happens to be overwritten with the same bit pattern it was initialized with may be missed.
+    pub fn stack_usage(&self) -> usize {
+        unsafe { &*self.stack() }
+            .iter()
+            .rposition(|&slot| slot != STACK_PAINT)
+            .map_or(0, |pos| pos + 1)
     }
 
     /// Returns the stack of this runtime.
@@ -187,6 +875,161 @@ impl Runtime {
         self.raw.as_ref().memory.mallocated
     }
 
+    /// Returns the current linear memory, which gets invalidated by calls into wasm as they may
+    /// grow it.
+    pub(crate) fn memory_slice_ptr(&self) -> *mut [u8] {
+        let mut len = 0;
+        let data = unsafe { ffi::m3_GetMemory(self.raw.as_ptr(), &mut len, 0) };
+        if data.is_null() {
+            ptr::slice_from_raw_parts_mut(NonNull::dangling().as_ptr(), 0)
+        } else {
+            ptr::slice_from_raw_parts_mut(data, len as usize)
+        }
+    }
+
+    /// Returns the part of the stack a call into wasm may use, which is all of it unless the call
+    /// is made from within a linked closure.
+    pub(crate) fn call_stack(&self) -> *mut [ffi::m3slot_t] {
+        let host_stack = self.host_stack.get();
+        if host_stack.is_null() {
+            return self.stack_mut();
+        }
+        let stack = self.stack_mut();
+        let used = (host_stack as usize - stack.cast::<ffi::m3slot_t>() as usize)
+            / mem::size_of::<ffi::m3slot_t>();
+        ptr::slice_from_raw_parts_mut(host_stack, unsafe { (*stack).len() } - used)
+    }
+
+    /// Runs `f`, which calls `func` in this runtime with the stack returned by
+    /// [`Runtime::call_stack`].
+    ///
+    /// wasm3 hands the runtime's `userdata` to linked closures, so it is pointed at this runtime
+    /// for the duration of the call. This allows closures to call back into wasm, see
+    /// [`CallContext::runtime`]. The runtime can not move while the call is in progress as it is
+    /// borrowed by it.
+    ///
+    /// With the `catch-unwind` feature a panic of a linked closure called by `func` is resumed
+    /// here, once the runtime is in a consistent state again.
+    ///
+    /// [`Runtime::call_stack`]: #method.call_stack
+    /// [`CallContext::runtime`]: struct.CallContext.html#method.runtime
+    #[inline]
+    pub(crate) fn enter(
+        &self,
+        func: &ffi::M3Function,
+        f: impl FnOnce() -> ffi::m3ret_t,
+    ) -> ffi::m3ret_t {
+        let raw = self.raw.as_ptr();
+        let prev = unsafe { mem::replace(&mut (*raw).userdata, self as *const Self as *mut _) };
+        let res = self.observed(func, || {
+            if self.host_stack.get().is_null() {
+                self.metered(f)
+            } else {
+                // the runtime's fuel is still installed by the outermost call
+                f()
+            }
+        });
+        unsafe { (*raw).userdata = prev };
+        #[cfg(feature = "catch-unwind")]
+        crate::unwind::resume(res);
+        res
+    }
+
+    /// Runs `f`, a linked closure invoked with the stack pointer `sp`, making calls into wasm it
+    /// makes use the stack above `sp`.
+    pub(crate) fn in_host_function<R>(&self, sp: ffi::m3stack_t, f: impl FnOnce() -> R) -> R {
+        // the guest may have grown the memory the closure is about to access
+        self.check_memory_growth();
+        let prev = self.host_stack.replace(sp.cast());
+        let res = f();
+        self.host_stack.set(prev);
+        res
+    }
+
+    /// Runs `f`, which calls into this runtime, with this runtime's fuel.
+    #[inline]
+    fn metered<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "metering")]
+        {
+            crate::metering::with_fuel(&self.fuel, f)
+        }
+        #[cfg(not(feature = "metering"))]
+        {
+            f()
+        }
+    }
+
+    /// Runs `f`, which calls `func` in this runtime, reporting the call to the call observer.
+    #[inline]
+    fn observed(&self, func: &ffi::M3Function, f: impl FnOnce() -> ffi::m3ret_t) -> ffi::m3ret_t {
+        #[cfg(feature = "profiling")]
+        {
+            crate::profiling::observe(&self.call_observer, func, f)
+        }
+        #[cfg(not(feature = "profiling"))]
+        {
+            let _ = func;
+            f()
+        }
+    }
+
+    /// Runs `f`, which calls into this runtime, with the runtime's fuel capped at `limit`, charging
+    /// whatever `f` consumed to the runtime's fuel afterwards, even if `f` panics.
+    ///
+    /// This also works from within a linked closure, as calls charge the runtime's fuel in place.
+    #[cfg(feature = "metering")]
+    pub(crate) fn with_fuel_limit<R>(&self, limit: u64, f: impl FnOnce() -> R) -> R {
+        struct Charge<'a> {
+            fuel: &'a Cell<u64>,
+            total: u64,
+            budget: u64,
+        }
+        impl Drop for Charge<'_> {
+            fn drop(&mut self) {
+                self.fuel.set(self.total - (self.budget - self.fuel.get()));
+            }
+        }
+
+        let total = self.fuel.get();
+        let budget = total.min(limit);
+        self.fuel.set(budget);
+        let _charge = Charge {
+            fuel: &self.fuel,
+            total,
+            budget,
+        };
+        f()
+    }
+
+    pub(crate) fn host_error(&self) -> Rc<RefCell<Option<String>>> {
+        self.host_error.clone()
+    }
+
+    /// Invokes the memory grow callback if the memory has grown since it was last checked.
+    pub(crate) fn check_memory_growth(&self) {
+        let size = self.memory_size();
+        if self.observed_memory_size.replace(size) < size {
+            // cloned out so the callback may replace itself without the cell being borrowed
+            let callback = self.memory_grow_callback.borrow().clone();
+            if let Some(MemoryGrowCallback(callback)) = callback {
+                callback(size);
+            }
+        }
+    }
+
+    /// Converts the result of a call into this runtime, picking up the error of a failed host
+    /// function.
+    pub(crate) fn call_result(&self, res: ffi::M3Result) -> Result<()> {
+        self.check_memory_growth();
+        if res == HOST_FUNCTION_FAILED.as_ptr().cast() {
+            Err(Error::HostFunction(
+                self.host_error.take().unwrap_or_default(),
+            ))
+        } else {
+            Error::from_ffi_res(res)
+        }
+    }
+
     pub(crate) fn push_closure(&self, closure: PinnedAnyClosure) {
         unsafe { (*self.closure_store.get()).push(closure) };
     }
@@ -196,6 +1039,241 @@ impl Runtime {
     }
 }
 
+/// A builder for a [`Runtime`], making all of its configuration explicit.
+///
+/// [`Runtime`]: struct.Runtime.html
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RuntimeBuilder {
+    stack_slots: u32,
+    memory_limit: Option<u32>,
+}
+
+impl Default for RuntimeBuilder {
+    fn default() -> Self {
+        RuntimeBuilder {
+            stack_slots: DEFAULT_STACK_SLOTS,
+            memory_limit: None,
+        }
+    }
+}
+
+impl RuntimeBuilder {
+    /// Creates a builder for a runtime with a stack of 64KiB and no memory limit besides the
+    /// maximum modules declare.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of slots of the runtime's stack.
+    pub fn stack_slots(mut self, stack_slots: u32) -> Self {
+        self.stack_slots = stack_slots;
+        self
+    }
+
+    /// Limits the runtime's linear memory to `max_memory_pages` 64KiB pages.
+    /// See [`Runtime::new_with_memory_limit`] for details.
+    ///
+    /// [`Runtime::new_with_memory_limit`]: struct.Runtime.html#method.new_with_memory_limit
+    pub fn memory_limit(mut self, max_memory_pages: u32) -> Self {
+        self.memory_limit = Some(max_memory_pages);
+        self
+    }
+
+    /// Creates the runtime in the given environment.
+    ///
+    /// # Errors
+    ///
+    /// This function will error on memory allocation failure, which includes stacks whose size
+    /// in bytes does not fit into a `u32`.
+    pub fn build(&self, environment: &Environment) -> Result<Runtime> {
+        let stack_size = self
+            .stack_slots
+            .checked_mul(mem::size_of::<ffi::m3slot_t>() as u32)
+            .ok_or_else(Error::malloc_error)?;
+        Runtime::new_impl(environment, stack_size, self.memory_limit)
+    }
+}
+
+/// A [`Runtime`] that can be sent to another thread.
+///
+/// A runtime can not be sent to other threads by itself, as the [`Environment`] it was created
+/// from holds state shared with everything else created from that environment, and closures
+/// linked into it are not required to be `Send`. This wrapper can therefore only be created for
+/// runtimes that are the sole owner of their environment, which means the environment has been
+/// dropped after the runtime was created from it and all of its modules have been loaded.
+///
+/// The runtime can not be used while it is wrapped, it has to be taken out again with
+/// [`SendRuntime::into_inner`] on the receiving thread.
+///
+/// # Example
+///
+/// ```ignore
+/// let env = Environment::new()?;
+/// let rt = env.create_runtime(1024 * 60)?;
+/// rt.parse_and_load_module(wasm)?;
+/// drop(env);
+/// let rt = SendRuntime::new(rt).map_err(|_| "runtime was not movable")?;
+/// std::thread::spawn(move || {
+///     let rt = rt.into_inner();
+///     rt.find_function::<(), ()>("main")?.call()
+/// });
+/// ```
+///
+/// [`Runtime`]: struct.Runtime.html
+/// [`Environment`]: struct.Environment.html
+/// [`SendRuntime::into_inner`]: #method.into_inner
+#[derive(Debug)]
+pub struct SendRuntime(Runtime);
+
+// SAFETY: all state reachable from the runtime is owned by it, as its environment is not shared
+// and the linked closures, memory grow callback and call observer with its clock are either
+// absent or guaranteed to be Send by the creator.
+unsafe impl Send for SendRuntime {}
+
+impl SendRuntime {
+    /// Wraps the given runtime so that it can be sent to another thread, returning it back if it
+    /// is not the sole owner of its environment or has closures linked, a memory grow callback or
+    /// a call observer set.
+    pub fn new(runtime: Runtime) -> core::result::Result<Self, Runtime> {
+        if Self::is_sendable(&runtime) {
+            Ok(SendRuntime(runtime))
+        } else {
+            Err(runtime)
+        }
+    }
+
+    fn is_sendable(runtime: &Runtime) -> bool {
+        #[cfg(feature = "profiling")]
+        let observed = runtime.call_observer.borrow().is_some();
+        #[cfg(not(feature = "profiling"))]
+        let observed = false;
+        runtime.environment.is_unique()
+            && unsafe { (*runtime.closure_store.get()).is_empty() }
+            && runtime.memory_grow_callback.borrow().is_none()
+            && !observed
+    }
+
+    /// Wraps the given runtime so that it can be sent to another thread without checking
+    /// whether that is sound.
+    ///
+    /// # Safety
+    ///
+    /// The runtime has to be the sole owner of its environment, meaning no other runtime,
+    /// [`Environment`] or [`ParsedModule`] may refer to the same environment, and all closures
+    /// linked into it as well as its memory grow callback, call observer and the observer's clock
+    /// have to be `Send`.
+    ///
+    /// [`Environment`]: struct.Environment.html
+    /// [`ParsedModule`]: struct.ParsedModule.html
+    pub unsafe fn new_unchecked(runtime: Runtime) -> Self {
+        SendRuntime(runtime)
+    }
+
+    /// Returns the wrapped runtime.
+    pub fn into_inner(self) -> Runtime {
+        self.0
+    }
+}
+
+/// A shared handle to a [`Runtime`], cloning it yields another handle to the same runtime.
+///
+/// The handle can be sent to and shared between threads, exclusive access to the runtime is
+/// enforced by a lock. The runtime is therefore wrapped in a [`SendRuntime`] first, and has to
+/// remain sendable while it is shared: no closures may be linked into it and no callbacks set,
+/// and its environment may not be shared with anything else.
+///
+/// ```ignore
+/// let rt = SendRuntime::new(rt).map_err(|_| "runtime was not movable")?;
+/// let handle = RuntimeHandle::new(rt);
+/// handle.lock().parse_and_load_module(wasm)?;
+/// let other = handle.clone();
+/// std::thread::spawn(move || {
+///     other.lock().find_function::<(), ()>("main")?.call()
+/// });
+/// ```
+///
+/// [`Runtime`]: struct.Runtime.html
+/// [`SendRuntime`]: struct.SendRuntime.html
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct RuntimeHandle(Arc<Mutex<Option<SendRuntime>>>);
+
+#[cfg(feature = "std")]
+impl RuntimeHandle {
+    /// Wraps the given runtime in a handle.
+    pub fn new(runtime: SendRuntime) -> Self {
+        RuntimeHandle(Arc::new(Mutex::new(Some(runtime))))
+    }
+
+    /// Locks the runtime for exclusive access, blocking until no other handle holds the lock.
+    ///
+    /// Once the returned guard is dropped the runtime is checked to still be sendable. If it is
+    /// not, the runtime is dropped on the current thread, where the state that can not be sent
+    /// was created, and the guard panics.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a thread panicked while holding the lock, which includes the runtime having been
+    /// dropped as it was made unsendable.
+    pub fn lock(&self) -> RuntimeGuard<'_> {
+        let guard = self
+            .0
+            .lock()
+            .expect("a thread panicked while holding the runtime");
+        RuntimeGuard(guard)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SendRuntime> for RuntimeHandle {
+    fn from(runtime: SendRuntime) -> Self {
+        RuntimeHandle::new(runtime)
+    }
+}
+
+/// Exclusive access to the runtime of a [`RuntimeHandle`], as returned by
+/// [`RuntimeHandle::lock`].
+///
+/// [`RuntimeHandle`]: struct.RuntimeHandle.html
+/// [`RuntimeHandle::lock`]: struct.RuntimeHandle.html#method.lock
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct RuntimeGuard<'h>(MutexGuard<'h, Option<SendRuntime>>);
+
+#[cfg(feature = "std")]
+impl Deref for RuntimeGuard<'_> {
+    type Target = Runtime;
+
+    fn deref(&self) -> &Runtime {
+        // the runtime is only taken out while panicking, which poisons the lock
+        &self.0.as_ref().unwrap().0
+    }
+}
+
+#[cfg(feature = "std")]
+impl DerefMut for RuntimeGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Runtime {
+        &mut self.0.as_mut().unwrap().0
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for RuntimeGuard<'_> {
+    fn drop(&mut self) {
+        // the runtime is handed out mutably, so it may have been given state that is not Send
+        let sendable = self
+            .0
+            .as_ref()
+            .map_or(true, |rt| SendRuntime::is_sendable(&rt.0));
+        if !sendable {
+            self.0.take();
+            if !std::thread::panicking() {
+                panic!("the runtime of a RuntimeHandle could no longer be sent to other threads");
+            }
+        }
+    }
+}
+
 impl Drop for Runtime {
     fn drop(&mut self) {
         unsafe { ffi::m3_FreeRuntime(self.raw.as_ptr()) };
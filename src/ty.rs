@@ -1,4 +1,8 @@
 // this module looks like a mess, lots of doc(hidden) attributes since rust traits cant have private functions
+use alloc::vec::Vec;
+
+use crate::{WasmPtr, WasmSlice};
+
 mod private {
     #[doc(hidden)]
     pub struct Seal;
@@ -58,6 +62,19 @@ const SIZE_IN_SLOT_COUNT: usize = 2;
 const SIZE_IN_SLOT_COUNT: usize = 1;
 
 /// Trait implemented by types that can be passed to and from wasm.
+///
+/// Tuples only implement [`WasmArgs`], they can not be returned as the bundled wasm3 does not
+/// support functions returning multiple values.
+///
+/// ```compile_fail
+/// # fn wasm() -> &'static [u8] { &[] }
+/// let env = wasm3::Environment::new().unwrap();
+/// let rt = env.create_runtime(1024).unwrap();
+/// let module = rt.parse_and_load_module(wasm()).unwrap();
+/// module.find_function::<(), (i32, i64)>("pair").unwrap();
+/// ```
+///
+/// [`WasmArgs`]: trait.WasmArgs.html
 pub trait WasmType: Sized {
     #[doc(hidden)]
     const TYPE_INDEX: u8;
@@ -84,6 +101,9 @@ pub trait WasmArgs {
     #[doc(hidden)]
     fn validate_types(types: &[u8]) -> bool;
     #[doc(hidden)]
+    // required for describing signature mismatches
+    fn push_type_indices(types: &mut Vec<u8>);
+    #[doc(hidden)]
     fn sealed_() -> private::Seal;
 }
 
@@ -208,6 +228,47 @@ impl WasmType for f64 {
     }
 }
 
+/// Booleans are passed as `i32`, with any non-zero value being read as `true`.
+impl WasmArg for bool {}
+impl WasmType for bool {
+    #[doc(hidden)]
+    const TYPE_INDEX: u8 = ffi::_bindgen_ty_1::c_m3Type_i32 as u8;
+    #[doc(hidden)]
+    const SIZE_IN_SLOT_COUNT: usize = SIZE_IN_SLOT_COUNT;
+    #[doc(hidden)]
+    unsafe fn pop_from_stack(stack: *mut ffi::m3slot_t) -> Self {
+        read_u32_from_stack(stack) != 0
+    }
+    #[doc(hidden)]
+    unsafe fn push_on_stack(self, stack: *mut ffi::m3slot_t) {
+        write_u32_to_stack(stack, self as u32);
+    }
+    #[doc(hidden)]
+    fn sealed_() -> private::Seal {
+        private::Seal
+    }
+}
+
+impl<T> WasmArg for WasmPtr<T> {}
+impl<T> WasmType for WasmPtr<T> {
+    #[doc(hidden)]
+    const TYPE_INDEX: u8 = ffi::_bindgen_ty_1::c_m3Type_i32 as u8;
+    #[doc(hidden)]
+    const SIZE_IN_SLOT_COUNT: usize = SIZE_IN_SLOT_COUNT;
+    #[doc(hidden)]
+    unsafe fn pop_from_stack(stack: *mut ffi::m3slot_t) -> Self {
+        WasmPtr::new(read_u32_from_stack(stack))
+    }
+    #[doc(hidden)]
+    unsafe fn push_on_stack(self, stack: *mut ffi::m3slot_t) {
+        write_u32_to_stack(stack, self.addr());
+    }
+    #[doc(hidden)]
+    fn sealed_() -> private::Seal {
+        private::Seal
+    }
+}
+
 impl WasmType for () {
     #[doc(hidden)]
     const TYPE_INDEX: u8 = ffi::_bindgen_ty_1::c_m3Type_none as u8;
@@ -233,6 +294,8 @@ impl WasmArgs for () {
         types.is_empty()
     }
     #[doc(hidden)]
+    fn push_type_indices(_: &mut Vec<u8>) {}
+    #[doc(hidden)]
     fn sealed_() -> private::Seal {
         private::Seal
     }
@@ -253,10 +316,58 @@ where
     }
     #[doc(hidden)]
     fn validate_types(types: &[u8]) -> bool {
-        types
-            .get(0)
-            .map(|&idx| idx == T::TYPE_INDEX)
-            .unwrap_or(false)
+        types == [T::TYPE_INDEX]
+    }
+    #[doc(hidden)]
+    fn push_type_indices(types: &mut Vec<u8>) {
+        types.push(T::TYPE_INDEX);
+    }
+    #[doc(hidden)]
+    fn sealed_() -> private::Seal {
+        private::Seal
+    }
+}
+
+impl WasmArgs for WasmSlice {
+    #[doc(hidden)]
+    unsafe fn push_on_stack(self, stack: *mut [ffi::m3slot_t]) {
+        WasmArgs::push_on_stack((self.ptr(), self.len()), stack);
+    }
+    #[doc(hidden)]
+    unsafe fn pop_from_stack(stack: *mut [ffi::m3slot_t]) -> Self {
+        let (ptr, len) = WasmArgs::pop_from_stack(stack);
+        WasmSlice::new(ptr, len)
+    }
+    #[doc(hidden)]
+    fn validate_types(types: &[u8]) -> bool {
+        <(u32, u32)>::validate_types(types)
+    }
+    #[doc(hidden)]
+    fn push_type_indices(types: &mut Vec<u8>) {
+        <(u32, u32)>::push_type_indices(types);
+    }
+    #[doc(hidden)]
+    fn sealed_() -> private::Seal {
+        private::Seal
+    }
+}
+
+impl WasmArgs for (WasmSlice,) {
+    #[doc(hidden)]
+    unsafe fn push_on_stack(self, stack: *mut [ffi::m3slot_t]) {
+        WasmArgs::push_on_stack(self.0, stack);
+    }
+    #[doc(hidden)]
+    unsafe fn pop_from_stack(stack: *mut [ffi::m3slot_t]) -> Self {
+        (WasmArgs::pop_from_stack(stack),)
+    }
+    #[doc(hidden)]
+    fn validate_types(types: &[u8]) -> bool {
+        WasmSlice::validate_types(types)
+    }
+    #[doc(hidden)]
+    fn push_type_indices(types: &mut Vec<u8>) {
+        WasmSlice::push_type_indices(types);
     }
     #[doc(hidden)]
     fn sealed_() -> private::Seal {
@@ -315,7 +426,13 @@ macro_rules! args_impl {
                 let mut ty_iter = types.iter();
                 $(
                     ty_iter.next().map(|&ty| ty == $types::TYPE_INDEX).unwrap_or(false)
-                )&&*
+                )&&* && ty_iter.next().is_none()
+            }
+            #[doc(hidden)]
+            fn push_type_indices(types: &mut Vec<u8>) {
+                $(
+                    types.push($types::TYPE_INDEX);
+                )*
             }
             #[doc(hidden)]
             fn sealed_() -> private::Seal { private::Seal }
@@ -327,6 +444,10 @@ args_impl!(A, B, C, D, E, F, G, H, J, K, L, M, N, O, P, Q);
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    // 64-bit values are read from two consecutive slots with 32-bit slots, so keep the stack aligned.
+    #[repr(align(8))]
+    struct Stack<const N: usize>([ffi::m3slot_t; N]);
     #[test]
     fn test_validate_types_single() {
         assert!(f64::validate_types(&[
@@ -368,6 +489,143 @@ mod tests {
         ]));
     }
 
+    #[test]
+    fn test_validate_types_extra_fail() {
+        let types = [
+            ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+            ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+        ];
+        assert!(!u32::validate_types(&types));
+        assert!(!<(u32,)>::validate_types(&types));
+        assert!(<(u32, u32)>::validate_types(&types));
+        assert!(!<(u32, u32, u32)>::validate_types(&types));
+    }
+
+    #[test]
+    fn test_validate_types_sixteen() {
+        type Args = (
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u32,
+            u64,
+            u32,
+            u32,
+            u32,
+        );
+        let mut types = [ffi::_bindgen_ty_1::c_m3Type_i32 as u8; 16];
+        assert!(!Args::validate_types(&types));
+        types[12] = ffi::_bindgen_ty_1::c_m3Type_i64 as u8;
+        assert!(Args::validate_types(&types));
+    }
+
+    #[test]
+    fn test_push_type_indices() {
+        let mut types = Vec::new();
+        <(u32, f64)>::push_type_indices(&mut types);
+        WasmSlice::push_type_indices(&mut types);
+        assert_eq!(
+            types,
+            [
+                ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+                ffi::_bindgen_ty_1::c_m3Type_f64 as u8,
+                ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+                ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+            ]
+        );
+        types.clear();
+        <()>::push_type_indices(&mut types);
+        assert!(types.is_empty());
+    }
+
+    #[test]
+    fn test_bool_stack() {
+        let mut stack = Stack([0; 2]);
+        unsafe {
+            WasmType::push_on_stack(true, stack.0.as_mut_ptr());
+            assert_eq!(<u32 as WasmType>::pop_from_stack(stack.0.as_mut_ptr()), 1);
+            WasmType::push_on_stack(false, stack.0.as_mut_ptr());
+            assert_eq!(<u32 as WasmType>::pop_from_stack(stack.0.as_mut_ptr()), 0);
+            WasmType::push_on_stack(42u32, stack.0.as_mut_ptr());
+            assert!(<bool as WasmType>::pop_from_stack(stack.0.as_mut_ptr()));
+        }
+        assert!(<(bool, u32)>::validate_types(&[
+            ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+            ffi::_bindgen_ty_1::c_m3Type_i32 as u8
+        ]));
+    }
+
+    #[test]
+    fn test_f32_stack_roundtrip_preserves_bits() {
+        let mut stack = Stack([0; 2]);
+        // signalling NaN with a payload, quiet NaN with a payload, smallest denormal, negative zero
+        for &bits in &[0x7FA0_0001u32, 0xFFC0_1234, 0x0000_0001, 0x8000_0000] {
+            unsafe {
+                WasmType::push_on_stack(f32::from_bits(bits), stack.0.as_mut_ptr());
+                let val: f32 = WasmType::pop_from_stack(stack.0.as_mut_ptr());
+                assert_eq!(val.to_bits(), bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_f64_stack_roundtrip_preserves_bits() {
+        let mut stack = Stack([0; 2]);
+        for &bits in &[
+            0x7FF4_0000_0000_0001u64,
+            0xFFF8_0000_DEAD_BEEF,
+            0x0000_0000_0000_0001,
+            0x000F_FFFF_FFFF_FFFF,
+            0x8000_0000_0000_0000,
+        ] {
+            unsafe {
+                WasmType::push_on_stack(f64::from_bits(bits), stack.0.as_mut_ptr());
+                let val: f64 = WasmType::pop_from_stack(stack.0.as_mut_ptr());
+                assert_eq!(val.to_bits(), bits);
+            }
+        }
+    }
+
+    #[test]
+    fn test_args_stack_roundtrip_floats() {
+        let mut stack = Stack([0; 20]);
+        let args = (
+            f32::from_bits(0x7FA0_0001),
+            1i32,
+            f64::from_bits(0x7FF4_0000_0000_0001),
+            f32::from_bits(0x0000_0001),
+            2u64,
+            f64::from_bits(0x0000_0000_0000_0001),
+            -3i64,
+            4u32,
+            f32::NAN,
+            f64::NEG_INFINITY,
+        );
+        unsafe {
+            WasmArgs::push_on_stack(args, &mut stack.0[..]);
+            let popped: (f32, i32, f64, f32, u64, f64, i64, u32, f32, f64) =
+                WasmArgs::pop_from_stack(&mut stack.0[..]);
+            assert_eq!(popped.0.to_bits(), args.0.to_bits());
+            assert_eq!(popped.1, args.1);
+            assert_eq!(popped.2.to_bits(), args.2.to_bits());
+            assert_eq!(popped.3.to_bits(), args.3.to_bits());
+            assert_eq!(popped.4, args.4);
+            assert_eq!(popped.5.to_bits(), args.5.to_bits());
+            assert_eq!(popped.6, args.6);
+            assert_eq!(popped.7, args.7);
+            assert_eq!(popped.8.to_bits(), args.8.to_bits());
+            assert_eq!(popped.9.to_bits(), args.9.to_bits());
+        }
+    }
+
     #[test]
     fn test_validate_types_quintuple_fail() {
         assert!(!<(f64, u32, i32, i64, f32)>::validate_types(&[
@@ -0,0 +1,34 @@
+//! Catching panics of linked closures, as unwinding out of them into wasm3's C code would be
+//! undefined behavior. A caught panic makes the call trap and is resumed once the trap has made
+//! its way back to the Rust caller.
+use alloc::boxed::Box;
+use core::any::Any;
+use core::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+/// The trap message returned by a linked closure that panicked.
+/// Its address identifies the trap when it gets returned from a call.
+pub(crate) static HOST_PANIC: [u8; 23] = *b"host function panicked\0";
+
+std::thread_local! {
+    // the payload of the panic that made the current call trap
+    static PANIC: RefCell<Option<Box<dyn Any + Send>>> = RefCell::new(None);
+}
+
+/// Runs `f`, a linked closure, trapping with `HOST_PANIC` if it panics.
+pub(crate) fn catch<R>(f: impl FnOnce() -> Result<R, ffi::M3Result>) -> Result<R, ffi::M3Result> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        PANIC.with(|panic| *panic.borrow_mut() = Some(payload));
+        Err(HOST_PANIC.as_ptr().cast())
+    })
+}
+
+/// Resumes the panic that made a call return `res`, if it trapped because of one.
+pub(crate) fn resume(res: ffi::m3ret_t) {
+    if res.cast() != HOST_PANIC.as_ptr() {
+        return;
+    }
+    if let Some(payload) = PANIC.with(|panic| panic.borrow_mut().take()) {
+        panic::resume_unwind(payload);
+    }
+}
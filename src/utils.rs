@@ -17,10 +17,14 @@ pub unsafe fn cstr_to_str<'a>(ptr: *const cty::c_char) -> &'a str {
 }
 
 pub unsafe fn eq_cstr_str(cstr: *const cty::c_char, str: &str) -> bool {
+    eq_cstr_bytes(cstr, str.as_bytes())
+}
+
+pub unsafe fn eq_cstr_bytes(cstr: *const cty::c_char, bytes: &[u8]) -> bool {
     if cstr.is_null() {
         return false;
     }
-    let mut bytes = str.as_bytes().iter();
+    let mut bytes = bytes.iter();
     let mut cstr = cstr.cast::<u8>();
     loop {
         match (bytes.next(), *cstr) {
@@ -97,4 +101,11 @@ mod tests {
         let str = "abc";
         assert!(unsafe { !eq_cstr_str(cstr.as_ptr().cast(), str) });
     }
+
+    #[test]
+    fn test_eq_cstr_bytes_invalid_utf8() {
+        let cstr = b"ab\xffcd\0";
+        assert!(unsafe { eq_cstr_bytes(cstr.as_ptr().cast(), b"ab\xffcd") });
+        assert!(unsafe { !eq_cstr_bytes(cstr.as_ptr().cast(), b"ab\xfecd") });
+    }
 }
@@ -0,0 +1,316 @@
+use core::slice;
+
+use crate::WasmType;
+
+/// The type of a wasm value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum ValueType {
+    /// A 32-bit integer.
+    I32 = ffi::_bindgen_ty_1::c_m3Type_i32 as u8,
+    /// A 64-bit integer.
+    I64 = ffi::_bindgen_ty_1::c_m3Type_i64 as u8,
+    /// A 32-bit float.
+    F32 = ffi::_bindgen_ty_1::c_m3Type_f32 as u8,
+    /// A 64-bit float.
+    F64 = ffi::_bindgen_ty_1::c_m3Type_f64 as u8,
+}
+
+impl ValueType {
+    /// Converts a wasm3 type, returning `None` for `none` and unknown types.
+    pub(crate) fn from_type_index(type_index: u8) -> Option<Self> {
+        match type_index {
+            ty if ty == ValueType::I32 as u8 => Some(ValueType::I32),
+            ty if ty == ValueType::I64 as u8 => Some(ValueType::I64),
+            ty if ty == ValueType::F32 as u8 => Some(ValueType::F32),
+            ty if ty == ValueType::F64 as u8 => Some(ValueType::F64),
+            _ => None,
+        }
+    }
+
+    /// Reinterprets a slice of wasm3 types, returning `None` if any of them is `none` or unknown.
+    pub(crate) fn from_type_indices(type_indices: &[u8]) -> Option<&[Self]> {
+        if type_indices
+            .iter()
+            .all(|&ty| Self::from_type_index(ty).is_some())
+        {
+            // SAFETY: ValueType is repr(u8) and all bytes have been checked to be valid discriminants
+            Some(unsafe { slice::from_raw_parts(type_indices.as_ptr().cast(), type_indices.len()) })
+        } else {
+            None
+        }
+    }
+}
+
+/// A dynamically typed wasm value.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Value {
+    /// A 32-bit integer.
+    I32(i32),
+    /// A 64-bit integer.
+    I64(i64),
+    /// A 32-bit float.
+    F32(f32),
+    /// A 64-bit float.
+    F64(f64),
+}
+
+impl Value {
+    /// The type of this value.
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::I32(_) => ValueType::I32,
+            Value::I64(_) => ValueType::I64,
+            Value::F32(_) => ValueType::F32,
+            Value::F64(_) => ValueType::F64,
+        }
+    }
+
+    /// Parses a value of the given type from a string, `None` if it is not a valid value of that
+    /// type.
+    ///
+    /// Integers may be decimal or `0x` prefixed hexadecimal with an optional sign, and unsigned
+    /// values that only fit the integer type when reinterpreted as unsigned are accepted as well.
+    /// Floats are parsed the way [`str::parse`] parses them.
+    ///
+    /// [`str::parse`]: https://doc.rust-lang.org/core/primitive.str.html#method.parse
+    pub fn parse(ty: ValueType, s: &str) -> Option<Self> {
+        match ty {
+            ValueType::I32 => parse_int(s)
+                .filter(|&val| val >= i128::from(i32::MIN) && val <= i128::from(u32::MAX))
+                .map(|val| Value::I32(val as u32 as i32)),
+            ValueType::I64 => parse_int(s)
+                .filter(|&val| val >= i128::from(i64::MIN) && val <= i128::from(u64::MAX))
+                .map(|val| Value::I64(val as u64 as i64)),
+            ValueType::F32 => s.parse().ok().map(Value::F32),
+            ValueType::F64 => s.parse().ok().map(Value::F64),
+        }
+    }
+
+    /// Reinterprets a raw slot as used by [`Function::call_raw`] as a value of the given type.
+    ///
+    /// [`Function::call_raw`]: struct.Function.html#method.call_raw
+    pub(crate) fn from_raw_slot(ty: ValueType, slot: u64) -> Self {
+        match ty {
+            ValueType::I32 => Value::I32(slot as u32 as i32),
+            ValueType::I64 => Value::I64(slot as i64),
+            ValueType::F32 => Value::F32(f32::from_bits(slot as u32)),
+            ValueType::F64 => Value::F64(f64::from_bits(slot)),
+        }
+    }
+
+    /// Converts this value into a raw slot as used by [`Function::call_raw`], 32-bit values are
+    /// zero extended.
+    ///
+    /// [`Function::call_raw`]: struct.Function.html#method.call_raw
+    pub(crate) fn to_raw_slot(self) -> u64 {
+        match self {
+            Value::I32(val) => u64::from(val as u32),
+            Value::I64(val) => val as u64,
+            Value::F32(val) => u64::from(val.to_bits()),
+            Value::F64(val) => val.to_bits(),
+        }
+    }
+
+    pub(crate) fn type_index(&self) -> u8 {
+        match self {
+            Value::I32(_) => i32::TYPE_INDEX,
+            Value::I64(_) => i64::TYPE_INDEX,
+            Value::F32(_) => f32::TYPE_INDEX,
+            Value::F64(_) => f64::TYPE_INDEX,
+        }
+    }
+
+    pub(crate) fn size_in_slot_count(&self) -> usize {
+        match self {
+            Value::I32(_) => i32::SIZE_IN_SLOT_COUNT,
+            Value::I64(_) => i64::SIZE_IN_SLOT_COUNT,
+            Value::F32(_) => f32::SIZE_IN_SLOT_COUNT,
+            Value::F64(_) => f64::SIZE_IN_SLOT_COUNT,
+        }
+    }
+
+    pub(crate) unsafe fn push_on_stack(self, stack: *mut ffi::m3slot_t) {
+        match self {
+            Value::I32(val) => val.push_on_stack(stack),
+            Value::I64(val) => val.push_on_stack(stack),
+            Value::F32(val) => val.push_on_stack(stack),
+            Value::F64(val) => val.push_on_stack(stack),
+        }
+    }
+
+    /// Reads a value of the given wasm3 type off the stack, returning `None` for `none` and unknown types.
+    pub(crate) unsafe fn pop_from_stack(type_index: u8, stack: *mut ffi::m3slot_t) -> Option<Self> {
+        match type_index {
+            ty if ty == i32::TYPE_INDEX => Some(Value::I32(i32::pop_from_stack(stack))),
+            ty if ty == i64::TYPE_INDEX => Some(Value::I64(i64::pop_from_stack(stack))),
+            ty if ty == f32::TYPE_INDEX => Some(Value::F32(f32::pop_from_stack(stack))),
+            ty if ty == f64::TYPE_INDEX => Some(Value::F64(f64::pop_from_stack(stack))),
+            _ => None,
+        }
+    }
+
+    /// Converts a tagged value handed out by wasm3, returning `None` for `none` and unknown types.
+    pub(crate) unsafe fn from_tagged(tagged: &ffi::M3TaggedValue) -> Option<Self> {
+        match tagged.type_ as u8 {
+            ty if ty == i32::TYPE_INDEX => Some(Value::I32(tagged.value.i32 as i32)),
+            ty if ty == i64::TYPE_INDEX => Some(Value::I64(tagged.value.i64 as i64)),
+            ty if ty == f32::TYPE_INDEX => Some(Value::F32(tagged.value.f32)),
+            ty if ty == f64::TYPE_INDEX => Some(Value::F64(tagged.value.f64)),
+            _ => None,
+        }
+    }
+
+    /// Converts this value into a tagged value that can be handed to wasm3.
+    pub(crate) fn to_tagged(self) -> ffi::M3TaggedValue {
+        let mut tagged = unsafe { core::mem::zeroed::<ffi::M3TaggedValue>() };
+        tagged.type_ = self.type_index() as _;
+        match self {
+            Value::I32(val) => tagged.value.i32 = val as u32,
+            Value::I64(val) => tagged.value.i64 = val as u64,
+            Value::F32(val) => tagged.value.f32 = val,
+            Value::F64(val) => tagged.value.f64 = val,
+        }
+        tagged
+    }
+}
+
+/// Parses a decimal or `0x` prefixed hexadecimal integer with an optional sign.
+fn parse_int(s: &str) -> Option<i128> {
+    let (negative, digits) = match s.as_bytes().first()? {
+        b'-' => (true, &s[1..]),
+        b'+' => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") | Some("0X") => (16, &digits[2..]),
+        _ => (10, digits),
+    };
+    // from_str_radix accepts a sign of its own, which must not follow the prefix
+    if !digits.bytes().next()?.is_ascii_hexdigit() {
+        return None;
+    }
+    let magnitude = i128::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -magnitude } else { magnitude })
+}
+
+macro_rules! value_from_impl {
+    ($($ty:ident => $variant:ident),*) => {
+        $(
+            impl From<$ty> for Value {
+                #[inline]
+                fn from(val: $ty) -> Self {
+                    Value::$variant(val)
+                }
+            }
+        )*
+    };
+}
+value_from_impl!(i32 => I32, i64 => I64, f32 => F32, f64 => F64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 64-bit values are read from two consecutive slots with 32-bit slots, so keep the stack aligned.
+    #[repr(align(8))]
+    struct Stack([ffi::m3slot_t; 2]);
+
+    #[test]
+    fn test_value_stack_roundtrip() {
+        let mut stack = Stack([0; 2]);
+        for &val in &[
+            Value::I32(-7),
+            Value::I64(-0xDEAD_BEEF),
+            Value::F32(1.5),
+            Value::F64(-2.25),
+        ] {
+            unsafe {
+                val.push_on_stack(stack.0.as_mut_ptr());
+                assert_eq!(
+                    Value::pop_from_stack(val.type_index(), stack.0.as_mut_ptr()),
+                    Some(val)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_value_type_indices() {
+        for &val in &[
+            Value::I32(0),
+            Value::I64(0),
+            Value::F32(0.0),
+            Value::F64(0.0),
+        ] {
+            assert_eq!(val.value_type() as u8, val.type_index());
+        }
+        let types = [i64::TYPE_INDEX, f32::TYPE_INDEX, i32::TYPE_INDEX];
+        assert_eq!(
+            ValueType::from_type_indices(&types),
+            Some(&[ValueType::I64, ValueType::F32, ValueType::I32][..])
+        );
+        assert_eq!(
+            ValueType::from_type_indices(&[f64::TYPE_INDEX, <()>::TYPE_INDEX]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_value_pop_none() {
+        let mut stack = Stack([0; 2]);
+        assert_eq!(
+            unsafe { Value::pop_from_stack(<()>::TYPE_INDEX, stack.0.as_mut_ptr()) },
+            None
+        );
+    }
+
+    #[test]
+    fn test_value_raw_slot_roundtrip() {
+        for &val in &[
+            Value::I32(-7),
+            Value::I64(-0xDEAD_BEEF),
+            Value::F32(1.5),
+            Value::F64(-2.25),
+        ] {
+            assert_eq!(
+                Value::from_raw_slot(val.value_type(), val.to_raw_slot()),
+                val
+            );
+        }
+        assert_eq!(Value::I32(-1).to_raw_slot(), 0xFFFF_FFFF);
+        assert_eq!(
+            Value::from_raw_slot(ValueType::I32, 0xFFFF_FFFF_0000_0002),
+            Value::I32(2)
+        );
+    }
+
+    #[test]
+    fn test_value_parse() {
+        assert_eq!(Value::parse(ValueType::I32, "-42"), Some(Value::I32(-42)));
+        assert_eq!(Value::parse(ValueType::I32, "+0x1F"), Some(Value::I32(31)));
+        assert_eq!(
+            Value::parse(ValueType::I32, "0xFFFFFFFF"),
+            Some(Value::I32(-1))
+        );
+        assert_eq!(Value::parse(ValueType::I32, "4294967296"), None);
+        assert_eq!(Value::parse(ValueType::I32, "-2147483649"), None);
+        assert_eq!(
+            Value::parse(ValueType::I64, "-0x8000000000000000"),
+            Some(Value::I64(i64::MIN))
+        );
+        assert_eq!(
+            Value::parse(ValueType::I64, "18446744073709551615"),
+            Some(Value::I64(-1))
+        );
+        assert_eq!(Value::parse(ValueType::F32, "1.5"), Some(Value::F32(1.5)));
+        assert_eq!(
+            Value::parse(ValueType::F64, "-2e3"),
+            Some(Value::F64(-2000.0))
+        );
+        for &invalid in &["", "-", "0x", "0x-1", "--1", "1.5", "abc", " 1"] {
+            assert_eq!(Value::parse(ValueType::I32, invalid), None, "{:?}", invalid);
+        }
+        assert_eq!(Value::parse(ValueType::F64, "one"), None);
+    }
+}
@@ -0,0 +1,246 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ptr;
+
+use crate::error::{Error, Result};
+use crate::function::CallContext;
+use crate::module::Module;
+use crate::ty::{WasmArgs, WasmType};
+
+const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+const ERRNO_SUCCESS: u32 = 0;
+const ERRNO_BADF: u32 = 8;
+const ERRNO_FAULT: u32 = 21;
+
+const FD_STDOUT: u32 = 1;
+const FD_STDERR: u32 = 2;
+
+/// A buffer collecting the output a WASI program writes to stdout or stderr, see
+/// [`WasiConfig::stdout`] and [`WasiConfig::stderr`].
+///
+/// Clones share the same underlying buffer, so a clone can be handed to the [`WasiConfig`] while
+/// the original is kept around to retrieve the output after the program has run.
+///
+/// [`WasiConfig::stdout`]: struct.WasiConfig.html#method.stdout
+/// [`WasiConfig::stderr`]: struct.WasiConfig.html#method.stderr
+/// [`WasiConfig`]: struct.WasiConfig.html
+#[derive(Clone, Debug, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    /// Creates a new, empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the output collected so far, leaving the buffer empty.
+    pub fn take(&self) -> Vec<u8> {
+        core::mem::take(&mut *self.0.borrow_mut())
+    }
+}
+
+/// The arguments, environment variables and output buffers of a WASI program, see
+/// [`Module::link_wasi_with_config`].
+///
+/// Directories can not be preopened: the WASI implementation bundled with wasm3 always preopens
+/// the current working directory of the host process as both `/` and `./`, and offers no way of
+/// changing this.
+///
+/// [`Module::link_wasi_with_config`]: struct.Module.html#method.link_wasi_with_config
+#[derive(Clone, Debug, Default)]
+pub struct WasiConfig {
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+    stdout: Option<OutputBuffer>,
+    stderr: Option<OutputBuffer>,
+}
+
+impl WasiConfig {
+    /// Creates a configuration without any arguments or environment variables.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the arguments of the program, which conventionally start with the program's name.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the environment variables of the program as pairs of names and values.
+    pub fn env<I, K, V>(mut self, env: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env = env
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self
+    }
+
+    /// Captures everything the program writes to stdout in `buffer` instead of writing it to the
+    /// stdout of the host process.
+    pub fn stdout(mut self, buffer: OutputBuffer) -> Self {
+        self.stdout = Some(buffer);
+        self
+    }
+
+    /// Captures everything the program writes to stderr in `buffer` instead of writing it to the
+    /// stderr of the host process.
+    pub fn stderr(mut self, buffer: OutputBuffer) -> Self {
+        self.stderr = Some(buffer);
+        self
+    }
+}
+
+/// Links the argument and environment functions of WASI to `module`, replacing the ones linked
+/// by wasm3 which only know about a single, process wide set of arguments. If output buffers are
+/// configured `fd_write` is replaced as well.
+pub(crate) fn link_config(module: &mut Module<'_>, config: &WasiConfig) -> Result<()> {
+    let args = config.args.iter().map(|arg| nul_terminated(arg.clone()));
+    link_strings(module, "args_sizes_get", "args_get", args.collect())?;
+    let env = config
+        .env
+        .iter()
+        .map(|(key, value)| nul_terminated(format!("{}={}", key, value)));
+    link_strings(module, "environ_sizes_get", "environ_get", env.collect())?;
+    if config.stdout.is_some() || config.stderr.is_some() {
+        link_fd_write(module, config.stdout.clone(), config.stderr.clone())?;
+    }
+    Ok(())
+}
+
+fn nul_terminated(s: String) -> Box<[u8]> {
+    let mut bytes = s.into_bytes();
+    bytes.push(0);
+    bytes.into_boxed_slice()
+}
+
+/// Links a pair of WASI functions that report the sizes of and copy out a list of strings, which
+/// is how both the arguments and the environment are handed to a program.
+fn link_strings(
+    module: &mut Module<'_>,
+    sizes_get: &str,
+    get: &str,
+    strings: Rc<[Box<[u8]>]>,
+) -> Result<()> {
+    let sizes = strings.clone();
+    ignore_missing(module.link_closure::<(u32, u32), u32, _>(
+        WASI_MODULE,
+        sizes_get,
+        move |mut cc, (count_ptr, buf_size_ptr)| {
+            let buf_size = sizes.iter().map(|s| s.len()).sum::<usize>();
+            let mut memory = cc.memory_view();
+            Ok(errno(
+                memory
+                    .write_u32(count_ptr as usize, sizes.len() as u32)
+                    .and_then(|()| memory.write_u32(buf_size_ptr as usize, buf_size as u32)),
+            ))
+        },
+    ))?;
+    ignore_missing(module.link_closure::<(u32, u32), u32, _>(
+        WASI_MODULE,
+        get,
+        move |mut cc, (ptrs, buf)| {
+            let mut memory = cc.memory_view();
+            let mut offset = buf as usize;
+            let res = strings.iter().enumerate().try_for_each(|(idx, s)| {
+                memory.write_u32(ptrs as usize + idx * 4, offset as u32)?;
+                memory.write_bytes(offset, s)?;
+                offset += s.len();
+                Ok(())
+            });
+            Ok(errno(res))
+        },
+    ))
+}
+
+/// Links `fd_write` so that writes to stdout and stderr end up in the given buffers, writes to
+/// any other file descriptor are passed on to the implementation linked by wasm3.
+fn link_fd_write(
+    module: &mut Module<'_>,
+    stdout: Option<OutputBuffer>,
+    stderr: Option<OutputBuffer>,
+) -> Result<()> {
+    // this has to be looked up before it gets replaced below
+    let original = module.linked_raw_call(WASI_MODULE, "fd_write");
+    ignore_missing(
+        module.link_closure_impl_boxed::<(u32, u32, u32, u32), u32, _>(
+            WASI_MODULE,
+            "fd_write",
+            move |mut cc, (fd, iovs, iovs_len, nwritten)| {
+                let buffer = match fd {
+                    FD_STDOUT => stdout.as_ref(),
+                    FD_STDERR => stderr.as_ref(),
+                    _ => None,
+                };
+                match (buffer, original) {
+                    (Some(buffer), _) => {
+                        Ok(errno(write_iovs(&mut cc, buffer, iovs, iovs_len, nwritten)))
+                    }
+                    (None, Some(original)) => unsafe {
+                        let mut stack = [0; 8];
+                        let stack = ptr::slice_from_raw_parts_mut(stack.as_mut_ptr(), stack.len());
+                        (fd, iovs, iovs_len, nwritten).push_on_stack(stack);
+                        let trap = original(cc.as_ptr(), stack.cast(), cc.memory_mut().cast());
+                        if trap.is_null() {
+                            Ok(u32::pop_from_stack(stack.cast()))
+                        } else {
+                            Err(trap.cast())
+                        }
+                    },
+                    (None, None) => Ok(ERRNO_BADF),
+                }
+            },
+        ),
+    )
+}
+
+/// Appends the contents of the `iovs_len` iovecs at `iovs` to `buffer`, storing the number of
+/// bytes written at `nwritten`.
+fn write_iovs(
+    cc: &mut CallContext<'_>,
+    buffer: &OutputBuffer,
+    iovs: u32,
+    iovs_len: u32,
+    nwritten: u32,
+) -> Result<()> {
+    let mut memory = cc.memory_view();
+    let mut output = buffer.0.borrow_mut();
+    let mut written = 0u32;
+    for idx in 0..iovs_len as usize {
+        let iov = iovs as usize + idx * 8;
+        let buf = memory.read_u32(iov)?;
+        let len = memory.read_u32(iov + 4)?;
+        output.extend_from_slice(memory.read_bytes(buf as usize, len as usize)?);
+        written = written.wrapping_add(len);
+    }
+    memory.write_u32(nwritten as usize, written)
+}
+
+fn errno(res: Result<()>) -> u32 {
+    match res {
+        Ok(()) => ERRNO_SUCCESS,
+        Err(_) => ERRNO_FAULT,
+    }
+}
+
+/// Modules only import the WASI functions they use, so missing ones are not an error.
+fn ignore_missing(res: Result<()>) -> Result<()> {
+    match res {
+        Err(Error::FunctionNotFound) => Ok(()),
+        res => res,
+    }
+}
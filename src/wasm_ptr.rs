@@ -0,0 +1,291 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::error::{Error, Result};
+use crate::memory::MemoryView;
+
+/// A typed pointer into the linear memory of a wasm module.
+///
+/// This is a `u32` offset that can be passed to and from wasm in place of a plain `i32`, for
+/// example as an argument of a closure linked with [`Module::link_closure`]. The null pointer `0`
+/// is a valid memory location in wasm, so pointers are not checked for null when being accessed.
+///
+/// [`Module::link_closure`]: struct.Module.html#method.link_closure
+pub struct WasmPtr<T> {
+    addr: u32,
+    _pd: PhantomData<fn() -> T>,
+}
+
+impl<T> WasmPtr<T> {
+    /// Creates a pointer to the given offset in linear memory.
+    #[inline]
+    pub const fn new(addr: u32) -> Self {
+        WasmPtr {
+            addr,
+            _pd: PhantomData,
+        }
+    }
+
+    /// Creates a null pointer.
+    #[inline]
+    pub const fn null() -> Self {
+        Self::new(0)
+    }
+
+    /// The offset in linear memory this pointer points to.
+    #[inline]
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    /// Whether this is a null pointer.
+    #[inline]
+    pub const fn is_null(&self) -> bool {
+        self.addr == 0
+    }
+
+    /// Returns a pointer `count` values of type `T` past this one, `None` if it would overflow
+    /// the 32-bit address space.
+    #[inline]
+    pub fn offset(&self, count: u32) -> Option<Self> {
+        u32::try_from(mem::size_of::<T>())
+            .ok()
+            .and_then(|size| size.checked_mul(count))
+            .and_then(|delta| self.addr.checked_add(delta))
+            .map(Self::new)
+    }
+
+    /// Reads the value this pointer points to.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds.
+    #[cfg(feature = "bytemuck")]
+    pub fn deref(&self, memory: &MemoryView<'_>) -> Result<T>
+    where
+        T: bytemuck::Pod,
+    {
+        memory
+            .read_bytes(self.addr as usize, mem::size_of::<T>())
+            .map(bytemuck::pod_read_unaligned)
+    }
+
+    /// Writes `value` to the location this pointer points to.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the access is out of bounds, in which case nothing is written.
+    #[cfg(feature = "bytemuck")]
+    pub fn write(&self, memory: &mut MemoryView<'_>, value: T) -> Result<()>
+    where
+        T: bytemuck::Pod,
+    {
+        memory.write_bytes(self.addr as usize, bytemuck::bytes_of(&value))
+    }
+}
+
+impl<T> Copy for WasmPtr<T> {}
+impl<T> Clone for WasmPtr<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Eq for WasmPtr<T> {}
+impl<T> PartialEq for WasmPtr<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.addr == other.addr
+    }
+}
+
+impl<T> Hash for WasmPtr<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.addr.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for WasmPtr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WasmPtr({:#x})", self.addr)
+    }
+}
+
+impl<T> From<WasmPtr<T>> for u32 {
+    fn from(ptr: WasmPtr<T>) -> Self {
+        ptr.addr
+    }
+}
+
+/// A buffer in the linear memory of a wasm module, described by a pointer and a length.
+///
+/// This can be used as the arguments of a closure linked with [`Module::link_closure`], either
+/// on its own or as `(WasmSlice,)`, in which case it is read from two consecutive `i32`
+/// arguments holding the pointer and the length.
+///
+/// [`Module::link_closure`]: struct.Module.html#method.link_closure
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct WasmSlice {
+    ptr: u32,
+    len: u32,
+}
+
+impl WasmSlice {
+    /// Creates a slice of `len` bytes starting at `ptr`.
+    #[inline]
+    pub const fn new(ptr: u32, len: u32) -> Self {
+        WasmSlice { ptr, len }
+    }
+
+    /// The offset in linear memory the slice starts at.
+    #[inline]
+    pub const fn ptr(&self) -> u32 {
+        self.ptr
+    }
+
+    /// The length of the slice in bytes.
+    #[inline]
+    pub const fn len(&self) -> u32 {
+        self.len
+    }
+
+    /// Whether the slice is empty.
+    #[inline]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn checked_end(&self) -> Result<()> {
+        match self.ptr.checked_add(self.len) {
+            Some(_) => Ok(()),
+            None => Err(Error::OutOfBoundsMemoryAccess {
+                offset: self.ptr as usize,
+                len: self.len as usize,
+            }),
+        }
+    }
+
+    /// Returns the bytes of this slice.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the slice wraps around the 32-bit address space or is out
+    /// of bounds of the memory.
+    pub fn as_bytes<'m>(&self, memory: &'m MemoryView<'_>) -> Result<&'m [u8]> {
+        self.checked_end()?;
+        memory.read_bytes(self.ptr as usize, self.len as usize)
+    }
+
+    /// Returns the bytes of this slice mutably.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the slice wraps around the 32-bit address space or is out
+    /// of bounds of the memory.
+    pub fn as_bytes_mut<'m>(&self, memory: &'m mut MemoryView<'_>) -> Result<&'m mut [u8]> {
+        self.checked_end()?;
+        memory.get_mut(self.ptr as usize, self.len as usize)
+    }
+
+    /// Writes the UTF-8 bytes of `s` to the start of this slice, returning the slice of the
+    /// written bytes. This allows filling a buffer the guest passed to a host function.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if `s` is longer than this slice, or the slice wraps around the
+    /// 32-bit address space or is out of bounds of the memory, in which case nothing is written.
+    pub fn write_str(&self, memory: &mut MemoryView<'_>, s: &str) -> Result<WasmSlice> {
+        let len = u32::try_from(s.len())
+            .ok()
+            .filter(|&len| len <= self.len)
+            .ok_or(Error::OutOfBoundsMemoryAccess {
+                offset: self.ptr as usize,
+                len: s.len(),
+            })?;
+        let written = WasmSlice::new(self.ptr, len);
+        written.as_bytes_mut(memory)?.copy_from_slice(s.as_bytes());
+        Ok(written)
+    }
+
+    /// Returns the bytes of this slice as a UTF-8 string.
+    ///
+    /// # Errors
+    ///
+    /// This function will error if the slice wraps around the 32-bit address space, is out of
+    /// bounds of the memory or the bytes are not valid UTF-8.
+    pub fn as_str<'m>(&self, memory: &'m MemoryView<'_>) -> Result<&'m str> {
+        self.checked_end()?;
+        memory.read_str(self.ptr as usize, self.len as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_ptr_offset() {
+        let ptr = WasmPtr::<u64>::new(16);
+        assert_eq!(ptr.offset(0), Some(ptr));
+        assert_eq!(ptr.offset(2), Some(WasmPtr::new(32)));
+        assert_eq!(ptr.offset(u32::MAX / 8), None);
+        assert_eq!(
+            WasmPtr::<()>::new(u32::MAX).offset(u32::MAX),
+            Some(WasmPtr::new(u32::MAX))
+        );
+    }
+
+    #[test]
+    fn test_wasm_ptr_null() {
+        assert!(WasmPtr::<u8>::null().is_null());
+        assert!(!WasmPtr::<u8>::new(1).is_null());
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_wasm_ptr_deref_write() {
+        let mut memory = [0; 16];
+        let mut view = MemoryView::new(&mut memory);
+        let ptr = WasmPtr::<u32>::new(5);
+        ptr.write(&mut view, 0xDEAD_BEEF).unwrap();
+        assert_eq!(ptr.deref(&view), Ok(0xDEAD_BEEF));
+        assert_eq!(view.read_u32(5), Ok(0xDEAD_BEEF));
+        assert!(WasmPtr::<u32>::new(13).deref(&view).is_err());
+        assert!(WasmPtr::<u32>::new(13).write(&mut view, 1).is_err());
+    }
+
+    #[test]
+    fn test_wasm_slice_bounds() {
+        let mut memory = *b"hello, wasm";
+        let mut view = MemoryView::new(&mut memory);
+        assert_eq!(WasmSlice::new(7, 4).as_bytes(&view), Ok(&b"wasm"[..]));
+        assert_eq!(WasmSlice::new(0, 5).as_str(&view), Ok("hello"));
+        assert_eq!(WasmSlice::new(11, 0).as_bytes(&view), Ok(&[][..]));
+        assert!(WasmSlice::new(8, 4).as_bytes(&view).is_err());
+        assert!(WasmSlice::new(u32::MAX, 2).as_bytes(&view).is_err());
+        WasmSlice::new(0, 1)
+            .as_bytes_mut(&mut view)
+            .unwrap()
+            .copy_from_slice(b"j");
+        assert_eq!(view.read_str(0, 5), Ok("jello"));
+        assert!(WasmSlice::new(u32::MAX, 1).as_bytes_mut(&mut view).is_err());
+    }
+
+    #[test]
+    fn test_wasm_slice_write_str() {
+        let mut memory = *b"hello, wasm";
+        let mut view = MemoryView::new(&mut memory);
+        let buffer = WasmSlice::new(7, 4);
+        assert_eq!(
+            buffer.write_str(&mut view, "host"),
+            Ok(WasmSlice::new(7, 4))
+        );
+        assert_eq!(buffer.write_str(&mut view, "ab"), Ok(WasmSlice::new(7, 2)));
+        assert_eq!(view.read_str(0, 11), Ok("hello, abst"));
+        assert!(buffer.write_str(&mut view, "wasm3").is_err());
+        assert!(WasmSlice::new(9, 4).write_str(&mut view, "abc").is_err());
+        assert_eq!(view.read_str(0, 11), Ok("hello, abst"));
+    }
+}
@@ -0,0 +1,59 @@
+#![cfg(feature = "custom-allocator")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wasm3::error::Error;
+use wasm3::Environment;
+
+extern "C" {
+    fn malloc(size: usize) -> *mut core::ffi::c_void;
+    fn realloc(ptr: *mut core::ffi::c_void, size: usize) -> *mut core::ffi::c_void;
+    fn free(ptr: *mut core::ffi::c_void);
+}
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+static FREES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "C" fn counting_malloc(size: usize) -> *mut core::ffi::c_void {
+    ALLOCS.fetch_add(1, Ordering::SeqCst);
+    malloc(size)
+}
+
+unsafe extern "C" fn counting_free(ptr: *mut core::ffi::c_void) {
+    if !ptr.is_null() {
+        FREES.fetch_add(1, Ordering::SeqCst);
+    }
+    free(ptr)
+}
+
+unsafe extern "C" fn other_free(ptr: *mut core::ffi::c_void) {
+    free(ptr)
+}
+
+// this is the only test in this file as the allocator is global to the process
+#[test]
+fn test_new_with_allocator() {
+    let env = unsafe { Environment::new_with_allocator(counting_malloc, realloc, counting_free) }
+        .expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    rt.parse_and_load_module(&include_bytes!("wasm_test_bins/wasm_test_bins.wasm")[..])
+        .expect("Unable to load module");
+    assert!(ALLOCS.load(Ordering::SeqCst) > 0);
+
+    // installing the same functions again is fine, different ones are not
+    let other = unsafe {
+        Environment::builder()
+            .allocator(counting_malloc, realloc, counting_free)
+            .build()
+    }
+    .expect("Unable to create environment");
+    assert_eq!(
+        unsafe { Environment::new_with_allocator(counting_malloc, realloc, other_free) }
+            .map(|_| ()),
+        Err(Error::AllocatorMismatch)
+    );
+
+    drop((rt, env, other));
+    assert!(FREES.load(Ordering::SeqCst) > 0);
+}
@@ -0,0 +1,83 @@
+//! Checks that calling into wasm and back into the host does not allocate on the Rust heap.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use wasm3::Environment;
+
+struct CountingAlloc;
+
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::SeqCst);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::SeqCst);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// (module
+///   (import "env" "host" (func $host (param i32) (result i32)))
+///   (func (export "sum8") (param i32 i64 i32 i64 i32 i64 i32 i64) (result i64)
+///     (i64.add (i64.extend_i32_s (local.get 0)) (local.get 1))
+///     (i64.add (i64.extend_i32_s (local.get 2)))
+///     (i64.add (local.get 3))
+///     (i64.add (i64.extend_i32_s (local.get 4)))
+///     (i64.add (local.get 5))
+///     (i64.add (i64.extend_i32_s (local.get 6)))
+///     (i64.add (local.get 7)))
+///   (func (export "ping") (param i32) (result i32)
+///     (i32.add (call $host (local.get 0)) (i32.const 1))))
+const CALLS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x12, 0x02, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x60, 0x08, 0x7f, 0x7e, 0x7f, 0x7e, 0x7f, 0x7e, 0x7f, 0x7e, 0x01, 0x7e, 0x02, 0x0c, 0x01, 0x03,
+    0x65, 0x6e, 0x76, 0x04, 0x68, 0x6f, 0x73, 0x74, 0x00, 0x00, 0x03, 0x03, 0x02, 0x01, 0x00, 0x07,
+    0x0f, 0x02, 0x04, 0x73, 0x75, 0x6d, 0x38, 0x00, 0x01, 0x04, 0x70, 0x69, 0x6e, 0x67, 0x00, 0x02,
+    0x0a, 0x29, 0x02, 0x1d, 0x00, 0x20, 0x00, 0xac, 0x20, 0x01, 0x7c, 0x20, 0x02, 0xac, 0x7c, 0x20,
+    0x03, 0x7c, 0x20, 0x04, 0xac, 0x7c, 0x20, 0x05, 0x7c, 0x20, 0x06, 0xac, 0x7c, 0x20, 0x07, 0x7c,
+    0x0b, 0x09, 0x00, 0x20, 0x00, 0x10, 0x00, 0x41, 0x01, 0x6a, 0x0b,
+];
+
+type Args8 = (i32, i64, i32, i64, i32, i64, i32, i64);
+
+// the only test in this file, so that no other test allocates while this one counts
+#[test]
+fn test_call_does_not_allocate() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(CALLS)
+        .expect("Unable to load module");
+    module
+        .link_closure::<i32, i32, _>("env", "host", |_, x| Ok(x * 2))
+        .expect("Unable to link closure");
+    let sum8 = module
+        .find_function::<Args8, i64>("sum8")
+        .expect("Unable to find function");
+    let ping = module
+        .find_function::<i32, i32>("ping")
+        .expect("Unable to find function");
+
+    let before = ALLOCS.load(Ordering::SeqCst);
+    let mut acc = 0;
+    for i in 0..1000 {
+        acc += sum8.call(i, 2, 3, 4, 5, 6, 7, 8).unwrap_or_default();
+        acc += i64::from(ping.call(i).unwrap_or_default());
+    }
+    let after = ALLOCS.load(Ordering::SeqCst);
+    assert_eq!(after - before, 0);
+    assert_eq!(acc, (0..1000).map(|i| i + 35 + 2 * i + 1).sum::<i64>());
+}
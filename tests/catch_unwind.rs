@@ -0,0 +1,75 @@
+#![cfg(feature = "catch-unwind")]
+use std::panic::{self, AssertUnwindSafe};
+
+use wasm3::Environment;
+
+/// (module
+///   (import "env" "host" (func $host (param i32)))
+///   (func (export "call") (param i32)
+///     (call $host (local.get 0))))
+const HOST_CALL: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x01, 0x7f, 0x00, 0x02,
+    0x0c, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x04, 0x68, 0x6f, 0x73, 0x74, 0x00, 0x00, 0x03, 0x02, 0x01,
+    0x00, 0x07, 0x08, 0x01, 0x04, 0x63, 0x61, 0x6c, 0x6c, 0x00, 0x01, 0x0a, 0x08, 0x01, 0x06, 0x00,
+    0x20, 0x00, 0x10, 0x00, 0x0b,
+];
+
+#[test]
+fn test_closure_panic_is_resumed() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(HOST_CALL)
+        .expect("Unable to load module");
+    module
+        .link_closure("env", "host", |_, arg: u32| {
+            if arg == 1 {
+                panic!("host panicked with {}", arg);
+            }
+            Ok(())
+        })
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<u32, ()>("call")
+        .expect("Unable to find function");
+
+    let payload = panic::catch_unwind(AssertUnwindSafe(|| func.call(1)))
+        .expect_err("The panic was not resumed");
+    assert_eq!(
+        payload.downcast_ref::<String>().map(String::as_str),
+        Some("host panicked with 1")
+    );
+    // the runtime is still usable after the panic
+    assert_eq!(func.call(0), Ok(()));
+}
+
+#[cfg(feature = "metering")]
+#[test]
+fn test_closure_panic_charges_timeout() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(HOST_CALL)
+        .expect("Unable to load module");
+    module
+        .link_closure(
+            "env",
+            "host",
+            |_, _: u32| -> Result<(), wasm3::error::Trap> { panic!("host panicked") },
+        )
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<u32, ()>("call")
+        .expect("Unable to find function");
+
+    rt.set_fuel(1000);
+    panic::catch_unwind(AssertUnwindSafe(|| func.call_with_timeout(0, 50)))
+        .expect_err("The panic was not resumed");
+    // the runtime's fuel is restored and charged for what the call consumed
+    let left = rt.fuel_remaining();
+    assert!(left < 1000 && left > 950, "{} units of fuel left", left);
+}
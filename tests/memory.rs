@@ -0,0 +1,763 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::rc::Rc;
+
+use wasm3::error::{Error, Trap};
+use wasm3::Environment;
+use wasm3::GuestAllocator;
+use wasm3::ModuleInfo;
+use wasm3::ParsedModule;
+use wasm3::Runtime;
+use wasm3::Value;
+
+fn runtime() -> Runtime {
+    Environment::new()
+        .expect("Unable to create environment")
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime")
+}
+
+/// (module (func (export "empty")))
+const NO_MEMORY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02,
+    0x01, 0x00, 0x07, 0x09, 0x01, 0x05, 0x65, 0x6d, 0x70, 0x74, 0x79, 0x00, 0x00, 0x0a, 0x04, 0x01,
+    0x02, 0x00, 0x0b,
+];
+
+/// (module (memory 1) (data (i32.const 16) "hello"))
+const DATA: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x0b, 0x0b, 0x01,
+    0x00, 0x41, 0x10, 0x0b, 0x05, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+];
+
+/// (module (memory (export "heap") 1))
+const EXPORTED_MEMORY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x08, 0x01,
+    0x04, 0x68, 0x65, 0x61, 0x70, 0x02, 0x00,
+];
+
+/// (module (memory 0 0))
+const EMPTY_MEMORY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x05, 0x04, 0x01, 0x01, 0x00, 0x00,
+];
+
+/// (module (import "env" "mem" (memory 0 0)))
+const IMPORTED_EMPTY_MEMORY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x02, 0x0d, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x03,
+    0x6d, 0x65, 0x6d, 0x02, 0x01, 0x00, 0x00,
+];
+
+/// (module
+///   (memory 1)
+///   (func (export "grow") (param i32) (result i32)
+///     (memory.grow (local.get 0))))
+const GROW: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x03, 0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x08, 0x01, 0x04, 0x67, 0x72, 0x6f,
+    0x77, 0x00, 0x00, 0x0a, 0x08, 0x01, 0x06, 0x00, 0x20, 0x00, 0x40, 0x00, 0x0b,
+];
+
+/// (module
+///   (memory 1)
+///   (func (export "get_input_ptr") (result i32)
+///     (i32.const 1024))
+///   (func (export "sum_buffer") (param $ptr i32) (param $len i32) (result i32)
+///     (local $acc i32)
+///     (block
+///       (loop
+///         (br_if 1 (i32.eqz (local.get $len)))
+///         (local.set $acc (i32.add (local.get $acc) (i32.load8_u (local.get $ptr))))
+///         (local.set $ptr (i32.add (local.get $ptr) (i32.const 1)))
+///         (local.set $len (i32.sub (local.get $len) (i32.const 1)))
+///         (br 0)))
+///     (local.get $acc)))
+const SUM_BUFFER: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0b, 0x02, 0x60, 0x00, 0x01, 0x7f, 0x60,
+    0x02, 0x7f, 0x7f, 0x01, 0x7f, 0x03, 0x03, 0x02, 0x00, 0x01, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07,
+    0x1e, 0x02, 0x0d, 0x67, 0x65, 0x74, 0x5f, 0x69, 0x6e, 0x70, 0x75, 0x74, 0x5f, 0x70, 0x74, 0x72,
+    0x00, 0x00, 0x0a, 0x73, 0x75, 0x6d, 0x5f, 0x62, 0x75, 0x66, 0x66, 0x65, 0x72, 0x00, 0x01, 0x0a,
+    0x33, 0x02, 0x05, 0x00, 0x41, 0x80, 0x08, 0x0b, 0x2b, 0x01, 0x01, 0x7f, 0x02, 0x40, 0x03, 0x40,
+    0x20, 0x01, 0x45, 0x0d, 0x01, 0x20, 0x02, 0x20, 0x00, 0x2d, 0x00, 0x00, 0x6a, 0x21, 0x02, 0x20,
+    0x00, 0x41, 0x01, 0x6a, 0x21, 0x00, 0x20, 0x01, 0x41, 0x01, 0x6b, 0x21, 0x01, 0x0c, 0x00, 0x0b,
+    0x0b, 0x20, 0x02, 0x0b,
+];
+
+/// (module
+///   (memory 1)
+///   (data (i32.const 8) "hello, wasm\00")
+///   (data (i32.const 32) "\ff\fe\00")
+///   (func (export "greeting") (result i32)
+///     (i32.const 8)))
+const STRINGS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, 0x03,
+    0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x0c, 0x01, 0x08, 0x67, 0x72, 0x65, 0x65,
+    0x74, 0x69, 0x6e, 0x67, 0x00, 0x00, 0x0a, 0x06, 0x01, 0x04, 0x00, 0x41, 0x08, 0x0b, 0x0b, 0x1a,
+    0x02, 0x00, 0x41, 0x08, 0x0b, 0x0c, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x2c, 0x20, 0x77, 0x61, 0x73,
+    0x6d, 0x00, 0x00, 0x41, 0x20, 0x0b, 0x03, 0xff, 0xfe, 0x00,
+];
+
+/// (module
+///   (memory 1 2)
+///   (global $next (mut i32) (i32.const 1024))
+///   (global $frees (export "frees") (mut i32) (i32.const 0))
+///   ;; bump allocator growing the memory as required, returns 0 once it is exhausted
+///   (func (export "malloc") (param $size i32) (result i32)
+///     (local $ptr i32)
+///     (local.set $ptr (global.get $next))
+///     (global.set $next (i32.add (local.get $ptr) (local.get $size)))
+///     (if (i32.gt_u (global.get $next) (i32.shl (memory.size) (i32.const 16)))
+///       (then
+///         (if (i32.eq
+///               (memory.grow
+///                 (i32.shr_u
+///                   (i32.sub
+///                     (i32.add (global.get $next) (i32.const 0xffff))
+///                     (i32.shl (memory.size) (i32.const 16)))
+///                   (i32.const 16)))
+///               (i32.const -1))
+///           (then (return (i32.const 0))))))
+///     (local.get $ptr))
+///   (func (export "free") (param i32)
+///     (global.set $frees (i32.add (global.get $frees) (i32.const 1)))))
+const BUMP_ALLOC: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0a, 0x02, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x60, 0x01, 0x7f, 0x00, 0x03, 0x03, 0x02, 0x00, 0x01, 0x05, 0x04, 0x01, 0x01, 0x01, 0x02, 0x06,
+    0x0c, 0x02, 0x7f, 0x01, 0x41, 0x80, 0x08, 0x0b, 0x7f, 0x01, 0x41, 0x00, 0x0b, 0x07, 0x19, 0x03,
+    0x06, 0x6d, 0x61, 0x6c, 0x6c, 0x6f, 0x63, 0x00, 0x00, 0x04, 0x66, 0x72, 0x65, 0x65, 0x00, 0x01,
+    0x05, 0x66, 0x72, 0x65, 0x65, 0x73, 0x03, 0x01, 0x0a, 0x43, 0x02, 0x37, 0x01, 0x01, 0x7f, 0x23,
+    0x00, 0x21, 0x01, 0x23, 0x00, 0x20, 0x00, 0x6a, 0x24, 0x00, 0x23, 0x00, 0x3f, 0x00, 0x41, 0x10,
+    0x74, 0x4b, 0x04, 0x40, 0x23, 0x00, 0x41, 0xff, 0xff, 0x03, 0x6a, 0x3f, 0x00, 0x41, 0x10, 0x74,
+    0x6b, 0x41, 0x10, 0x76, 0x40, 0x00, 0x41, 0x7f, 0x46, 0x04, 0x40, 0x41, 0x00, 0x0f, 0x0b, 0x0b,
+    0x20, 0x01, 0x0b, 0x09, 0x00, 0x23, 0x01, 0x41, 0x01, 0x6a, 0x24, 0x01, 0x0b,
+];
+
+/// (module
+///   (memory 1)
+///   (func (export "grow_or_trap") (param i32) (result i32)
+///     (local $prev i32)
+///     (local.set $prev (memory.grow (local.get 0)))
+///     (if (i32.eq (local.get $prev) (i32.const -1))
+///       (then (unreachable)))
+///     (local.get $prev)))
+const GROW_OR_TRAP: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x03, 0x02, 0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x10, 0x01, 0x0c, 0x67, 0x72, 0x6f,
+    0x77, 0x5f, 0x6f, 0x72, 0x5f, 0x74, 0x72, 0x61, 0x70, 0x00, 0x00, 0x0a, 0x17, 0x01, 0x15, 0x01,
+    0x01, 0x7f, 0x20, 0x00, 0x40, 0x00, 0x21, 0x01, 0x20, 0x01, 0x41, 0x7f, 0x46, 0x04, 0x40, 0x00,
+    0x0b, 0x20, 0x01, 0x0b,
+];
+
+const PAGE_SIZE: usize = 64 * 1024;
+
+/// (module
+///   (memory 1)
+///   (data (i32.const 16) "hello")
+///   (func (export "store") (param i32 i32)
+///     (i32.store8 (local.get 0) (local.get 1)))
+///   (func (export "grow") (param i32) (result i32)
+///     (memory.grow (local.get 0))))
+const SNAPSHOT: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0b, 0x02, 0x60, 0x02, 0x7f, 0x7f, 0x00,
+    0x60, 0x01, 0x7f, 0x01, 0x7f, 0x03, 0x03, 0x02, 0x00, 0x01, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07,
+    0x10, 0x02, 0x05, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x00, 0x00, 0x04, 0x67, 0x72, 0x6f, 0x77, 0x00,
+    0x01, 0x0a, 0x12, 0x02, 0x09, 0x00, 0x20, 0x00, 0x20, 0x01, 0x3a, 0x00, 0x00, 0x0b, 0x06, 0x00,
+    0x20, 0x00, 0x40, 0x00, 0x0b, 0x0b, 0x0b, 0x01, 0x00, 0x41, 0x10, 0x0b, 0x05, 0x68, 0x65, 0x6c,
+    0x6c, 0x6f,
+];
+
+/// (module
+///   (type $void (func))
+///   (import "env" "check" (func $check (type $void)))
+///   (memory 1)
+///   (func (export "run") (type $void)
+///     (drop (memory.grow (i32.const 1)))
+///     (call $check)))
+const GROW_THEN_CALL: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x02, 0x0d,
+    0x01, 0x03, 0x65, 0x6e, 0x76, 0x05, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x00, 0x00, 0x03, 0x02, 0x01,
+    0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x07, 0x01, 0x03, 0x72, 0x75, 0x6e, 0x00, 0x01, 0x0a,
+    0x0b, 0x01, 0x09, 0x00, 0x41, 0x01, 0x40, 0x00, 0x1a, 0x10, 0x00, 0x0b,
+];
+
+#[test]
+fn test_memory_no_memory() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+    assert!(rt.memory().is_empty());
+}
+
+#[test]
+fn test_memory_without_pages() {
+    let env = Environment::new().expect("Unable to create environment");
+    for &bytes in &[EMPTY_MEMORY, IMPORTED_EMPTY_MEMORY] {
+        let module = ParsedModule::parse(&env, bytes).expect("Unable to parse module");
+        // a memory that may not hold any pages is still a memory
+        assert_eq!(module.validate().map(|report| report.memories), Ok(1));
+        assert_eq!(ModuleInfo::from(&module).num_memories, 1);
+    }
+
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(EMPTY_MEMORY)
+        .expect("Unable to load module");
+    assert!(module.has_memory());
+}
+
+#[test]
+fn test_memory_export_name() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(EXPORTED_MEMORY)
+        .expect("Unable to load module");
+    assert!(module.has_memory());
+    assert_eq!(module.memory_export_name(), Some("heap"));
+
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    assert!(module.has_memory());
+    assert_eq!(module.memory_export_name(), None);
+
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+    assert!(!module.has_memory());
+    assert_eq!(module.memory_export_name(), None);
+}
+
+#[test]
+fn test_memory_data_segment() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    let memory = rt.memory();
+    assert_eq!(memory.len(), PAGE_SIZE);
+    assert_eq!(&memory[16..21], b"hello");
+}
+
+#[test]
+fn test_memory_after_grow() {
+    let mut rt = runtime();
+    let module = rt
+        .parse_and_load_module(GROW)
+        .expect("Unable to load module");
+    let func = module
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(func.call(2), Ok(1));
+    let memory = rt.memory();
+    assert_eq!(memory.len(), 3 * PAGE_SIZE);
+    assert!(memory[PAGE_SIZE..].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn test_memory_mut() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    rt.memory_mut()[16..21].copy_from_slice(b"world");
+    assert_eq!(&rt.memory()[16..21], b"world");
+}
+
+#[test]
+fn test_memory_mut_no_memory() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+    assert!(rt.memory_mut().is_empty());
+}
+
+#[test]
+fn test_memory_mut_roundtrip() {
+    let mut rt = runtime();
+    let ptr = rt
+        .parse_and_load_module(SUM_BUFFER)
+        .expect("Unable to load module")
+        .find_function::<(), u32>("get_input_ptr")
+        .expect("Unable to find function")
+        .call()
+        .expect("Unable to call function") as usize;
+
+    let input = [1, 2, 3, 4, 250];
+    rt.memory_mut()[ptr..ptr + input.len()].copy_from_slice(&input);
+
+    let func = rt
+        .find_function::<(u32, u32), u32>("sum_buffer")
+        .expect("Unable to find function");
+    assert_eq!(func.call(ptr as u32, input.len() as u32), Ok(260));
+}
+
+#[test]
+fn test_memory_size() {
+    let mut rt = runtime();
+    let module = rt
+        .parse_and_load_module(GROW)
+        .expect("Unable to load module");
+    assert_eq!(rt.memory_size(), PAGE_SIZE);
+    assert_eq!(rt.memory_pages(), 1);
+    let func = module
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(func.call(2), Ok(1));
+    assert_eq!(rt.memory_size(), 3 * PAGE_SIZE);
+    assert_eq!(rt.memory_pages(), 3);
+    assert_eq!(rt.memory().len(), rt.memory_size());
+}
+
+#[test]
+fn test_memory_size_no_memory() {
+    let rt = runtime();
+    rt.parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+    assert_eq!(rt.memory_size(), 0);
+    assert_eq!(rt.memory_pages(), 0);
+}
+
+#[test]
+fn test_memory_view() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    let mut view = rt.memory_view();
+    assert_eq!(view.len(), PAGE_SIZE);
+    assert_eq!(view.read_u8(16), Ok(b'h'));
+    view.write_u32(17, u32::from_le_bytes(*b"ELLO")).unwrap();
+    assert_eq!(
+        view.read_u64(PAGE_SIZE - 4),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: PAGE_SIZE - 4,
+            len: 8
+        })
+    );
+    assert_eq!(&rt.memory()[16..21], b"hELLO");
+}
+
+#[test]
+fn test_read_write_memory() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    assert_eq!(rt.read_memory(16, 5), Ok(&b"hello"[..]));
+    assert_eq!(rt.write_memory(PAGE_SIZE - 2, b"ab"), Ok(()));
+    assert_eq!(rt.read_memory(PAGE_SIZE - 2, 2), Ok(&b"ab"[..]));
+    assert_eq!(rt.read_memory(PAGE_SIZE, 0), Ok(&b""[..]));
+}
+
+#[test]
+fn test_read_write_memory_out_of_bounds() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    assert_eq!(
+        rt.write_memory(PAGE_SIZE - 2, b"abc"),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: PAGE_SIZE - 2,
+            len: 3
+        })
+    );
+    assert_eq!(rt.read_memory(PAGE_SIZE - 2, 2), Ok(&[0, 0][..]));
+    assert_eq!(
+        rt.read_memory(usize::MAX, 2),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: usize::MAX,
+            len: 2
+        })
+    );
+}
+
+#[test]
+fn test_read_strings() {
+    let mut rt = runtime();
+    let ptr = rt
+        .parse_and_load_module(STRINGS)
+        .expect("Unable to load module")
+        .find_function::<(), u32>("greeting")
+        .expect("Unable to find function")
+        .call()
+        .expect("Unable to call function") as usize;
+    assert_eq!(rt.read_cstr(ptr), Ok("hello, wasm"));
+    assert_eq!(rt.read_str(ptr, 5), Ok("hello"));
+    assert!(matches!(rt.read_cstr(32), Err(Error::InvalidUtf8(_))));
+    assert_eq!(rt.read_cstr_lossy(32).as_deref(), Ok("\u{FFFD}\u{FFFD}"));
+    assert_eq!(rt.read_str_lossy(ptr, 5).as_deref(), Ok("hello"));
+}
+
+#[test]
+fn test_read_cstr_end_of_memory() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(STRINGS)
+        .expect("Unable to load module");
+    rt.write_memory(PAGE_SIZE - 3, b"abc").unwrap();
+    assert_eq!(
+        rt.read_cstr(PAGE_SIZE - 3),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: PAGE_SIZE - 3,
+            len: 4
+        })
+    );
+    assert_eq!(
+        rt.read_str(PAGE_SIZE - 3, 4),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: PAGE_SIZE - 3,
+            len: 4
+        })
+    );
+}
+
+#[test]
+fn test_guest_allocator() {
+    let mut rt = runtime();
+    {
+        let module = rt
+            .parse_and_load_module(BUMP_ALLOC)
+            .expect("Unable to load module");
+        let frees = module.find_global("frees").expect("Unable to find global");
+        let alloc = GuestAllocator::new(&rt).expect("Unable to create allocator");
+
+        let hello = alloc.alloc_str("hello").expect("Unable to allocate");
+        assert_eq!((hello.ptr(), hello.len()), (1024, 5));
+        let world = alloc.alloc_bytes(b"world").expect("Unable to allocate");
+        assert_eq!(world.ptr(), 1029);
+        assert_eq!(world.free(), Ok(()));
+        assert_eq!(frees.get(), Value::I32(1));
+        assert_eq!(hello.into_raw(), 1024);
+        assert_eq!(frees.get(), Value::I32(1));
+    }
+    assert_eq!(rt.read_memory(1024, 10), Ok(&b"helloworld"[..]));
+}
+
+#[test]
+fn test_guest_allocator_grows_memory() {
+    let mut rt = runtime();
+    let data = vec![0xAB; PAGE_SIZE];
+    {
+        rt.parse_and_load_module(BUMP_ALLOC)
+            .expect("Unable to load module");
+        let alloc = GuestAllocator::new(&rt).expect("Unable to create allocator");
+        let buffer = alloc.alloc_bytes(&data).expect("Unable to allocate");
+        assert_eq!(buffer.ptr(), 1024);
+        buffer.into_raw();
+        assert_eq!(
+            alloc.alloc_bytes(&data).map(|buffer| buffer.ptr()),
+            Err(Error::GuestAllocationFailed { len: PAGE_SIZE })
+        );
+    }
+    assert_eq!(rt.memory_pages(), 2);
+    assert_eq!(rt.read_memory(1024, PAGE_SIZE), Ok(&data[..]));
+}
+
+#[test]
+fn test_guest_allocator_missing_functions() {
+    let rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    assert_eq!(
+        GuestAllocator::new(&rt).map(|_| ()),
+        Err(Error::FunctionNotFound)
+    );
+}
+
+#[test]
+fn test_grow_memory() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    assert_eq!(rt.grow_memory(0), Ok(1));
+    assert_eq!(rt.grow_memory(2), Ok(1));
+    assert_eq!(rt.memory_pages(), 3);
+    assert_eq!(rt.memory_size(), 3 * PAGE_SIZE);
+    assert_eq!(&rt.memory()[16..21], b"hello");
+
+    let scratch = 2 * PAGE_SIZE;
+    assert_eq!(rt.read_memory(scratch, PAGE_SIZE), Ok(&[0; PAGE_SIZE][..]));
+    rt.write_memory(scratch, b"scratch")
+        .expect("Unable to write memory");
+    assert_eq!(rt.read_memory(scratch, 7), Ok(&b"scratch"[..]));
+}
+
+#[test]
+fn test_grow_memory_seen_by_guest() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(GROW)
+        .expect("Unable to load module");
+    assert_eq!(rt.grow_memory(1), Ok(1));
+    rt.write_memory(2 * PAGE_SIZE - 4, b"last")
+        .expect("Unable to write memory");
+    assert_eq!(&rt.memory()[2 * PAGE_SIZE - 4..], b"last");
+    let grow = rt
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(grow.call(0), Ok(2));
+}
+
+#[test]
+fn test_grow_memory_past_max() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(BUMP_ALLOC)
+        .expect("Unable to load module");
+    assert_eq!(rt.grow_memory(2), Err(Error::MemoryGrowFailed));
+    assert_eq!(rt.grow_memory(u32::MAX), Err(Error::MemoryGrowFailed));
+    assert_eq!(rt.memory_pages(), 1);
+    assert_eq!(rt.grow_memory(1), Ok(1));
+    assert_eq!(rt.memory().len(), 2 * PAGE_SIZE);
+}
+
+#[test]
+fn test_memory_limit() {
+    let env = Environment::new().expect("Unable to create environment");
+    let mut rt = env
+        .create_runtime_with_memory_limit(1024 * 60, 4)
+        .expect("Unable to create runtime");
+    let func = rt
+        .parse_and_load_module(GROW_OR_TRAP)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("grow_or_trap")
+        .expect("Unable to find function");
+    assert_eq!(func.call(2), Ok(1));
+    match func.call(2) {
+        Err(Error::Wasm3(err)) => assert!(err.is_trap(Trap::Unreachable)),
+        res => panic!("expected a trap, got {:?}", res),
+    }
+    // the module stays callable after trapping
+    assert_eq!(func.call(1), Ok(3));
+    assert_eq!(rt.memory_pages(), 4);
+    assert_eq!(rt.grow_memory(1), Err(Error::MemoryGrowFailed));
+}
+
+#[test]
+fn test_memory_limit_initial_pages() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime_with_memory_limit(1024 * 60, 0)
+        .expect("Unable to create runtime");
+    assert!(matches!(
+        rt.parse_and_load_module(DATA),
+        Err(Error::Wasm3(_))
+    ));
+    rt.parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+}
+
+#[test]
+fn test_memory_snapshot() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(SNAPSHOT)
+        .expect("Unable to load module");
+    let store = module
+        .find_function::<(u32, u32), ()>("store")
+        .expect("Unable to find function");
+    let grow = module
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    let snapshot = rt.snapshot_memory();
+    assert_eq!(snapshot.pages(), 1);
+    assert_eq!(&snapshot.data()[16..21], b"hello");
+    store
+        .call(16, b'j'.into())
+        .expect("Unable to call function");
+    assert_eq!(grow.call(1), Ok(1));
+    store
+        .call(PAGE_SIZE as u32 + 1, 1)
+        .expect("Unable to call function");
+    rt.restore_memory(&snapshot)
+        .expect("Unable to restore memory");
+    // the grown memory is kept but zeroed
+    assert_eq!(rt.memory_pages(), 2);
+    assert_eq!(rt.snapshot_memory().data()[..PAGE_SIZE], *snapshot.data());
+    assert!(rt.snapshot_memory().data()[PAGE_SIZE..]
+        .iter()
+        .all(|&byte| byte == 0));
+}
+
+#[test]
+fn test_memory_snapshot_restore_grows() {
+    let rt = runtime();
+    let grow = rt
+        .parse_and_load_module(SNAPSHOT)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(grow.call(1), Ok(1));
+    let snapshot = rt.snapshot_memory();
+
+    let other = runtime();
+    other
+        .parse_and_load_module(SNAPSHOT)
+        .expect("Unable to load module");
+    assert_eq!(other.memory_pages(), 1);
+    other
+        .restore_memory(&snapshot)
+        .expect("Unable to restore memory");
+    assert_eq!(other.memory_pages(), 2);
+    assert_eq!(other.snapshot_memory(), snapshot);
+}
+
+fn memory_digest(rt: &mut Runtime) -> Vec<u8> {
+    let mut digest = Vec::new();
+    rt.memory_digest(|chunk| digest.extend_from_slice(chunk));
+    digest
+}
+
+fn memory_hash(rt: &mut Runtime) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    rt.memory_hash(&mut hasher);
+    hasher.finish()
+}
+
+fn store_byte(rt: &Runtime, offset: u32, byte: u8) {
+    rt.find_function::<(u32, u32), ()>("store")
+        .expect("Unable to find function")
+        .call(offset, byte.into())
+        .expect("Unable to call function");
+}
+
+#[test]
+fn test_memory_digest() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(SNAPSHOT)
+        .expect("Unable to load module");
+    let digest = memory_digest(&mut rt);
+    assert_eq!(digest.len(), 4 + PAGE_SIZE);
+    assert_eq!(digest[..4], 1u32.to_le_bytes());
+    assert_eq!(digest[4..], *rt.memory());
+    let hash = memory_hash(&mut rt);
+
+    store_byte(&rt, 16, b'j');
+    assert_ne!(memory_hash(&mut rt), hash);
+    store_byte(&rt, 16, b'h');
+    assert_eq!(memory_hash(&mut rt), hash);
+
+    // growing the memory changes the digest even though the new page is zeroed
+    rt.grow_memory(1).expect("Unable to grow memory");
+    assert_ne!(memory_hash(&mut rt), hash);
+}
+
+#[test]
+fn test_memory_digest_deterministic() {
+    let mut runtimes = [runtime(), runtime()];
+    for rt in &mut runtimes {
+        rt.parse_and_load_module(SNAPSHOT)
+            .expect("Unable to load module");
+        store_byte(rt, 1024, 42);
+        store_byte(rt, 1025, 7);
+    }
+    let [first, second] = &mut runtimes;
+    assert_eq!(memory_digest(first), memory_digest(second));
+    assert_eq!(memory_hash(first), memory_hash(second));
+}
+
+#[test]
+fn test_read_into_write_at() {
+    let rt = runtime();
+    let sum = rt
+        .parse_and_load_module(SUM_BUFFER)
+        .expect("Unable to load module")
+        .find_function::<(u32, u32), u32>("sum_buffer")
+        .expect("Unable to find function");
+    let payload: Vec<u8> = (0..=255).cycle().take(PAGE_SIZE - 1024).collect();
+    rt.write_at(1024, &payload).expect("Unable to write memory");
+    assert_eq!(
+        sum.call(1024, payload.len() as u32),
+        Ok(payload.iter().map(|&byte| u32::from(byte)).sum())
+    );
+    let mut copy = vec![0; payload.len()];
+    rt.read_into(1024, &mut copy)
+        .expect("Unable to read memory");
+    assert_eq!(copy, payload);
+
+    let mut dst = [0xAA; 4];
+    assert_eq!(
+        rt.read_into(PAGE_SIZE as u32 - 2, &mut dst),
+        Err(Error::OutOfBoundsMemoryAccess {
+            offset: PAGE_SIZE - 2,
+            len: 4
+        })
+    );
+    assert_eq!(dst, [0xAA; 4]);
+    assert!(rt.write_at(PAGE_SIZE as u32, &[1]).is_err());
+}
+
+#[test]
+fn test_memory_raw() {
+    let mut rt = runtime();
+    rt.parse_and_load_module(DATA)
+        .expect("Unable to load module");
+    let (ptr, len) = unsafe { rt.memory_raw() };
+    assert_eq!(len, PAGE_SIZE);
+    assert_eq!(
+        unsafe { std::slice::from_raw_parts(ptr.add(16), 5) },
+        b"hello"
+    );
+
+    rt.grow_memory(1).expect("Unable to grow memory");
+    let (ptr, len) = unsafe { rt.memory_raw() };
+    assert_eq!(len, 2 * PAGE_SIZE);
+    unsafe { ptr.add(PAGE_SIZE).write(42) };
+    assert_eq!(rt.read_memory(PAGE_SIZE, 1), Ok(&[42][..]));
+    assert_eq!(rt.read_memory(16, 5), Ok(&b"hello"[..]));
+}
+
+#[test]
+fn test_memory_raw_no_memory() {
+    let rt = runtime();
+    rt.parse_and_load_module(NO_MEMORY)
+        .expect("Unable to load module");
+    assert_eq!(unsafe { rt.memory_raw() }.1, 0);
+}
+
+#[test]
+fn test_memory_grow_callback() {
+    let mut rt = runtime();
+    let grown = Rc::new(RefCell::new(Vec::new()));
+    let sizes = grown.clone();
+    rt.set_memory_grow_callback(move |size| sizes.borrow_mut().push(size));
+    let grow = rt
+        .parse_and_load_module(SNAPSHOT)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(*grown.borrow(), [PAGE_SIZE]);
+    assert_eq!(grow.call(2), Ok(1));
+    assert_eq!(grow.call(0), Ok(3));
+    assert_eq!(rt.grow_memory(1), Ok(3));
+    assert_eq!(*grown.borrow(), [PAGE_SIZE, 3 * PAGE_SIZE, 4 * PAGE_SIZE]);
+
+    rt.clear_memory_grow_callback();
+    let grow = rt
+        .find_function::<u32, u32>("grow")
+        .expect("Unable to find function");
+    assert_eq!(grow.call(1), Ok(4));
+    assert_eq!(grown.borrow().len(), 3);
+}
+
+#[test]
+fn test_module_memory_grow_callback_before_closure() {
+    let rt = runtime();
+    let mut module = rt
+        .parse_and_load_module(GROW_THEN_CALL)
+        .expect("Unable to load module");
+    let grown = Rc::new(RefCell::new(Vec::new()));
+    let sizes = grown.clone();
+    module.set_memory_grow_callback(move |size| sizes.borrow_mut().push(size));
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let (sizes, seen_by_closure) = (grown.clone(), seen.clone());
+    module
+        .link_closure::<(), (), _>("env", "check", move |_, ()| {
+            seen_by_closure
+                .borrow_mut()
+                .extend(sizes.borrow().iter().copied());
+            Ok(())
+        })
+        .expect("Unable to link closure");
+    let run = module
+        .find_function::<(), ()>("run")
+        .expect("Unable to find function");
+    assert_eq!(run.call(), Ok(()));
+    assert_eq!(*seen.borrow(), [2 * PAGE_SIZE]);
+    assert_eq!(*grown.borrow(), [2 * PAGE_SIZE]);
+}
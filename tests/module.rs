@@ -0,0 +1,1121 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm3::error::{Error, Trap};
+use wasm3::wasm3_sys as ffi;
+use wasm3::Environment;
+use wasm3::ModuleInfo;
+use wasm3::ParsedModule;
+use wasm3::ValidationReport;
+use wasm3::Value;
+use wasm3::ValueType;
+#[cfg(feature = "bytemuck")]
+use wasm3::WasmPtr;
+use wasm3::WasmSlice;
+use wasm3::WasmType;
+
+/// (module
+///   (import "env" "log" (func (param i32 i64)))
+///   (import "env" "now" (func (result f64))))
+const IMPORTS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0a, 0x02, 0x60, 0x02, 0x7f, 0x7e, 0x00,
+    0x60, 0x00, 0x01, 0x7c, 0x02, 0x15, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x6c, 0x6f, 0x67, 0x00,
+    0x00, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x6e, 0x6f, 0x77, 0x00, 0x01,
+];
+
+/// (module
+///   (import "env" "get" (func $get (result i64)))
+///   (func (export "call_get") (result i64)
+///     (call $get)))
+const IMPORT_GET: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7e, 0x02,
+    0x0b, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x67, 0x65, 0x74, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00,
+    0x07, 0x0c, 0x01, 0x08, 0x63, 0x61, 0x6c, 0x6c, 0x5f, 0x67, 0x65, 0x74, 0x00, 0x01, 0x0a, 0x06,
+    0x01, 0x04, 0x00, 0x10, 0x00, 0x0b,
+];
+
+/// (module
+///   (global (export "answer") i32 (i32.const 42))
+///   (global (export "offset") i64 (i64.const -8))
+///   (global (export "ratio") f32 (f32.const 0.5))
+///   (global (export "scale") (mut f64) (f64.const 2.5)))
+const GLOBALS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x06, 0x1f, 0x04, 0x7f, 0x00, 0x41, 0x2a, 0x0b,
+    0x7e, 0x00, 0x42, 0x78, 0x0b, 0x7d, 0x00, 0x43, 0x00, 0x00, 0x00, 0x3f, 0x0b, 0x7c, 0x01, 0x44,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x40, 0x0b, 0x07, 0x23, 0x04, 0x06, 0x61, 0x6e, 0x73,
+    0x77, 0x65, 0x72, 0x03, 0x00, 0x06, 0x6f, 0x66, 0x66, 0x73, 0x65, 0x74, 0x03, 0x01, 0x05, 0x72,
+    0x61, 0x74, 0x69, 0x6f, 0x03, 0x02, 0x05, 0x73, 0x63, 0x61, 0x6c, 0x65, 0x03, 0x03,
+];
+
+/// (module
+///   (import "env" "log" (func $log (param i32)))
+///   (memory 1)
+///   (global $counter i32 (i32.const 1))
+///   (global i64 (i64.const 0))
+///   (export "counter" (global $counter))
+///   (func (export "run")
+///     (call $log (i32.const 1)))
+///   (func))
+const VALIDATE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x60, 0x01, 0x7f, 0x00, 0x60,
+    0x00, 0x00, 0x02, 0x0b, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x6c, 0x6f, 0x67, 0x00, 0x00, 0x03,
+    0x03, 0x02, 0x01, 0x01, 0x05, 0x03, 0x01, 0x00, 0x01, 0x06, 0x0b, 0x02, 0x7f, 0x00, 0x41, 0x01,
+    0x0b, 0x7e, 0x00, 0x42, 0x00, 0x0b, 0x07, 0x11, 0x02, 0x03, 0x72, 0x75, 0x6e, 0x00, 0x01, 0x07,
+    0x63, 0x6f, 0x75, 0x6e, 0x74, 0x65, 0x72, 0x03, 0x00, 0x0a, 0x0b, 0x02, 0x06, 0x00, 0x41, 0x01,
+    0x10, 0x00, 0x0b, 0x02, 0x00, 0x0b,
+];
+
+/// (module
+///   (import "env" "init" (func $init (result i32)))
+///   (global $value (mut i32) (i32.const 0))
+///   (func $start (global.set $value (call $init)))
+///   (func (export "get") (result i32) (global.get $value))
+///   (start $start))
+const START: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x60, 0x00, 0x01, 0x7f, 0x60,
+    0x00, 0x00, 0x02, 0x0c, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x04, 0x69, 0x6e, 0x69, 0x74, 0x00, 0x00,
+    0x03, 0x03, 0x02, 0x01, 0x00, 0x06, 0x06, 0x01, 0x7f, 0x01, 0x41, 0x00, 0x0b, 0x07, 0x07, 0x01,
+    0x03, 0x67, 0x65, 0x74, 0x00, 0x02, 0x08, 0x01, 0x01, 0x0a, 0x0d, 0x02, 0x06, 0x00, 0x10, 0x00,
+    0x24, 0x00, 0x0b, 0x04, 0x00, 0x23, 0x00, 0x0b,
+];
+
+/// (module
+///   (memory (export "memory") 1)
+///   (func $start (i32.store (i32.const 0) (i32.const 42)))
+///   (start $start))
+const START_SENTINEL: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02,
+    0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x0a, 0x01, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72,
+    0x79, 0x02, 0x00, 0x08, 0x01, 0x00, 0x0a, 0x0b, 0x01, 0x09, 0x00, 0x41, 0x00, 0x41, 0x2a, 0x36,
+    0x02, 0x00, 0x0b,
+];
+
+/// (module
+///   (import "env" "h" (func $h (param i32) (result i32)))
+///   (func $a (export "a") (param i32) (result i32)
+///     (i32.add (call $h (local.get 0)) (local.get 0)))
+///   (func (export "b") (param i32) (result i32)
+///     (i32.mul (call $a (local.get 0)) (i32.const 10))))
+const REENTER: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x02, 0x09, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x01, 0x68, 0x00, 0x00, 0x03, 0x03, 0x02, 0x00, 0x00,
+    0x07, 0x09, 0x02, 0x01, 0x61, 0x00, 0x01, 0x01, 0x62, 0x00, 0x02, 0x0a, 0x15, 0x02, 0x09, 0x00,
+    0x20, 0x00, 0x10, 0x00, 0x20, 0x00, 0x6a, 0x0b, 0x09, 0x00, 0x20, 0x00, 0x10, 0x01, 0x41, 0x0a,
+    0x6c, 0x0b,
+];
+
+/// (module
+///   (func (export "double") (param i32) (result i32)
+///     (i32.mul (local.get 0) (i32.const 2))))
+const LIB: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x03, 0x02, 0x01, 0x00, 0x07, 0x0a, 0x01, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c, 0x65, 0x00, 0x00,
+    0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x41, 0x02, 0x6c, 0x0b,
+];
+
+/// (module
+///   (import "lib" "double" (func $double (param i32) (result i32)))
+///   (func (export "quadruple") (param i32) (result i32)
+///     (call $double (call $double (local.get 0)))))
+const MAIN: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x02, 0x0e, 0x01, 0x03, 0x6c, 0x69, 0x62, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c, 0x65, 0x00, 0x00,
+    0x03, 0x02, 0x01, 0x00, 0x07, 0x0d, 0x01, 0x09, 0x71, 0x75, 0x61, 0x64, 0x72, 0x75, 0x70, 0x6c,
+    0x65, 0x00, 0x01, 0x0a, 0x0a, 0x01, 0x08, 0x00, 0x20, 0x00, 0x10, 0x00, 0x10, 0x00, 0x0b,
+];
+
+/// (module $named)
+const NAMED: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0d, 0x04, 0x6e, 0x61, 0x6d, 0x65, 0x00,
+    0x06, 0x05, 0x6e, 0x61, 0x6d, 0x65, 0x64,
+];
+
+/// (module
+///   (import "lib" "double" (func $double (param i32) (result i32)))
+///   (func (export "double") (param i32) (result i32)
+///     (i32.mul (local.get 0) (i32.const 2)))
+///   (func (export "quadruple") (param i32) (result i32)
+///     (call $double (call $double (local.get 0)))))
+const SELF_LINK: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x02, 0x0e, 0x01, 0x03, 0x6c, 0x69, 0x62, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c, 0x65, 0x00, 0x00,
+    0x03, 0x03, 0x02, 0x00, 0x00, 0x07, 0x16, 0x02, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c, 0x65, 0x00,
+    0x01, 0x09, 0x71, 0x75, 0x61, 0x64, 0x72, 0x75, 0x70, 0x6c, 0x65, 0x00, 0x02, 0x0a, 0x12, 0x02,
+    0x07, 0x00, 0x20, 0x00, 0x41, 0x02, 0x6c, 0x0b, 0x08, 0x00, 0x20, 0x00, 0x10, 0x00, 0x10, 0x00,
+    0x0b,
+];
+
+/// (module
+///   (import "env" "double" (func $double (param i32)))
+///   (memory 1)
+///   (data (i32.const 8) "\05\00\00\00")
+///   (func (export "run") (result i32)
+///     (call $double (i32.const 8))
+///     (i32.load (i32.const 8))))
+#[cfg(feature = "bytemuck")]
+const DOUBLE_PTR: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x09, 0x02, 0x60, 0x01, 0x7f, 0x00, 0x60,
+    0x00, 0x01, 0x7f, 0x02, 0x0e, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x06, 0x64, 0x6f, 0x75, 0x62, 0x6c,
+    0x65, 0x00, 0x00, 0x03, 0x02, 0x01, 0x01, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x07, 0x01, 0x03,
+    0x72, 0x75, 0x6e, 0x00, 0x01, 0x0a, 0x0d, 0x01, 0x0b, 0x00, 0x41, 0x08, 0x10, 0x00, 0x41, 0x08,
+    0x28, 0x02, 0x00, 0x0b, 0x0b, 0x0a, 0x01, 0x00, 0x41, 0x08, 0x0b, 0x04, 0x05, 0x00, 0x00, 0x00,
+];
+
+/// (module
+///   (import "env" "log" (func $log (param i32 i32) (result i32)))
+///   (memory 1)
+///   (data (i32.const 16) "hello, host")
+///   (func (export "log_buffer") (param i32 i32) (result i32)
+///     (call $log (local.get 0) (local.get 1))))
+const LOG: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+    0x7f, 0x02, 0x0b, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x6c, 0x6f, 0x67, 0x00, 0x00, 0x03, 0x02,
+    0x01, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x0e, 0x01, 0x0a, 0x6c, 0x6f, 0x67, 0x5f, 0x62,
+    0x75, 0x66, 0x66, 0x65, 0x72, 0x00, 0x01, 0x0a, 0x0a, 0x01, 0x08, 0x00, 0x20, 0x00, 0x20, 0x01,
+    0x10, 0x00, 0x0b, 0x0b, 0x11, 0x01, 0x00, 0x41, 0x10, 0x0b, 0x0b, 0x68, 0x65, 0x6c, 0x6c, 0x6f,
+    0x2c, 0x20, 0x68, 0x6f, 0x73, 0x74,
+];
+
+/// (module
+///   (import "env" "not" (func $not (param i32) (result i32)))
+///   (func (export "id") (param i32) (result i32)
+///     (local.get 0))
+///   (func (export "call_not") (param i32) (result i32)
+///     (call $not (local.get 0))))
+const BOOLS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x02, 0x0b, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x03, 0x6e, 0x6f, 0x74, 0x00, 0x00, 0x03, 0x03, 0x02,
+    0x00, 0x00, 0x07, 0x11, 0x02, 0x02, 0x69, 0x64, 0x00, 0x01, 0x08, 0x63, 0x61, 0x6c, 0x6c, 0x5f,
+    0x6e, 0x6f, 0x74, 0x00, 0x02, 0x0a, 0x0d, 0x02, 0x04, 0x00, 0x20, 0x00, 0x0b, 0x06, 0x00, 0x20,
+    0x00, 0x10, 0x00, 0x0b,
+];
+
+/// (module
+///   (func (export "pair") (result i32 i64)
+///     (i32.const 1)
+///     (i64.const 2)))
+const MULTI_VALUE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x00, 0x02, 0x7f, 0x7e,
+    0x03, 0x02, 0x01, 0x00, 0x07, 0x08, 0x01, 0x04, 0x70, 0x61, 0x69, 0x72, 0x00, 0x00, 0x0a, 0x08,
+    0x01, 0x06, 0x00, 0x41, 0x01, 0x42, 0x02, 0x0b,
+];
+
+/// (module
+///   (@custom "meta" "\01\02\03")
+///   (type (func))
+///   (@custom "empty" ""))
+const CUSTOM_SECTIONS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x00, 0x08, 0x04, 0x6d, 0x65, 0x74, 0x61, 0x01,
+    0x02, 0x03, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x00, 0x06, 0x05, 0x65, 0x6d, 0x70, 0x74, 0x79,
+];
+
+#[test]
+fn test_imports() {
+    let env = Environment::new().expect("Unable to create environment");
+    let module = env.parse_module(IMPORTS).expect("Unable to parse module");
+    let imports = module
+        .imports()
+        .map(|import| {
+            (
+                import.module_name,
+                import.field_name,
+                import.signature.as_str().to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            ("env", "log", "v(iI)".to_owned()),
+            ("env", "now", "F()".to_owned())
+        ]
+    );
+}
+
+#[test]
+fn test_module_imports() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(IMPORTS)
+        .expect("Unable to load module");
+    module
+        .link_closure::<(), f64, _>("env", "now", |_, ()| Ok(0.0))
+        .expect("Unable to link closure");
+    let imports = module
+        .imports()
+        .map(|import| {
+            (
+                import.module_name(),
+                import.field_name(),
+                import.signature().as_str().to_owned(),
+                import.is_linked(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        imports,
+        [
+            ("env", "log", "v(iI)".to_owned(), false),
+            ("env", "now", "F()".to_owned(), true)
+        ]
+    );
+    let log = module
+        .find_import("env", "log")
+        .expect("Unable to find import");
+    assert_eq!(log.signature().as_str(), "v(iI)");
+    assert!(module.find_import("env", "missing").is_none());
+}
+
+#[test]
+fn test_imports_none() {
+    let env = Environment::new().expect("Unable to create environment");
+    let module = env
+        .parse_module(&include_bytes!("wasm_test_bins/wasm_test_bins.wasm")[..])
+        .expect("Unable to parse module");
+    assert_eq!(module.imports().count(), 0);
+}
+
+#[test]
+fn test_functions_skip_imports() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    let functions = module
+        .functions()
+        .map(|func| (func.name(), func.index(), func.ret_type()))
+        .collect::<Vec<_>>();
+    assert_eq!(functions, [(Some("call_get"), 1, Some(ValueType::I64))]);
+}
+
+unsafe extern "C" fn get_context(
+    _rt: ffi::IM3Runtime,
+    sp: ffi::m3stack_t,
+    _mem: *mut core::ffi::c_void,
+    context: *mut core::ffi::c_void,
+) -> *const core::ffi::c_void {
+    let counter = &mut *context.cast::<i64>();
+    *counter += 1;
+    counter.push_on_stack(sp);
+    ffi::m3Err_none as _
+}
+
+#[test]
+fn test_link_function_with_context() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut counter = 41i64;
+    let mut module = rt
+        .parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    unsafe {
+        module.link_function_with_context::<(), i64>(
+            "env",
+            "get",
+            get_context,
+            (&mut counter as *mut i64).cast(),
+        )
+    }
+    .expect("Unable to link function");
+    let func = module
+        .find_function::<(), i64>("call_get")
+        .expect("Unable to find function");
+    assert_eq!(func.call(), Ok(42));
+    assert_eq!(func.call(), Ok(43));
+    drop(rt);
+    assert_eq!(counter, 43);
+}
+
+unsafe extern "C" fn missing_import(
+    _rt: ffi::IM3Runtime,
+    _sp: ffi::m3stack_t,
+    _mem: *mut core::ffi::c_void,
+) -> *const core::ffi::c_void {
+    Trap::Unreachable.as_ptr().cast()
+}
+
+#[test]
+fn test_link_all_missing_with() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(IMPORTS)
+        .expect("Unable to load module");
+    module
+        .link_closure::<(), f64, _>("env", "now", |_, ()| Ok(0.0))
+        .expect("Unable to link closure");
+    assert_eq!(module.link_all_missing_with(missing_import), Ok(1));
+    assert!(module.imports().all(|import| import.is_linked()));
+    assert_eq!(module.link_all_missing_with(missing_import), Ok(0));
+
+    let mut module = rt
+        .parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    assert_eq!(module.link_all_missing_with(missing_import), Ok(1));
+    let func = module
+        .find_function::<(), i64>("call_get")
+        .expect("Unable to find function");
+    match func.call() {
+        Err(Error::Wasm3(err)) => assert!(err.is_trap(Trap::Unreachable)),
+        res => panic!("expected a trap, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_find_global() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(GLOBALS)
+        .expect("Unable to load module");
+    let get = |name| module.find_global(name).map(|global| global.get());
+    assert_eq!(get("answer"), Ok(Value::I32(42)));
+    assert_eq!(get("offset"), Ok(Value::I64(-8)));
+    assert_eq!(get("ratio"), Ok(Value::F32(0.5)));
+    assert_eq!(get("scale"), Ok(Value::F64(2.5)));
+    assert_eq!(get("missing"), Err(Error::GlobalNotFound));
+}
+
+#[test]
+fn test_globals() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(GLOBALS)
+        .expect("Unable to load module");
+    assert_eq!(
+        module
+            .globals()
+            .map(|global| (global.name(), global.value_type(), global.is_mutable()))
+            .collect::<Vec<_>>(),
+        [
+            (Some("answer"), ValueType::I32, false),
+            (Some("offset"), ValueType::I64, false),
+            (Some("ratio"), ValueType::F32, false),
+            (Some("scale"), ValueType::F64, true),
+        ]
+    );
+    let module = rt
+        .parse_and_load_module(VALIDATE)
+        .expect("Unable to load module");
+    assert_eq!(
+        module
+            .globals()
+            .map(|global| (global.name(), global.get()))
+            .collect::<Vec<_>>(),
+        [(Some("counter"), Value::I32(1)), (None, Value::I64(0))]
+    );
+}
+
+#[test]
+fn test_set_global() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(GLOBALS)
+        .expect("Unable to load module");
+    let scale = module.find_global("scale").expect("Unable to find global");
+    assert!(scale.is_mutable());
+    assert_eq!(scale.set(Value::F64(-1.25)), Ok(()));
+    assert_eq!(scale.get(), Value::F64(-1.25));
+    assert_eq!(scale.set(Value::F32(1.0)), Err(Error::InvalidGlobalType));
+    assert_eq!(scale.get(), Value::F64(-1.25));
+
+    let answer = module.find_global("answer").expect("Unable to find global");
+    assert!(!answer.is_mutable());
+    assert_eq!(answer.set(Value::I32(7)), Err(Error::GlobalNotMutable));
+    assert_eq!(answer.get(), Value::I32(42));
+}
+
+#[test]
+fn test_link_closure_trap() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    let mut results = vec![Ok(3), Err(Trap::Abort), Err(Trap::Unreachable)].into_iter();
+    module
+        .link_closure::<(), i64, _>("env", "get", move |_, ()| {
+            results.next().expect("called too often")
+        })
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<(), i64>("call_get")
+        .expect("Unable to find function");
+    assert_eq!(func.call(), Ok(3));
+    for &trap in &[Trap::Abort, Trap::Unreachable] {
+        match func.call() {
+            Err(Error::Wasm3(err)) => assert!(err.is_trap(trap)),
+            res => panic!("expected a trap, got {:?}", res),
+        }
+    }
+}
+
+#[test]
+fn test_link_closure_fallible() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    let mut values = vec![Ok(7), Err("no value available")].into_iter();
+    module
+        .link_closure_fallible::<(), i64, Box<dyn std::error::Error>, _>(
+            "env",
+            "get",
+            move |_, ()| values.next().expect("called too often").map_err(Into::into),
+        )
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<(), i64>("call_get")
+        .expect("Unable to find function");
+    assert_eq!(func.call(), Ok(7));
+    assert_eq!(
+        func.call(),
+        Err(Error::HostFunction("no value available".to_owned()))
+    );
+}
+
+#[test]
+fn test_validate() {
+    let env = Environment::new().expect("Unable to create environment");
+    let module = ParsedModule::parse(&env, VALIDATE).expect("Unable to parse module");
+    assert_eq!(
+        module.validate(),
+        Ok(ValidationReport {
+            functions: 3,
+            imports: 1,
+            exports: 2,
+            globals: 2,
+            memories: 1,
+        })
+    );
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    assert_eq!(
+        module.validate(),
+        Ok(ValidationReport {
+            functions: 0,
+            imports: 0,
+            exports: 4,
+            globals: 4,
+            memories: 0,
+        })
+    );
+}
+
+#[test]
+fn test_call_wasm_from_closure() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(REENTER)
+        .expect("Unable to load module");
+    let depth = Rc::new(Cell::new(0));
+    let max_depth = depth.clone();
+    module
+        .link_closure_fallible::<i32, i32, Error, _>("env", "h", move |cc, n| {
+            if n == 0 {
+                return Ok(100);
+            }
+            max_depth.set(max_depth.get() + 1);
+            cc.runtime().find_function::<i32, i32>("b")?.call(n - 1)
+        })
+        .expect("Unable to link closure");
+    let a = module
+        .find_function::<i32, i32>("a")
+        .expect("Unable to find function");
+    // a(3) = b(2) + 3 = a(2) * 10 + 3 = ..., a(0) = 100
+    assert_eq!(a.call(3), Ok(100_123));
+    assert_eq!(depth.get(), 3);
+    // the frames of the outer calls survived, so the runtime is usable afterwards
+    assert_eq!(a.call(0), Ok(100));
+}
+
+#[test]
+fn test_run_start() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(START)
+        .expect("Unable to load module");
+    let calls = Rc::new(Cell::new(0));
+    let counter = calls.clone();
+    module
+        .link_closure::<(), i32, _>("env", "init", move |_, ()| {
+            counter.set(counter.get() + 1);
+            Ok(42)
+        })
+        .expect("Unable to link closure");
+    let get = module
+        .find_function::<(), i32>("get")
+        .expect("Unable to find function");
+    assert_eq!(get.call(), Ok(0));
+    assert_eq!(module.run_start(), Ok(()));
+    assert_eq!(get.call(), Ok(42));
+    assert_eq!(module.run_start(), Ok(()));
+    assert_eq!(calls.get(), 1);
+
+    let module = rt
+        .parse_and_load_module(VALIDATE)
+        .expect("Unable to load module");
+    assert_eq!(module.run_start(), Ok(()));
+}
+
+#[test]
+fn test_load_and_start() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, START_SENTINEL).expect("Unable to parse module");
+    let module = rt.load_and_start(module).expect("Unable to load module");
+    let mut sentinel = [0; 4];
+    rt.read_into(0, &mut sentinel)
+        .expect("Unable to read memory");
+    assert_eq!(u32::from_le_bytes(sentinel), 42);
+
+    // the start function only ever runs once
+    rt.write_at(0, &[0; 4]).expect("Unable to write memory");
+    assert_eq!(module.run_start(), Ok(()));
+    rt.read_into(0, &mut sentinel)
+        .expect("Unable to read memory");
+    assert_eq!(u32::from_le_bytes(sentinel), 0);
+}
+
+#[test]
+fn test_module_info() {
+    let env = Environment::new().expect("Unable to create environment");
+    let mut module = ParsedModule::parse(&env, VALIDATE).expect("Unable to parse module");
+    assert_eq!(
+        ModuleInfo::from(&module),
+        ModuleInfo {
+            name: None,
+            num_functions: 3,
+            num_imports: 1,
+            num_globals: 2,
+            num_memories: 1,
+        }
+    );
+    module.set_name("validate").expect("Unable to set name");
+    assert_eq!(ModuleInfo::from(&module).name.as_deref(), Some("validate"));
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    assert_eq!(
+        ModuleInfo::from(&module),
+        ModuleInfo {
+            name: None,
+            num_functions: 0,
+            num_imports: 0,
+            num_globals: 4,
+            num_memories: 0,
+        }
+    );
+    let mut module = ParsedModule::parse(&env, NAMED).expect("Unable to parse module");
+    assert_eq!(ModuleInfo::from(&module).name.as_deref(), Some("named"));
+    module.set_name("renamed").expect("Unable to set name");
+    assert_eq!(ModuleInfo::from(&module).name.as_deref(), Some("renamed"));
+}
+
+#[test]
+fn test_runtime_reset() {
+    let env = Environment::new().expect("Unable to create environment");
+    let mut rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let counter = Rc::new(Cell::new(0));
+    for _ in 0..2 {
+        let mut module = rt
+            .parse_and_load_module(IMPORT_GET)
+            .expect("Unable to load module");
+        let count = counter.clone();
+        module
+            .link_closure::<(), i64, _>("env", "get", move |_, ()| {
+                count.set(count.get() + 1);
+                Ok(count.get())
+            })
+            .expect("Unable to link closure");
+        let grown = counter.clone();
+        rt.set_memory_grow_callback(move |_| grown.set(grown.get() + 1));
+        let func = module
+            .find_function::<(), i64>("call_get")
+            .expect("Unable to find function");
+        assert_eq!(func.call(), Ok(1));
+        assert!(rt.stack_usage() > 0);
+
+        rt.reset().expect("Unable to reset runtime");
+        assert_eq!(rt.module_count(), 0);
+        assert_eq!(rt.memory_pages(), 0);
+        assert_eq!(rt.stack_usage(), 0);
+        // the linked closure and the memory grow callback have been dropped
+        assert_eq!(Rc::strong_count(&counter), 1);
+        counter.set(0);
+    }
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn test_link_closure_wasm_ptr() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(DOUBLE_PTR)
+        .expect("Unable to load module");
+    module
+        .link_closure_fallible::<WasmPtr<u32>, (), Error, _>("env", "double", |mut cc, ptr| {
+            let mut memory = cc.memory_view();
+            let value = ptr.deref(&memory)?;
+            ptr.write(&mut memory, value * 2)
+        })
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<(), u32>("run")
+        .expect("Unable to find function");
+    assert_eq!(func.call(), Ok(10));
+    assert_eq!(func.call(), Ok(20));
+}
+
+#[test]
+fn test_runtime_modules() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    assert_eq!(rt.module_count(), 0);
+    rt.parse_and_load_module(GLOBALS)
+        .expect("Unable to load module");
+    rt.parse_and_load_module(IMPORT_GET)
+        .expect("Unable to load module");
+    assert_eq!(rt.module_count(), 2);
+    // every yielded module can be used to look up its exports
+    let provider = rt
+        .modules()
+        .find(|module| module.find_global("answer").is_ok())
+        .expect("Unable to find module");
+    assert_eq!(
+        provider.find_global("answer").map(|global| global.get()),
+        Ok(Value::I32(42))
+    );
+    assert!(rt
+        .modules()
+        .any(|module| module.exports().any(|func| func.name() == "call_get")));
+}
+
+#[test]
+fn test_link_closure_wasm_slice() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(LOG)
+        .expect("Unable to load module");
+    let logged = Rc::new(RefCell::new(Vec::new()));
+    let log = logged.clone();
+    module
+        .link_closure_fallible::<(WasmSlice,), u32, Error, _>(
+            "env",
+            "log",
+            move |mut cc, (buffer,)| {
+                let memory = cc.memory_view();
+                log.borrow_mut().push(buffer.as_str(&memory)?.to_owned());
+                Ok(buffer.len())
+            },
+        )
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<(u32, u32), u32>("log_buffer")
+        .expect("Unable to find function");
+    assert_eq!(func.call(16, 11), Ok(11));
+    assert_eq!(*logged.borrow(), ["hello, host"]);
+    // out of bounds and wrapping buffers fail the call instead of panicking
+    assert!(matches!(func.call(65530, 100), Err(Error::HostFunction(_))));
+    assert!(matches!(
+        func.call(u32::MAX, 2),
+        Err(Error::HostFunction(_))
+    ));
+    assert_eq!(logged.borrow().len(), 1);
+}
+
+#[test]
+fn test_link_closure_reads_memory() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(LOG)
+        .expect("Unable to load module");
+    let output = Rc::new(RefCell::new(Vec::new()));
+    let sink = output.clone();
+    module
+        .link_closure::<(u32, u32), u32, _>("env", "log", move |mut cc, (ptr, len)| {
+            let memory = cc.memory_view();
+            let bytes = memory
+                .read_bytes(ptr as usize, len as usize)
+                .map_err(|_| Trap::OutOfBoundsMemoryAccess)?;
+            sink.borrow_mut().extend_from_slice(bytes);
+            Ok(len)
+        })
+        .expect("Unable to link closure");
+    let func = module
+        .find_function::<(u32, u32), u32>("log_buffer")
+        .expect("Unable to find function");
+    assert_eq!(func.call(16, 5), Ok(5));
+    assert_eq!(func.call(21, 6), Ok(6));
+    assert_eq!(*output.borrow(), b"hello, host");
+    match func.call(65530, 100) {
+        Err(Error::Wasm3(err)) => assert!(err.is_trap(Trap::OutOfBoundsMemoryAccess)),
+        res => panic!("expected a trap, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_parse_multi_value_unsupported() {
+    let env = Environment::new().expect("Unable to create environment");
+    match ParsedModule::parse(&env, MULTI_VALUE) {
+        Err(Error::Wasm3(_)) => (),
+        res => panic!("expected a parse error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_parse_from_reader() {
+    struct FailingReader;
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    let env = Environment::new().expect("Unable to create environment");
+    let module = ParsedModule::parse_from_reader(&env, std::io::Cursor::new(VALIDATE))
+        .expect("Unable to parse module");
+    assert_eq!(module.validate().map(|report| report.functions), Ok(3));
+    match ParsedModule::parse_from_reader(&env, FailingReader) {
+        Err(Error::Io(std::io::ErrorKind::BrokenPipe)) => (),
+        res => panic!("expected an I/O error, got {:?}", res.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_custom_sections() {
+    let env = Environment::new().expect("Unable to create environment");
+    let module = ParsedModule::parse(&env, CUSTOM_SECTIONS).expect("Unable to parse module");
+    let sections = module.custom_sections().collect::<Vec<_>>();
+    assert_eq!(sections, [("meta", &[1, 2, 3][..]), ("empty", &[][..])]);
+    let module = ParsedModule::parse(&env, VALIDATE).expect("Unable to parse module");
+    assert_eq!(module.custom_sections().count(), 0);
+}
+
+#[test]
+fn test_runtime_modules_by_name() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    for name in &["first", "second"] {
+        let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+        rt.load_module_named(module, name)
+            .expect("Unable to load module");
+    }
+    let mut names = rt
+        .modules()
+        .map(|module| module.name().to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, ["first", "second"]);
+    let second = rt
+        .modules()
+        .find(|module| module.name() == "second")
+        .expect("Unable to find module");
+    assert_eq!(
+        second.find_global("answer").map(|global| global.get()),
+        Ok(Value::I32(42))
+    );
+}
+
+#[test]
+fn test_parsed_module_set_name() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    assert_eq!(module.set_name("bad\0name"), Err(Error::InvalidModuleName));
+    module.set_name("first").expect("Unable to set name");
+    module.set_name("globals").expect("Unable to set name");
+    assert_eq!(module.name(), "globals");
+    let module = rt.load_module(module).expect("Unable to load module");
+    assert_eq!(module.name(), "globals");
+    let found = rt.find_module("globals").expect("Unable to find module");
+    assert_eq!(found.name(), "globals");
+    assert!(rt.find_module("first").is_err());
+}
+
+#[test]
+fn test_load_module_named() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    assert!(matches!(
+        rt.load_module_named(module, "bad\0name"),
+        Err(Error::InvalidModuleName)
+    ));
+    assert_eq!(rt.module_count(), 0);
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    let module = rt
+        .load_module_named(module, "instance")
+        .expect("Unable to load module");
+    assert_eq!(module.name(), "instance");
+    assert!(rt.find_module("instance").is_ok());
+}
+
+#[test]
+fn test_link_module() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut main = rt
+        .parse_and_load_module(MAIN)
+        .expect("Unable to load module");
+
+    // a module named like the import that does not export the function
+    let module = ParsedModule::parse(&env, START).expect("Unable to parse module");
+    let other = rt
+        .load_module_named(module, "lib")
+        .expect("Unable to load module");
+    assert_eq!(
+        main.link_module(&other),
+        Err(Error::UnresolvedImport {
+            module_name: "lib".into(),
+            function_name: "double".into(),
+        })
+    );
+    assert!(!main
+        .find_import("lib", "double")
+        .expect("Unable to find import")
+        .is_linked());
+
+    let module = ParsedModule::parse(&env, LIB).expect("Unable to parse module");
+    let lib = rt
+        .load_module_named(module, "lib")
+        .expect("Unable to load module");
+    main.link_module(&lib).expect("Unable to link module");
+    let quadruple = main
+        .find_function::<i32, i32>("quadruple")
+        .expect("Unable to find function");
+    assert_eq!(quadruple.call(3), Ok(12));
+}
+
+#[test]
+fn test_link_module_keeps_linked_imports() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut main = rt
+        .parse_and_load_module(MAIN)
+        .expect("Unable to load module");
+    main.link_closure::<i32, i32, _>("lib", "double", |_, n| Ok(n * 3))
+        .expect("Unable to link closure");
+    let module = ParsedModule::parse(&env, LIB).expect("Unable to parse module");
+    let lib = rt
+        .load_module_named(module, "lib")
+        .expect("Unable to load module");
+    main.link_module(&lib).expect("Unable to link module");
+    // the closure is still linked, so main does not depend on lib
+    let quadruple = main
+        .find_function::<i32, i32>("quadruple")
+        .expect("Unable to find function");
+    assert_eq!(quadruple.call(3), Ok(27));
+    assert_eq!(unsafe { lib.unload() }, Ok(()));
+    assert_eq!(quadruple.call(3), Ok(27));
+}
+
+#[test]
+fn test_link_module_to_itself() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, SELF_LINK).expect("Unable to parse module");
+    rt.load_module_named(module, "lib")
+        .expect("Unable to load module");
+    let mut main = rt.find_module("lib").expect("Unable to find module");
+    let lib = rt.find_module("lib").expect("Unable to find module");
+    main.link_module(&lib).expect("Unable to link module");
+    assert!(main
+        .find_import("lib", "double")
+        .expect("Unable to find import")
+        .is_linked());
+    let quadruple = main
+        .find_function::<i32, i32>("quadruple")
+        .expect("Unable to find function");
+    assert_eq!(quadruple.call(3), Ok(12));
+}
+
+#[test]
+fn test_unload_module() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    let first = rt
+        .load_module_named(module, "first")
+        .expect("Unable to load module");
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    rt.load_module_named(module, "second")
+        .expect("Unable to load module");
+    let stale = rt.find_module("first").expect("Unable to find module");
+    // nothing obtained from the module is used afterwards
+    assert_eq!(unsafe { first.unload() }, Ok(()));
+    assert_eq!(rt.module_count(), 1);
+    // a handle to a module that is no longer loaded is rejected
+    assert!(matches!(
+        unsafe { stale.unload() },
+        Err(Error::ModuleNotFound)
+    ));
+    assert_eq!(rt.module_count(), 1);
+    assert!(matches!(
+        rt.find_module("first"),
+        Err(Error::ModuleNotFound)
+    ));
+    let second = rt.find_module("second").expect("Unable to find module");
+    assert_eq!(
+        second.find_global("answer").map(|global| global.get()),
+        Ok(Value::I32(42))
+    );
+    assert_eq!(unsafe { second.unload() }, Ok(()));
+    assert_eq!(rt.module_count(), 0);
+}
+
+#[test]
+fn test_unload_module_same_name() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, GLOBALS).expect("Unable to parse module");
+    rt.load_module_named(module, "globals")
+        .expect("Unable to load module");
+    let module = ParsedModule::parse(&env, LIB).expect("Unable to parse module");
+    let second = rt
+        .load_module_named(module, "globals")
+        .expect("Unable to load module");
+    assert_eq!(unsafe { second.unload() }, Ok(()));
+    assert_eq!(rt.module_count(), 1);
+    // the handle tells the modules apart, so the one loaded first remains
+    let module = rt.find_module("globals").expect("Unable to find module");
+    assert!(module.find_global("answer").is_ok());
+    assert!(module.find_function::<i32, i32>("double").is_err());
+}
+
+#[test]
+fn test_unload_linked_module() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, MAIN).expect("Unable to parse module");
+    let mut main = rt
+        .load_module_named(module, "main")
+        .expect("Unable to load module");
+    let module = ParsedModule::parse(&env, LIB).expect("Unable to parse module");
+    let lib = rt
+        .load_module_named(module, "lib")
+        .expect("Unable to load module");
+    main.link_module(&lib).expect("Unable to link module");
+
+    assert_eq!(
+        unsafe { lib.unload() },
+        Err(Error::ModuleInUse {
+            module_name: "lib".into(),
+            dependent: "main".into(),
+        })
+    );
+    assert_eq!(rt.module_count(), 2);
+    let lib = rt.find_module("lib").expect("Unable to find module");
+    assert_eq!(unsafe { main.unload() }, Ok(()));
+    assert_eq!(unsafe { lib.unload() }, Ok(()));
+    assert_eq!(rt.module_count(), 0);
+}
+
+#[test]
+fn test_module_display() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = ParsedModule::parse(&env, START).expect("Unable to parse module");
+    let module = rt
+        .load_module_named(module, "start")
+        .expect("Unable to load module");
+    assert_eq!(module.to_string(), "Module(start: 1 exports, 1 imports)");
+}
+
+#[test]
+fn test_bool_args_and_returns() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = rt
+        .parse_and_load_module(BOOLS)
+        .expect("Unable to load module");
+    module
+        .link_closure::<bool, bool, _>("env", "not", |_, b| Ok(!b))
+        .expect("Unable to link closure");
+
+    // any non-zero i32 returned by the guest is read as true
+    let id = module
+        .find_function::<u32, bool>("id")
+        .expect("Unable to find function");
+    assert_eq!(id.call(0), Ok(false));
+    assert_eq!(id.call(1), Ok(true));
+    assert_eq!(id.call(7), Ok(true));
+
+    // true is passed to the guest as 1
+    let id = module
+        .find_function::<bool, u32>("id")
+        .expect("Unable to find function");
+    assert_eq!(id.call(true), Ok(1));
+    assert_eq!(id.call(false), Ok(0));
+
+    let call_not = module
+        .find_function::<u32, u32>("call_not")
+        .expect("Unable to find function");
+    assert_eq!(call_not.call(0), Ok(1));
+    assert_eq!(call_not.call(7), Ok(0));
+}
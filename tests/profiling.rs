@@ -0,0 +1,108 @@
+#![cfg(feature = "profiling")]
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use wasm3::profiling::{CallEvent, Clock};
+use wasm3::{Environment, SendRuntime};
+
+/// (module
+///   (func (export "add") (param i32 i32) (result i32)
+///     (i32.add (local.get 0) (local.get 1)))
+///   (func (export "trap")
+///     (unreachable)))
+const PROFILED: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0a, 0x02, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+    0x7f, 0x60, 0x00, 0x00, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x0e, 0x02, 0x03, 0x61, 0x64, 0x64,
+    0x00, 0x00, 0x04, 0x74, 0x72, 0x61, 0x70, 0x00, 0x01, 0x0a, 0x0d, 0x02, 0x07, 0x00, 0x20, 0x00,
+    0x20, 0x01, 0x6a, 0x0b, 0x03, 0x00, 0x00, 0x0b,
+];
+
+/// A clock that advances by a millisecond every time it is read.
+struct TickClock(Cell<u64>);
+
+impl Clock for TickClock {
+    fn now(&self) -> Duration {
+        let ticks = self.0.get();
+        self.0.set(ticks + 1);
+        Duration::from_millis(ticks)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Enter(Option<String>),
+    Exit(Option<String>, Duration, bool),
+}
+
+#[test]
+fn test_call_observer() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let module = rt
+        .parse_and_load_module(PROFILED)
+        .expect("Unable to load module");
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let recorded = events.clone();
+    rt.set_call_observer(TickClock(Cell::new(0)), move |event: &CallEvent<'_>| {
+        recorded.borrow_mut().push(match *event {
+            CallEvent::Enter { function } => Event::Enter(function.map(String::from)),
+            CallEvent::Exit {
+                function,
+                duration,
+                trapped,
+            } => Event::Exit(function.map(String::from), duration, trapped),
+        })
+    });
+
+    let add = module
+        .find_function::<(i32, i32), i32>("add")
+        .expect("Unable to find function");
+    assert_eq!(add.call(2, 3), Ok(5));
+    let trap = module
+        .find_function_dyn("trap")
+        .expect("Unable to find function");
+    assert!(trap.call_dynamic(&[]).is_err());
+    assert_eq!(
+        *events.borrow(),
+        vec![
+            Event::Enter(Some("add".into())),
+            Event::Exit(Some("add".into()), Duration::from_millis(1), false),
+            Event::Enter(Some("trap".into())),
+            Event::Exit(Some("trap".into()), Duration::from_millis(1), true),
+        ]
+    );
+
+    rt.clear_call_observer();
+    assert_eq!(add.call(2, 3), Ok(5));
+    assert_eq!(events.borrow().len(), 4);
+}
+
+#[test]
+fn test_send_runtime_with_call_observer() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    drop(env);
+    rt.set_call_observer(TickClock(Cell::new(0)), |_| ());
+    // the observer and its clock are not required to be Send
+    let rt = SendRuntime::new(rt).expect_err("runtime should not be sendable");
+    rt.clear_call_observer();
+    SendRuntime::new(rt).expect("runtime should be sendable");
+}
+
+#[test]
+fn test_reset_clears_call_observer() {
+    let env = Environment::new().expect("Unable to create environment");
+    let mut rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    drop(env);
+    rt.set_call_observer(TickClock(Cell::new(0)), |_| ());
+    rt.reset().expect("Unable to reset runtime");
+    // the observer is gone along with everything else the runtime was set up with
+    SendRuntime::new(rt).expect("runtime should be sendable");
+}
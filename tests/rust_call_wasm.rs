@@ -1,6 +1,137 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm3::error::{Error, Trap};
+use wasm3::DynFunction;
 use wasm3::Environment;
+use wasm3::FunctionSignature;
 use wasm3::Module;
+use wasm3::ParsedModule;
 use wasm3::Runtime;
+use wasm3::RuntimeHandle;
+use wasm3::SendRuntime;
+use wasm3::Value;
+use wasm3::ValueType;
+
+/// (module
+///   (func $fib (export "fib") (param i32) (result i32)
+///     (if (i32.lt_u (local.get 0) (i32.const 2))
+///       (then (return (local.get 0))))
+///     (return
+///       (i32.add
+///         (call $fib (i32.sub (local.get 0) (i32.const 2)))
+///         (call $fib (i32.sub (local.get 0) (i32.const 1)))))))
+const FIB32: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x06, 0x01, 0x60, 0x01, 0x7f, 0x01, 0x7f,
+    0x03, 0x02, 0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x66, 0x69, 0x62, 0x00, 0x00, 0x0a, 0x1f, 0x01,
+    0x1d, 0x00, 0x20, 0x00, 0x41, 0x02, 0x49, 0x04, 0x40, 0x20, 0x00, 0x0f, 0x0b, 0x20, 0x00, 0x41,
+    0x02, 0x6b, 0x10, 0x00, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x10, 0x00, 0x6a, 0x0f, 0x0b,
+];
+
+/// (module
+///   (func (export "mixed") (param i32 i64 f32 f64) (result f64)
+///     (local.get 3))
+///   (func (export "demote") (param f64) (result f32)
+///     (f32.demote_f64 (local.get 0))))
+const SIGNATURES: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0e, 0x02, 0x60, 0x04, 0x7f, 0x7e, 0x7d,
+    0x7c, 0x01, 0x7c, 0x60, 0x01, 0x7c, 0x01, 0x7d, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x12, 0x02,
+    0x05, 0x6d, 0x69, 0x78, 0x65, 0x64, 0x00, 0x00, 0x06, 0x64, 0x65, 0x6d, 0x6f, 0x74, 0x65, 0x00,
+    0x01, 0x0a, 0x0c, 0x02, 0x04, 0x00, 0x20, 0x03, 0x0b, 0x05, 0x00, 0x20, 0x00, 0xb6, 0x0b,
+];
+
+/// (module (func (export "init") (result i32) (i32.const 1)))
+const INIT: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, 0x03,
+    0x02, 0x01, 0x00, 0x07, 0x08, 0x01, 0x04, 0x69, 0x6e, 0x69, 0x74, 0x00, 0x00, 0x0a, 0x06, 0x01,
+    0x04, 0x00, 0x41, 0x01, 0x0b,
+];
+
+/// (module
+///   (func (export "div") (param i32 i32) (result i32)
+///     (i32.div_s (local.get 0) (local.get 1))))
+const DIVIDE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+    0x7f, 0x03, 0x02, 0x01, 0x00, 0x07, 0x07, 0x01, 0x03, 0x64, 0x69, 0x76, 0x00, 0x00, 0x0a, 0x09,
+    0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6d, 0x0b,
+];
+
+/// (module (func $recurse (export "recurse") (call $recurse)))
+const RECURSE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x04, 0x01, 0x60, 0x00, 0x00, 0x03, 0x02,
+    0x01, 0x00, 0x07, 0x0b, 0x01, 0x07, 0x72, 0x65, 0x63, 0x75, 0x72, 0x73, 0x65, 0x00, 0x00, 0x0a,
+    0x06, 0x01, 0x04, 0x00, 0x10, 0x00, 0x0b,
+];
+
+/// (module
+///   (import "env" "sum12" (func $sum12
+///     (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)))
+///   (func (export "sum16")
+///     (param i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32 i32) (result i32)
+///     (i32.add (local.get 0) (local.get 1))
+///     ;; ... adding up all 16 parameters
+///     (i32.add (local.get 15)))
+///   (func (export "call_sum12") (result i32)
+///     (call $sum12
+///       (i32.const 1) (i32.const 2) (i32.const 3) (i32.const 4) (i32.const 5) (i32.const 6)
+///       (i32.const 7) (i32.const 8) (i32.const 9) (i32.const 10) (i32.const 11) (i32.const 12))))
+const ARITY: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x29, 0x03, 0x60, 0x0c, 0x7f, 0x7f, 0x7f,
+    0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x01, 0x7f, 0x60, 0x10, 0x7f, 0x7f, 0x7f,
+    0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x01, 0x7f, 0x60,
+    0x00, 0x01, 0x7f, 0x02, 0x0d, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x05, 0x73, 0x75, 0x6d, 0x31, 0x32,
+    0x00, 0x00, 0x03, 0x03, 0x02, 0x01, 0x02, 0x07, 0x16, 0x02, 0x05, 0x73, 0x75, 0x6d, 0x31, 0x36,
+    0x00, 0x01, 0x0a, 0x63, 0x61, 0x6c, 0x6c, 0x5f, 0x73, 0x75, 0x6d, 0x31, 0x32, 0x00, 0x02, 0x0a,
+    0x50, 0x02, 0x31, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x20, 0x02, 0x6a, 0x20, 0x03, 0x6a, 0x20,
+    0x04, 0x6a, 0x20, 0x05, 0x6a, 0x20, 0x06, 0x6a, 0x20, 0x07, 0x6a, 0x20, 0x08, 0x6a, 0x20, 0x09,
+    0x6a, 0x20, 0x0a, 0x6a, 0x20, 0x0b, 0x6a, 0x20, 0x0c, 0x6a, 0x20, 0x0d, 0x6a, 0x20, 0x0e, 0x6a,
+    0x20, 0x0f, 0x6a, 0x0b, 0x1c, 0x00, 0x41, 0x01, 0x41, 0x02, 0x41, 0x03, 0x41, 0x04, 0x41, 0x05,
+    0x41, 0x06, 0x41, 0x07, 0x41, 0x08, 0x41, 0x09, 0x41, 0x0a, 0x41, 0x0b, 0x41, 0x0c, 0x10, 0x00,
+    0x0b,
+];
+
+/// (module
+///   (func (export "spin")
+///     (loop (br 0)))
+///   (func (export "count") (param i32) (result i32)
+///     (loop
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br_if 0 (local.get 0)))
+///     (local.get 0)))
+const LOOPS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x09, 0x02, 0x60, 0x00, 0x00, 0x60, 0x01,
+    0x7f, 0x01, 0x7f, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x10, 0x02, 0x04, 0x73, 0x70, 0x69, 0x6e,
+    0x00, 0x00, 0x05, 0x63, 0x6f, 0x75, 0x6e, 0x74, 0x00, 0x01, 0x0a, 0x1c, 0x02, 0x07, 0x00, 0x03,
+    0x40, 0x0c, 0x00, 0x0b, 0x0b, 0x12, 0x00, 0x03, 0x40, 0x20, 0x00, 0x41, 0x01, 0x6b, 0x21, 0x00,
+    0x20, 0x00, 0x0d, 0x00, 0x0b, 0x20, 0x00, 0x0b,
+];
+
+/// (module
+///   (import "env" "check" (func $check (result i32)))
+///   (func (export "run") (result i32)
+///     (call $check))
+///   (func (export "spin")
+///     (loop (br 0))))
+const CHECK_SPIN: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x60, 0x00, 0x01, 0x7f, 0x60,
+    0x00, 0x00, 0x02, 0x0d, 0x01, 0x03, 0x65, 0x6e, 0x76, 0x05, 0x63, 0x68, 0x65, 0x63, 0x6b, 0x00,
+    0x00, 0x03, 0x03, 0x02, 0x00, 0x01, 0x07, 0x0e, 0x02, 0x03, 0x72, 0x75, 0x6e, 0x00, 0x01, 0x04,
+    0x73, 0x70, 0x69, 0x6e, 0x00, 0x02, 0x0a, 0x0e, 0x02, 0x04, 0x00, 0x10, 0x00, 0x0b, 0x07, 0x00,
+    0x03, 0x40, 0x0c, 0x00, 0x0b, 0x0b,
+];
+
+/// (module
+///   (func $helper (result i32)
+///     (i32.const 7))
+///   (func (export "call_helper") (result i32)
+///     (call $helper)))
+const HELPER: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7f, 0x03,
+    0x03, 0x02, 0x00, 0x00, 0x07, 0x0f, 0x01, 0x0b, 0x63, 0x61, 0x6c, 0x6c, 0x5f, 0x68, 0x65, 0x6c,
+    0x70, 0x65, 0x72, 0x00, 0x01, 0x0a, 0x0b, 0x02, 0x04, 0x00, 0x41, 0x07, 0x0b, 0x04, 0x00, 0x10,
+    0x00, 0x0b,
+];
 
 fn runtime() -> Runtime {
     Environment::new()
@@ -98,3 +229,909 @@ fn test_resize_memory() {
     rt.resize_memory(10).expect("could not resize memory");
     assert_eq!(func.call(), Ok(10));
 }
+
+#[test]
+fn test_call_dynamic() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function_dyn("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(
+        func.call_dynamic(&[Value::I32(124), Value::I32(612)]),
+        Ok(vec![Value::I32(736)])
+    );
+}
+
+#[test]
+fn test_call_dynamic_stack_too_small() {
+    let rt = Environment::new()
+        .expect("Unable to create environment")
+        .create_runtime(4)
+        .expect("Unable to create runtime");
+    let module = module(&rt);
+    let func = module
+        .find_function_dyn("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(
+        func.call_dynamic(&[Value::I32(124), Value::I32(612)]),
+        Err(Error::StackOverflow)
+    );
+}
+
+#[test]
+fn test_call_dynamic_from_typed() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function::<(u64, u64), u64>("add_u64")
+        .expect("Unable to find function");
+    let func = DynFunction::from(func);
+    assert_eq!(func.arg_types(), [ValueType::I64, ValueType::I64]);
+    assert_eq!(
+        func.call_dynamic(&[Value::I64(124), Value::I64(612)]),
+        Ok(vec![Value::I64(736)])
+    );
+}
+
+#[test]
+fn test_call_dynamic_no_args_no_ret() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function_dyn("empty")
+        .expect("Unable to find function");
+    assert_eq!(func.call_dynamic(&[]), Ok(vec![]));
+}
+
+#[test]
+fn test_dyn_function_try_typed() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function_dyn("add_u64")
+        .expect("Unable to find function");
+    match func.try_typed::<(u32, u32), u32>() {
+        Err(Error::SignatureMismatch {
+            expected,
+            requested,
+            first_mismatch,
+        }) => {
+            assert_eq!(expected.as_str(), "I(II)");
+            assert_eq!(requested.as_str(), "i(ii)");
+            assert_eq!(first_mismatch, Some(0));
+        }
+        res => panic!("expected a signature mismatch, got {:?}", res.map(|_| ())),
+    }
+    let func = func
+        .try_typed::<(u64, u64), u64>()
+        .expect("Unable to convert function");
+    assert_eq!(func.call(1, 2), Ok(3));
+}
+
+#[test]
+fn test_call_dynamic_signature_mismatch() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function_dyn("add_u64")
+        .expect("Unable to find function");
+    assert_eq!(
+        func.call_dynamic(&[Value::I64(1)]),
+        Err(Error::ArgumentTypeMismatch {
+            expected: vec![ValueType::I64, ValueType::I64],
+            provided: vec![ValueType::I64],
+        })
+    );
+    assert_eq!(
+        func.call_dynamic(&[Value::I64(1), Value::I32(2)]),
+        Err(Error::ArgumentTypeMismatch {
+            expected: vec![ValueType::I64, ValueType::I64],
+            provided: vec![ValueType::I64, ValueType::I32],
+        })
+    );
+}
+
+#[test]
+fn test_runtime_call() {
+    let rt = runtime();
+    module(&rt);
+    rt.parse_and_load_module(FIB32)
+        .expect("Unable to load module");
+    assert_eq!(rt.call::<(u64, u64), u64>("add_u64", (124, 612)), Ok(736));
+    assert_eq!(rt.call::<u32, u32>("fib", 10), Ok(55));
+    assert_eq!(
+        rt.call::<(), ()>("missing", ()),
+        Err(Error::FunctionNotFound)
+    );
+    assert!(matches!(
+        rt.call::<u64, u64>("fib", 10),
+        Err(Error::SignatureMismatch { .. })
+    ));
+
+    rt.parse_and_load_module(FIB32)
+        .expect("Unable to load module");
+    match rt.call::<u32, u32>("fib", 10) {
+        Err(Error::AmbiguousFunction { modules }) => assert_eq!(modules.len(), 2),
+        res => panic!("expected an ambiguous function, got {:?}", res),
+    }
+}
+
+#[test]
+fn test_runtime_find_function_in() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    for &name in &["first", "second"] {
+        let module = ParsedModule::parse(&env, INIT).expect("Unable to parse module");
+        let module = rt
+            .load_module_named(module, name)
+            .expect("Unable to load module");
+        assert_eq!(module.name(), name);
+    }
+    match rt.find_function::<(), i32>("init") {
+        Err(Error::AmbiguousFunction { mut modules }) => {
+            modules.sort();
+            assert_eq!(modules, ["first", "second"]);
+        }
+        res => panic!("expected an ambiguous function, got {:?}", res),
+    }
+    let func = rt
+        .find_function_in::<(), i32>("second", "init")
+        .expect("Unable to find function");
+    assert_eq!(func.module_name(), "second");
+    assert_eq!(func.call(), Ok(1));
+    assert!(matches!(
+        rt.find_function_in::<(), i32>("third", "init"),
+        Err(Error::ModuleNotFound)
+    ));
+    assert!(matches!(
+        rt.find_function_in::<(), i32>("first", "missing"),
+        Err(Error::FunctionNotFound)
+    ));
+}
+
+#[test]
+fn test_stack_overflow() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(RECURSE)
+        .expect("Unable to load module");
+    let func = module
+        .find_function::<(), ()>("recurse")
+        .expect("Unable to find function");
+    let err = func.call().expect_err("call should overflow the stack");
+    assert_eq!(err, Error::StackOverflow);
+    assert!(err.is_trap(Trap::StackOverflow));
+    assert!(!err.is_trap(Trap::Unreachable));
+}
+
+#[test]
+fn test_trap_message() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(DIVIDE)
+        .expect("Unable to load module");
+    let div = module
+        .find_function::<(i32, i32), i32>("div")
+        .expect("Unable to find function");
+    let err = div.call(1, 0).expect_err("Division by zero did not trap");
+    match &err {
+        Error::Wasm3(trap) => assert!(trap.is_trap(Trap::DivisionByZero)),
+        err => panic!("unexpected error {:?}", err),
+    }
+    // the message wasm3 reports the trap with is kept for display
+    assert_eq!(err.to_string(), Trap::DivisionByZero.to_string());
+    assert!(err.to_string().contains("divide by zero"));
+    assert!(format!("{:?}", err).contains("divide by zero"));
+}
+
+#[test]
+fn test_as_callable() {
+    let rt = runtime();
+    let module = module(&rt);
+    let add_u32 = module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    let sums = [(1, 2), (3, 4), (u32::MAX, 1)]
+        .iter()
+        .copied()
+        .map(add_u32.as_callable())
+        .collect::<Result<Vec<_>, _>>();
+    assert_eq!(sums, Ok(vec![3, 7, 0]));
+}
+
+#[test]
+fn test_call_unchecked() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module");
+    let fib = module
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    for n in 0..10 {
+        assert_eq!(unsafe { fib.call_unchecked(n) }, fib.call(n));
+    }
+
+    let module = rt
+        .parse_and_load_module(DIVIDE)
+        .expect("Unable to load module");
+    let div = module
+        .find_function::<(i32, i32), i32>("div")
+        .expect("Unable to find function");
+    assert_eq!(unsafe { div.call_unchecked((6, 3)) }, Ok(2));
+    assert!(matches!(
+        unsafe { div.call_unchecked((1, 0)) },
+        Err(Error::Wasm3(trap)) if trap.is_trap(Trap::DivisionByZero)
+    ));
+}
+
+#[test]
+fn test_call_raw() {
+    let rt = runtime();
+    let module = module(&rt);
+    let add_u32 = module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    // all slots passed below hold values of the parameter types
+    let mut slots = [u64::from(u32::MAX), 2];
+    assert_eq!(unsafe { add_u32.call_raw(&mut slots) }, Ok(()));
+    assert_eq!(slots, [1, 2]);
+    assert_eq!(
+        unsafe { add_u32.call_raw(&mut [1]) },
+        Err(Error::ArgumentCountMismatch {
+            expected: 2,
+            provided: 1,
+        })
+    );
+    let empty = module
+        .find_function::<(), ()>("empty")
+        .expect("Unable to find function");
+    assert_eq!(unsafe { empty.call_raw(&mut []) }, Ok(()));
+    let constant = module
+        .find_function::<(), u64>("constant")
+        .expect("Unable to find function");
+    assert_eq!(
+        unsafe { constant.call_raw(&mut []) },
+        Err(Error::ArgumentCountMismatch {
+            expected: 1,
+            provided: 0,
+        })
+    );
+
+    let module = rt
+        .parse_and_load_module(SIGNATURES)
+        .expect("Unable to load module");
+    let mixed = module
+        .find_function::<(i32, i64, f32, f64), f64>("mixed")
+        .expect("Unable to find function");
+    let mut slots = [
+        u64::from(-1i32 as u32),
+        0xFF,
+        u64::from(3.0f32.to_bits()),
+        4.5f64.to_bits(),
+    ];
+    assert_eq!(unsafe { mixed.call_raw(&mut slots) }, Ok(()));
+    assert_eq!(f64::from_bits(slots[0]), 4.5);
+    let demote = module
+        .find_function::<f64, f32>("demote")
+        .expect("Unable to find function");
+    let mut slots = [1.5f64.to_bits()];
+    assert_eq!(unsafe { demote.call_raw(&mut slots) }, Ok(()));
+    assert_eq!(slots[0], u64::from(1.5f32.to_bits()));
+}
+
+#[test]
+fn test_call_raw_stack_too_small() {
+    let rt = Environment::new()
+        .expect("Unable to create environment")
+        .create_runtime(4)
+        .expect("Unable to create runtime");
+    let module = module(&rt);
+    let add_u32 = module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(
+        unsafe { add_u32.call_raw(&mut [1, 2]) },
+        Err(Error::StackOverflow)
+    );
+}
+
+#[test]
+fn test_call_argv() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(SIGNATURES)
+        .expect("Unable to load module");
+    let mixed = module
+        .find_function_dyn("mixed")
+        .expect("Unable to find function");
+    assert_eq!(
+        mixed.call_argv(&["-1", "0xFF", "3", "4.5"]),
+        Ok(vec![Value::F64(4.5)])
+    );
+    assert_eq!(
+        mixed.call_argv(&["1", "2", "3"]),
+        Err(Error::ArgumentCountMismatch {
+            expected: 4,
+            provided: 3,
+        })
+    );
+    assert_eq!(
+        mixed.call_argv(&["1", "2.5", "3", "4"]),
+        Err(Error::InvalidArgument {
+            index: 1,
+            expected: ValueType::I64,
+        })
+    );
+    assert_eq!(
+        mixed.call_argv(&["1", "2", "three", "4"]),
+        Err(Error::InvalidArgument {
+            index: 2,
+            expected: ValueType::F32,
+        })
+    );
+}
+
+#[test]
+fn test_call_checked() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(SIGNATURES)
+        .expect("Unable to load module");
+    let mixed = module
+        .find_function_dyn("mixed")
+        .expect("Unable to find function");
+    assert_eq!(
+        mixed.call_checked(&[
+            Value::I32(1),
+            Value::I64(2),
+            Value::F32(3.0),
+            Value::F64(4.5)
+        ]),
+        Ok(vec![Value::F64(4.5)])
+    );
+    assert_eq!(
+        mixed.call_checked(&[Value::I32(1)]),
+        Err(Error::ArgumentCountMismatch {
+            expected: 4,
+            provided: 1,
+        })
+    );
+    assert_eq!(
+        mixed.call_checked(&[
+            Value::I32(1),
+            Value::I32(2),
+            Value::F32(3.0),
+            Value::F64(4.5)
+        ]),
+        Err(Error::ArgumentTypeMismatch {
+            expected: vec![
+                ValueType::I32,
+                ValueType::I64,
+                ValueType::F32,
+                ValueType::F64
+            ],
+            provided: vec![
+                ValueType::I32,
+                ValueType::I32,
+                ValueType::F32,
+                ValueType::F64
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_exports() {
+    let rt = runtime();
+    let module = module(&rt);
+    let export = module
+        .exports()
+        .find(|export| export.name() == "add_u32")
+        .expect("Unable to find export");
+    let func = module
+        .function::<(u32, u32), u32>(export.index())
+        .expect("Unable to find function");
+    assert_eq!(func.name(), Some("add_u32"));
+    assert_eq!(func.call(124, 612), Ok(736));
+    for name in &["add_u64", "invert", "constant", "empty", "memory_size"] {
+        assert!(module.exports().any(|export| export.name() == *name));
+    }
+}
+
+#[test]
+fn test_stack_usage() {
+    let rt = runtime();
+    assert_eq!(rt.stack_usage(), 0);
+    let func = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    assert_eq!(func.call(1), Ok(1));
+    let shallow = rt.stack_usage();
+    assert!(shallow > 0);
+    assert_eq!(func.call(20), Ok(6765));
+    let deep = rt.stack_usage();
+    assert!(deep > shallow);
+    assert!(deep <= unsafe { &*rt.stack() }.len());
+    // the usage is a high-water mark, so shallower calls don't lower it
+    assert_eq!(func.call(1), Ok(1));
+    assert_eq!(rt.stack_usage(), deep);
+}
+
+#[test]
+fn test_runtime_builder() {
+    let env = Environment::new().expect("Unable to create environment");
+    let mut rt = Runtime::builder()
+        .stack_slots(4096)
+        .memory_limit(2)
+        .build(&env)
+        .expect("Unable to create runtime");
+    assert_eq!(unsafe { &*rt.stack() }.len(), 4096);
+    let func = module(&rt)
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(func.call(1, 2), Ok(3));
+    assert_eq!(rt.grow_memory(1), Ok(1));
+    assert_eq!(rt.grow_memory(1), Err(Error::MemoryGrowFailed));
+}
+
+#[test]
+fn test_send_runtime() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    module(&rt);
+    // the environment is still shared with `env`
+    let rt = SendRuntime::new(rt).expect_err("runtime should not be sendable");
+    drop(env);
+    let rt = SendRuntime::new(rt).expect("runtime should be sendable");
+    let res = std::thread::spawn(move || {
+        let rt = rt.into_inner();
+        let func = rt
+            .find_function::<(u32, u32), u32>("add_u32")
+            .expect("Unable to find function");
+        func.call(1, 2).ok()
+    })
+    .join()
+    .expect("thread panicked");
+    assert_eq!(res, Some(3));
+}
+
+#[test]
+fn test_functions() {
+    let rt = runtime();
+    let module = module(&rt);
+    assert_eq!(module.functions().count(), module.exports().count());
+    let add_u64 = module
+        .functions()
+        .find(|func| func.name() == Some("add_u64"))
+        .expect("Unable to find function");
+    assert_eq!(add_u64.arg_types(), [ValueType::I64, ValueType::I64]);
+    assert_eq!(add_u64.ret_type(), Some(ValueType::I64));
+    assert_eq!(
+        module
+            .function::<(u64, u64), u64>(add_u64.index())
+            .expect("Unable to find function")
+            .call(1, 2),
+        Ok(3)
+    );
+    let empty = module
+        .functions()
+        .find(|func| func.name() == Some("empty"))
+        .expect("Unable to find function");
+    assert_eq!(empty.arg_types(), []);
+    assert_eq!(empty.ret_type(), None);
+    assert_eq!(
+        empty.signature(),
+        FunctionSignature {
+            params: vec![],
+            returns: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_function_identity() {
+    let rt = runtime();
+    let other = runtime();
+    let other_module = module(&other);
+    let module = module(&rt);
+    let add_u32 = module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    let again = module
+        .function::<(u32, u32), u32>(add_u32.index())
+        .expect("Unable to find function");
+    assert_eq!(add_u32, again);
+    let dyn_add_u32 = module
+        .find_function_dyn("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(dyn_add_u32.index(), add_u32.index());
+    let add_u64 = module
+        .find_function_dyn("add_u64")
+        .expect("Unable to find function");
+    assert_ne!(dyn_add_u32, add_u64);
+
+    let mut calls = HashMap::new();
+    *calls.entry(add_u32).or_insert(0) += 1;
+    *calls.entry(again).or_insert(0) += 1;
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[&add_u32], 2);
+
+    let other_add_u32 = other_module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    assert_ne!(other_add_u32, add_u32);
+    assert_eq!(other_add_u32.index(), add_u32.index());
+}
+
+#[test]
+fn test_find_function_by_bytes() {
+    let rt = runtime();
+    let module = module(&rt);
+    let func = module
+        .find_function_by_bytes::<(u32, u32), u32>(b"add_u32")
+        .expect("Unable to find function");
+    assert_eq!(func.call(1, 2), Ok(3));
+    assert_eq!(
+        module
+            .find_function_by_bytes::<(u32, u32), u32>(b"add_u32\xff")
+            .map(|_| ()),
+        Err(Error::FunctionNotFound)
+    );
+}
+
+#[test]
+fn test_runtime_handle() {
+    let rt = SendRuntime::new(runtime()).expect("runtime should be sendable");
+    let handle = RuntimeHandle::new(rt);
+    module(&handle.lock());
+    let other = handle.clone();
+    let res = std::thread::spawn(move || {
+        other
+            .lock()
+            .find_function::<(u32, u32), u32>("add_u32")
+            .expect("Unable to find function")
+            .call(1, 2)
+    })
+    .join()
+    .expect("thread panicked");
+    assert_eq!(res, Ok(3));
+    assert_eq!(handle.lock().memory_pages(), 1);
+}
+
+#[test]
+fn test_runtime_handle_unsendable() {
+    let rt = SendRuntime::new(runtime()).expect("runtime should be sendable");
+    let handle = RuntimeHandle::new(rt);
+    let res = std::panic::catch_unwind(|| {
+        handle.lock().set_memory_grow_callback(|_| ());
+    });
+    assert!(res.is_err());
+    // the runtime has been dropped instead of being shared
+    let res = std::panic::catch_unwind(|| {
+        handle.lock();
+    });
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_function_signature_mixed() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(SIGNATURES)
+        .expect("Unable to load module");
+    let mixed = module
+        .find_function::<(i32, i64, f32, f64), f64>("mixed")
+        .expect("Unable to find function");
+    assert_eq!(
+        mixed.arg_types(),
+        [
+            ValueType::I32,
+            ValueType::I64,
+            ValueType::F32,
+            ValueType::F64
+        ]
+    );
+    assert_eq!(mixed.ret_type(), Some(ValueType::F64));
+    assert_eq!(
+        mixed.signature(),
+        FunctionSignature {
+            params: vec![
+                ValueType::I32,
+                ValueType::I64,
+                ValueType::F32,
+                ValueType::F64
+            ],
+            returns: vec![ValueType::F64],
+        }
+    );
+    assert_eq!(mixed.call(1, 2, 3.0, 4.5), Ok(4.5));
+    let demote = module
+        .find_function_dyn("demote")
+        .expect("Unable to find function");
+    assert_eq!(demote.arg_types(), [ValueType::F64]);
+    assert_eq!(demote.ret_type(), Some(ValueType::F32));
+    assert_eq!(
+        demote.call_dynamic(&[Value::F64(1.5)]),
+        Ok(vec![Value::F32(1.5)])
+    );
+}
+
+#[test]
+fn test_function_signature() {
+    let rt = runtime();
+    let module = module(&rt);
+    let add_u32 = module
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(add_u32.arg_types(), [ValueType::I32, ValueType::I32]);
+    assert_eq!(add_u32.ret_type(), Some(ValueType::I32));
+    let empty = module
+        .find_function_dyn("empty")
+        .expect("Unable to find function");
+    assert_eq!(empty.arg_types(), []);
+    assert_eq!(empty.ret_type(), None);
+    assert_eq!(
+        empty.signature(),
+        FunctionSignature {
+            params: vec![],
+            returns: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_function_names() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module");
+    let func = module
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    assert_eq!(func.name(), Some("fib"));
+    assert_eq!(func.module_name(), module.name());
+    let func = module
+        .find_function_dyn("fib")
+        .expect("Unable to find function");
+    assert_eq!(func.name(), Some("fib"));
+    assert_eq!(func.module_name(), module.name());
+}
+
+#[test]
+fn test_function_without_name() {
+    let rt = runtime();
+    let module = rt
+        .parse_and_load_module(HELPER)
+        .expect("Unable to load module");
+    let helper = module
+        .function::<(), i32>(0)
+        .expect("Unable to find function");
+    assert_eq!(helper.name(), None);
+    assert_eq!(helper.call(), Ok(7));
+    let helper = DynFunction::from(helper);
+    assert_eq!(helper.name(), None);
+    let call_helper = module
+        .function::<(), i32>(1)
+        .expect("Unable to find function");
+    assert_eq!(call_helper.name(), Some("call_helper"));
+}
+
+#[test]
+fn test_function_debug_display() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    let mut module = ParsedModule::parse(&env, FIB32).expect("Unable to parse module");
+    module.set_name("math").expect("Unable to set name");
+    let module = rt.load_module(module).expect("Unable to load module");
+    let func = module
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    assert_eq!(
+        format!("{:?}", func),
+        r#"Function { name: Some("fib"), module: "math", signature: i(i) }"#
+    );
+    assert_eq!(func.to_string(), "math::fib");
+    let func = module
+        .find_function_dyn("fib")
+        .expect("Unable to find function");
+    assert_eq!(
+        format!("{:?}", func),
+        r#"DynFunction { name: Some("fib"), module: "math", signature: i(i) }"#
+    );
+    assert_eq!(func.to_string(), "math::fib");
+}
+
+#[cfg(feature = "metering")]
+#[test]
+fn test_fuel() {
+    let rt = runtime();
+    let func = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    rt.set_fuel(100);
+    assert_eq!(func.call(1), Ok(1));
+    assert_eq!(rt.fuel_remaining(), 99);
+    assert_eq!(func.call(20), Err(Error::OutOfFuel));
+    assert_eq!(rt.fuel_remaining(), 0);
+    assert_eq!(func.call(1), Err(Error::OutOfFuel));
+    // the runtime stays usable after being refueled
+    rt.set_fuel(u64::MAX);
+    assert_eq!(func.call(20), Ok(6765));
+}
+
+#[cfg(feature = "metering")]
+#[test]
+fn test_call_with_timeout() {
+    let rt = runtime();
+    let func = rt
+        .parse_and_load_module(FIB32)
+        .expect("Unable to load module")
+        .find_function::<u32, u32>("fib")
+        .expect("Unable to find function");
+    rt.set_fuel(1000);
+    assert_eq!(func.call_with_timeout(1, 10), Ok(1));
+    assert_eq!(rt.fuel_remaining(), 999);
+    assert_eq!(func.call_with_timeout(20, 100), Err(Error::Timeout));
+    assert_eq!(rt.fuel_remaining(), 899);
+    // the runtime's fuel still bounds the call
+    assert_eq!(func.call_with_timeout(20, u64::MAX), Err(Error::OutOfFuel));
+    assert_eq!(rt.fuel_remaining(), 0);
+}
+
+#[cfg(feature = "metering")]
+#[test]
+fn test_call_with_timeout_loop() {
+    let rt = runtime();
+    let spin = rt
+        .parse_and_load_module(LOOPS)
+        .expect("Unable to load module")
+        .find_function::<(), ()>("spin")
+        .expect("Unable to find function");
+    assert_eq!(spin.call_with_timeout((), 10_000), Err(Error::Timeout));
+    assert_eq!(rt.fuel_remaining(), u64::MAX - 10_000);
+    // the function can be called again after timing out
+    assert_eq!(spin.call_with_timeout((), 10), Err(Error::Timeout));
+}
+
+#[cfg(feature = "metering")]
+#[test]
+fn test_call_with_timeout_from_closure() {
+    let rt = runtime();
+    let mut module = rt
+        .parse_and_load_module(CHECK_SPIN)
+        .expect("Unable to load module");
+    let observed = Rc::new(Cell::new(None));
+    let seen = observed.clone();
+    module
+        .link_closure::<(), i32, _>("env", "check", move |cc, ()| {
+            let rt = cc.runtime();
+            let spin = rt
+                .find_function::<(), ()>("spin")
+                .expect("Unable to find function");
+            let before = rt.fuel_remaining();
+            let timeout = spin.call_with_timeout((), 100);
+            let charged = before - rt.fuel_remaining();
+            let exhausted = spin.call_with_timeout((), before);
+            let left = rt.fuel_remaining();
+            rt.set_fuel(before);
+            seen.set(Some((timeout, charged, exhausted, left)));
+            Ok(7)
+        })
+        .expect("Unable to link closure");
+    let run = module
+        .find_function::<(), i32>("run")
+        .expect("Unable to find function");
+    rt.set_fuel(1000);
+    assert_eq!(run.call(), Ok(7));
+    // the limit applies to the fuel left to the outer call, which is charged for the inner calls
+    assert_eq!(
+        observed.take(),
+        Some((Err(Error::Timeout), 100, Err(Error::OutOfFuel), 0))
+    );
+    // the fuel set by the closure is what the outer call continued with
+    assert!(rt.fuel_remaining() > 900);
+}
+
+type Args12 = (u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32, u32);
+type Args16 = (
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+);
+type Args16Mismatch = (
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u32,
+    u64,
+    u32,
+    u32,
+    u32,
+);
+
+#[test]
+fn test_high_arity() {
+    let rt = runtime();
+    let mut module = rt
+        .parse_and_load_module(ARITY)
+        .expect("Unable to load module");
+    module
+        .link_closure::<Args12, u32, _>(
+            "env",
+            "sum12",
+            |_, (a, b, c, d, e, f, g, h, i, j, k, l)| {
+                // weigh the arguments by their position to catch them being reordered
+                Ok([a, b, c, d, e, f, g, h, i, j, k, l]
+                    .iter()
+                    .fold(0, |acc, &arg| acc * 2 + arg))
+            },
+        )
+        .expect("Unable to link closure");
+    let call_sum12 = module
+        .find_function::<(), u32>("call_sum12")
+        .expect("Unable to find function");
+    assert_eq!(call_sum12.call(), Ok(8178));
+
+    let sum16 = module
+        .find_function::<Args16, u32>("sum16")
+        .expect("Unable to find function");
+    assert_eq!(
+        sum16.call(1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16),
+        Ok(136)
+    );
+    // a mismatch in the 13th argument is caught
+    assert!(matches!(
+        module.find_function::<Args16Mismatch, u32>("sum16"),
+        Err(Error::SignatureMismatch {
+            first_mismatch: Some(12),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_take_error_info_none() {
+    let env = Environment::new().expect("Unable to create environment");
+    let rt = env
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime");
+    assert_eq!(rt.take_error_info(), None);
+    let func = module(&rt)
+        .find_function::<(u32, u32), u32>("add_u32")
+        .expect("Unable to find function");
+    assert_eq!(func.call(1, 2), Ok(3));
+    assert_eq!(rt.take_error_info(), None);
+}
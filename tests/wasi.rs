@@ -0,0 +1,151 @@
+#![cfg(feature = "wasi")]
+use wasm3::Environment;
+use wasm3::OutputBuffer;
+use wasm3::Runtime;
+use wasm3::WasiConfig;
+
+/// (module
+///   (type $strings (func (param i32 i32) (result i32)))
+///   (import "wasi_snapshot_preview1" "args_sizes_get" (func $args_sizes_get (type $strings)))
+///   (import "wasi_snapshot_preview1" "args_get" (func $args_get (type $strings)))
+///   (import "wasi_snapshot_preview1" "environ_sizes_get" (func $environ_sizes_get (type $strings)))
+///   (import "wasi_snapshot_preview1" "environ_get" (func $environ_get (type $strings)))
+///   (memory (export "memory") 1)
+///   (func (export "args_sizes") (type $strings)
+///     (call $args_sizes_get (local.get 0) (local.get 1)))
+///   (func (export "args") (type $strings)
+///     (call $args_get (local.get 0) (local.get 1)))
+///   (func (export "environ_sizes") (type $strings)
+///     (call $environ_sizes_get (local.get 0) (local.get 1)))
+///   (func (export "environ") (type $strings)
+///     (call $environ_get (local.get 0) (local.get 1))))
+const STRINGS: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01,
+    0x7f, 0x02, 0x9b, 0x01, 0x04, 0x16, 0x77, 0x61, 0x73, 0x69, 0x5f, 0x73, 0x6e, 0x61, 0x70, 0x73,
+    0x68, 0x6f, 0x74, 0x5f, 0x70, 0x72, 0x65, 0x76, 0x69, 0x65, 0x77, 0x31, 0x0e, 0x61, 0x72, 0x67,
+    0x73, 0x5f, 0x73, 0x69, 0x7a, 0x65, 0x73, 0x5f, 0x67, 0x65, 0x74, 0x00, 0x00, 0x16, 0x77, 0x61,
+    0x73, 0x69, 0x5f, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x5f, 0x70, 0x72, 0x65, 0x76,
+    0x69, 0x65, 0x77, 0x31, 0x08, 0x61, 0x72, 0x67, 0x73, 0x5f, 0x67, 0x65, 0x74, 0x00, 0x00, 0x16,
+    0x77, 0x61, 0x73, 0x69, 0x5f, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x5f, 0x70, 0x72,
+    0x65, 0x76, 0x69, 0x65, 0x77, 0x31, 0x11, 0x65, 0x6e, 0x76, 0x69, 0x72, 0x6f, 0x6e, 0x5f, 0x73,
+    0x69, 0x7a, 0x65, 0x73, 0x5f, 0x67, 0x65, 0x74, 0x00, 0x00, 0x16, 0x77, 0x61, 0x73, 0x69, 0x5f,
+    0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x5f, 0x70, 0x72, 0x65, 0x76, 0x69, 0x65, 0x77,
+    0x31, 0x0b, 0x65, 0x6e, 0x76, 0x69, 0x72, 0x6f, 0x6e, 0x5f, 0x67, 0x65, 0x74, 0x00, 0x00, 0x03,
+    0x05, 0x04, 0x00, 0x00, 0x00, 0x00, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x38, 0x05, 0x06, 0x6d,
+    0x65, 0x6d, 0x6f, 0x72, 0x79, 0x02, 0x00, 0x0a, 0x61, 0x72, 0x67, 0x73, 0x5f, 0x73, 0x69, 0x7a,
+    0x65, 0x73, 0x00, 0x04, 0x04, 0x61, 0x72, 0x67, 0x73, 0x00, 0x05, 0x0d, 0x65, 0x6e, 0x76, 0x69,
+    0x72, 0x6f, 0x6e, 0x5f, 0x73, 0x69, 0x7a, 0x65, 0x73, 0x00, 0x06, 0x07, 0x65, 0x6e, 0x76, 0x69,
+    0x72, 0x6f, 0x6e, 0x00, 0x07, 0x0a, 0x25, 0x04, 0x08, 0x00, 0x20, 0x00, 0x20, 0x01, 0x10, 0x00,
+    0x0b, 0x08, 0x00, 0x20, 0x00, 0x20, 0x01, 0x10, 0x01, 0x0b, 0x08, 0x00, 0x20, 0x00, 0x20, 0x01,
+    0x10, 0x02, 0x0b, 0x08, 0x00, 0x20, 0x00, 0x20, 0x01, 0x10, 0x03, 0x0b,
+];
+
+/// (module
+///   (import "wasi_snapshot_preview1" "fd_write"
+///     (func $fd_write (param i32 i32 i32 i32) (result i32)))
+///   (memory (export "memory") 1)
+///   (data (i32.const 0) "\10\00\00\00\06\00\00\00")
+///   (data (i32.const 16) "hello\n")
+///   (func (export "write") (param i32) (result i32)
+///     (call $fd_write (local.get 0) (i32.const 0) (i32.const 1) (i32.const 8))))
+const FD_WRITE: &[u8] = &[
+    0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x0e, 0x02, 0x60, 0x04, 0x7f, 0x7f, 0x7f,
+    0x7f, 0x01, 0x7f, 0x60, 0x01, 0x7f, 0x01, 0x7f, 0x02, 0x23, 0x01, 0x16, 0x77, 0x61, 0x73, 0x69,
+    0x5f, 0x73, 0x6e, 0x61, 0x70, 0x73, 0x68, 0x6f, 0x74, 0x5f, 0x70, 0x72, 0x65, 0x76, 0x69, 0x65,
+    0x77, 0x31, 0x08, 0x66, 0x64, 0x5f, 0x77, 0x72, 0x69, 0x74, 0x65, 0x00, 0x00, 0x03, 0x02, 0x01,
+    0x01, 0x05, 0x03, 0x01, 0x00, 0x01, 0x07, 0x12, 0x02, 0x06, 0x6d, 0x65, 0x6d, 0x6f, 0x72, 0x79,
+    0x02, 0x00, 0x05, 0x77, 0x72, 0x69, 0x74, 0x65, 0x00, 0x01, 0x0a, 0x0e, 0x01, 0x0c, 0x00, 0x20,
+    0x00, 0x41, 0x00, 0x41, 0x01, 0x41, 0x08, 0x10, 0x00, 0x0b, 0x0b, 0x1c, 0x01, 0x00, 0x41, 0x00,
+    0x0b, 0x16, 0x10, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x68, 0x65, 0x6c, 0x6c, 0x6f, 0x0a,
+];
+
+const ERRNO_BADF: u32 = 8;
+const ERRNO_FAULT: u32 = 21;
+
+fn runtime() -> Runtime {
+    Environment::new()
+        .expect("Unable to create environment")
+        .create_runtime(1024 * 60)
+        .expect("Unable to create runtime")
+}
+
+fn read_u32(rt: &Runtime, offset: u32) -> u32 {
+    let mut buf = [0; 4];
+    rt.read_into(offset, &mut buf)
+        .expect("Unable to read memory");
+    u32::from_le_bytes(buf)
+}
+
+#[test]
+fn test_wasi_config_args_env() {
+    let rt = runtime();
+    let mut module = rt
+        .parse_and_load_module(STRINGS)
+        .expect("Unable to load module");
+    let config = WasiConfig::new()
+        .args(vec!["prog", "--verbose"])
+        .env(vec![("HOME", "/home/guest")]);
+    module
+        .link_wasi_with_config(&config)
+        .expect("Unable to link wasi");
+    let call = |name: &str, a: u32, b: u32| {
+        module
+            .find_function::<(u32, u32), u32>(name)
+            .expect("Unable to find function")
+            .call(a, b)
+            .expect("Unable to call function")
+    };
+
+    assert_eq!(call("args_sizes", 0, 4), 0);
+    assert_eq!((read_u32(&rt, 0), read_u32(&rt, 4)), (2, 15));
+    assert_eq!(call("args", 16, 64), 0);
+    assert_eq!((read_u32(&rt, 16), read_u32(&rt, 20)), (64, 69));
+    let mut args = [0; 15];
+    rt.read_into(64, &mut args).expect("Unable to read memory");
+    assert_eq!(&args, b"prog\0--verbose\0");
+
+    assert_eq!(call("environ_sizes", 0, 4), 0);
+    assert_eq!((read_u32(&rt, 0), read_u32(&rt, 4)), (1, 17));
+    assert_eq!(call("environ", 16, 128), 0);
+    assert_eq!(read_u32(&rt, 16), 128);
+    let mut env = [0; 17];
+    rt.read_into(128, &mut env).expect("Unable to read memory");
+    assert_eq!(&env, b"HOME=/home/guest\0");
+
+    // the buffer would extend past the end of the memory
+    assert_eq!(call("args", 16, 64 * 1024 - 8), ERRNO_FAULT);
+}
+
+#[test]
+fn test_wasi_config_capture_output() {
+    let rt = runtime();
+    let mut module = rt
+        .parse_and_load_module(FD_WRITE)
+        .expect("Unable to load module");
+    let stdout = OutputBuffer::new();
+    let stderr = OutputBuffer::new();
+    let config = WasiConfig::new()
+        .stdout(stdout.clone())
+        .stderr(stderr.clone());
+    module
+        .link_wasi_with_config(&config)
+        .expect("Unable to link wasi");
+    let write = module
+        .find_function::<u32, u32>("write")
+        .expect("Unable to find function");
+
+    assert_eq!(write.call(1).expect("Unable to call function"), 0);
+    assert_eq!(read_u32(&rt, 8), 6);
+    assert_eq!(write.call(1).expect("Unable to call function"), 0);
+    assert_eq!(write.call(2).expect("Unable to call function"), 0);
+    assert_eq!(stdout.take(), b"hello\nhello\n");
+    assert_eq!(stderr.take(), b"hello\n");
+    assert!(stdout.take().is_empty());
+
+    // other file descriptors are still handled by wasm3
+    assert_eq!(
+        write.call(9999).expect("Unable to call function"),
+        ERRNO_BADF
+    );
+}
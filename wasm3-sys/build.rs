@@ -158,6 +158,14 @@ fn main() {
         cfg.define("d_m3HasWASI", None);
     }
 
+    // route wasm3's heap usage through shims that the wasm3 crate defines
+    if cfg!(feature = "custom-allocator") {
+        cfg.define("malloc", Some("m3rs_malloc"))
+            .define("calloc", Some("m3rs_calloc"))
+            .define("realloc", Some("m3rs_realloc"))
+            .define("free", Some("m3rs_free"));
+    }
+
     cfg.define(
         "d_m3Use32BitSlots",
         if cfg!(feature = "use-32bit-slots") {